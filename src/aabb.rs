@@ -0,0 +1,256 @@
+use nalgebra_glm::Vec3;
+
+// Componentes de la dirección menores a esto se tratan como rayo paralelo al slab
+const PARALLEL_EPSILON: f32 = 1e-8;
+
+// Caja alineada a los ejes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aabb {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+// Resultado del test de slabs
+#[derive(Debug, Clone, Copy)]
+pub struct AabbHit {
+    pub distance: f32,
+    pub axis: usize,    // Eje de la cara impactada
+    pub normal: Vec3,   // Normal hacia afuera de esa cara
+    pub inside: bool,   // El inicio del rayo (origen o t_min) estaba dentro: el impacto es la cara de salida
+}
+
+impl Aabb {
+    pub fn new(min: Vec3, max: Vec3) -> Self {
+        Aabb { min, max }
+    }
+
+    // Caja vacía: neutra para union
+    pub fn empty() -> Self {
+        Aabb {
+            min: Vec3::new(f32::INFINITY, f32::INFINITY, f32::INFINITY),
+            max: Vec3::new(f32::NEG_INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY),
+        }
+    }
+
+    pub fn union(&self, other: &Aabb) -> Aabb {
+        Aabb {
+            min: self.min.inf(&other.min),
+            max: self.max.sup(&other.max),
+        }
+    }
+
+    pub fn include_point(&self, point: &Vec3) -> Aabb {
+        Aabb {
+            min: self.min.inf(point),
+            max: self.max.sup(point),
+        }
+    }
+
+    pub fn contains(&self, point: &Vec3) -> bool {
+        (0..3).all(|a| point[a] >= self.min[a] && point[a] <= self.max[a])
+    }
+
+    pub fn centroid(&self) -> Vec3 {
+        (self.min + self.max) / 2.0
+    }
+
+    pub fn size(&self) -> Vec3 {
+        self.max - self.min
+    }
+
+    pub fn surface_area(&self) -> f32 {
+        let size = self.size();
+        2.0 * (size.x * size.y + size.y * size.z + size.z * size.x)
+    }
+
+    // Test de slabs: (tmin, eje de entrada, tmax, eje de salida), o None si el rayo no toca la caja
+    fn slabs(&self, ray_origin: &Vec3, ray_dir: &Vec3) -> Option<(f32, usize, f32, usize)> {
+        let mut tmin = f32::NEG_INFINITY;
+        let mut tmax = f32::INFINITY;
+        let mut axis = 0; // Eje del slab que determina tmin
+        let mut exit_axis = 0; // Eje del slab que determina tmax
+
+        for a in 0..3 {
+            // Rayo paralelo a este slab: 1/0 daría infinitos y 0*inf = NaN en las esquinas,
+            // así que solo se comprueba si el origen está dentro del slab
+            if ray_dir[a].abs() < PARALLEL_EPSILON {
+                if ray_origin[a] < self.min[a] || ray_origin[a] > self.max[a] {
+                    return None;
+                }
+                continue;
+            }
+
+            let mut t0 = (self.min[a] - ray_origin[a]) / ray_dir[a];
+            let mut t1 = (self.max[a] - ray_origin[a]) / ray_dir[a];
+            if t0 > t1 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+
+            if t0 > tmin {
+                tmin = t0;
+                axis = a;
+            }
+            if t1 < tmax {
+                tmax = t1;
+                exit_axis = a;
+            }
+
+            if tmin > tmax {
+                return None;
+            }
+        }
+
+        // Dirección nula: no hay rayo que intersectar
+        if !tmin.is_finite() {
+            return None;
+        }
+
+        // La caja queda completa detrás del origen
+        if tmax < 0.0 {
+            return None;
+        }
+
+        Some((tmin, axis, tmax, exit_axis))
+    }
+
+    // Si el origen está dentro de la caja devuelve la cara de salida
+    pub fn intersect_ray(&self, ray_origin: &Vec3, ray_dir: &Vec3) -> Option<AabbHit> {
+        self.intersect_ray_from(ray_origin, ray_dir, 0.0)
+    }
+
+    // Igual, con el rayo empezando en t_min: si la entrada queda antes de t_min se usa la salida,
+    // y si también la salida queda antes no hay impacto
+    pub fn intersect_ray_from(&self, ray_origin: &Vec3, ray_dir: &Vec3, t_min: f32) -> Option<AabbHit> {
+        let (tmin, axis, tmax, exit_axis) = self.slabs(ray_origin, ray_dir)?;
+        if tmax < t_min {
+            return None;
+        }
+
+        // Normal de la cara de entrada: opuesta al rayo en el eje del slab; la de salida, en su sentido
+        let mut normal = Vec3::new(0.0, 0.0, 0.0);
+        let hit = if tmin < t_min {
+            normal[exit_axis] = if ray_dir[exit_axis] > 0.0 { 1.0 } else { -1.0 };
+            AabbHit { distance: tmax, axis: exit_axis, normal, inside: true }
+        } else {
+            normal[axis] = if ray_dir[axis] > 0.0 { -1.0 } else { 1.0 };
+            AabbHit { distance: tmin, axis, normal, inside: false }
+        };
+
+        Some(hit)
+    }
+
+    // Caja invertida (habitación): solo se ven las caras interiores, así que el impacto es siempre
+    // la cara de salida, con la normal hacia adentro. Desde afuera se ve a través de la pared cercana.
+    pub fn intersect_ray_inverted(&self, ray_origin: &Vec3, ray_dir: &Vec3) -> Option<AabbHit> {
+        let (tmin, _, tmax, exit_axis) = self.slabs(ray_origin, ray_dir)?;
+        let mut normal = Vec3::new(0.0, 0.0, 0.0);
+        normal[exit_axis] = if ray_dir[exit_axis] > 0.0 { -1.0 } else { 1.0 };
+        Some(AabbHit { distance: tmax, axis: exit_axis, normal, inside: tmin < 0.0 })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    const RANDOM_RAYS: usize = 2000;
+
+    fn random_box(rng: &mut StdRng) -> Aabb {
+        let min = Vec3::new(rng.gen_range(-5.0..5.0), rng.gen_range(-5.0..5.0), rng.gen_range(-5.0..5.0));
+        let size = Vec3::new(rng.gen_range(0.1..3.0), rng.gen_range(0.1..3.0), rng.gen_range(0.1..3.0));
+        Aabb::new(min, min + size)
+    }
+
+    fn random_direction(rng: &mut StdRng) -> Vec3 {
+        loop {
+            let v = Vec3::new(rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0));
+            if v.magnitude() > 0.1 {
+                return v.normalize();
+            }
+        }
+    }
+
+    // El punto del impacto cae sobre el plano de la cara que indica la normal
+    fn assert_on_face(bounds: &Aabb, origin: &Vec3, dir: &Vec3, hit: &AabbHit) {
+        assert_eq!(hit.normal.magnitude(), 1.0);
+        assert_eq!(hit.normal[hit.axis].abs(), 1.0);
+        let point = origin + dir * hit.distance;
+        let face = if hit.normal[hit.axis] < 0.0 { bounds.min[hit.axis] } else { bounds.max[hit.axis] };
+        assert!((point[hit.axis] - face).abs() < 1e-3, "point {:?} is not on face {}", point, face);
+        let grown = Aabb::new(bounds.min - Vec3::repeat(1e-3), bounds.max + Vec3::repeat(1e-3));
+        assert!(grown.contains(&point));
+    }
+
+    #[test]
+    fn union_encloses_both_boxes_and_contains_includes_the_faces() {
+        let a = Aabb::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(1.0, 1.0, 1.0));
+        let b = Aabb::new(Vec3::new(2.0, -1.0, 0.5), Vec3::new(3.0, 0.5, 4.0));
+        let union = a.union(&b);
+        assert_eq!(union, Aabb::new(Vec3::new(0.0, -1.0, 0.0), Vec3::new(3.0, 1.0, 4.0)));
+        assert_eq!(Aabb::empty().union(&a), a);
+
+        assert!(a.contains(&Vec3::new(0.5, 0.5, 0.5)));
+        assert!(a.contains(&Vec3::new(1.0, 0.0, 1.0)));
+        assert!(!a.contains(&Vec3::new(1.01, 0.5, 0.5)));
+        assert!(union.contains(&b.centroid()) && !a.contains(&b.centroid()));
+        assert!(!Aabb::empty().contains(&Vec3::zeros()));
+        assert_eq!(a.surface_area(), 6.0);
+    }
+
+    #[test]
+    fn random_rays_from_outside_hit_the_entry_face() {
+        let mut rng = StdRng::seed_from_u64(109);
+        let mut hits = 0;
+        for _ in 0..RANDOM_RAYS {
+            let bounds = random_box(&mut rng);
+            // Origen fuera de la caja, apuntando a un punto de su interior
+            let target = bounds.min + bounds.size().component_mul(&Vec3::new(rng.gen(), rng.gen(), rng.gen()));
+            let origin = bounds.centroid() + random_direction(&mut rng) * 20.0;
+            let dir = (target - origin).normalize();
+
+            let hit = bounds.intersect_ray(&origin, &dir).expect("ray aimed at the box must hit it");
+            let (tmin, _, tmax, _) = bounds.slabs(&origin, &dir).unwrap();
+            assert!(tmin <= tmax);
+            assert!(hit.distance >= 0.0);
+            assert!(!hit.inside);
+            assert!(hit.normal.dot(&dir) <= 0.0, "entry normal must face the ray");
+            assert_on_face(&bounds, &origin, &dir, &hit);
+            hits += 1;
+        }
+        assert_eq!(hits, RANDOM_RAYS);
+    }
+
+    #[test]
+    fn random_rays_from_inside_hit_the_exit_face() {
+        let mut rng = StdRng::seed_from_u64(110);
+        for _ in 0..RANDOM_RAYS {
+            let bounds = random_box(&mut rng);
+            let origin = bounds.min + bounds.size().component_mul(&Vec3::new(
+                rng.gen_range(0.05..0.95),
+                rng.gen_range(0.05..0.95),
+                rng.gen_range(0.05..0.95),
+            ));
+            let dir = random_direction(&mut rng);
+
+            let hit = bounds.intersect_ray(&origin, &dir).expect("a ray from inside always exits");
+            assert!(hit.inside);
+            assert!(hit.distance >= 0.0);
+            assert!(hit.normal.dot(&dir) >= 0.0, "exit normal points along the ray");
+            assert_on_face(&bounds, &origin, &dir, &hit);
+        }
+    }
+
+    #[test]
+    fn random_rays_pointing_away_miss() {
+        let mut rng = StdRng::seed_from_u64(111);
+        for _ in 0..RANDOM_RAYS {
+            let bounds = random_box(&mut rng);
+            let outward = random_direction(&mut rng);
+            let origin = bounds.centroid() + outward * 20.0;
+            // Alejándose del centro de la caja desde fuera de su esfera envolvente
+            assert!(bounds.intersect_ray(&origin, &outward).is_none());
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use nalgebra_glm::Vec3;
+
+// Media y varianza de la luminancia de las muestras de un píxel (algoritmo de Welford, en una
+// sola pasada y sin guardar las muestras)
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PixelStats {
+    pub count: u32,
+    pub mean: f32,
+    m2: f32, // Suma de los cuadrados de las desviaciones respecto a la media
+}
+
+impl PixelStats {
+    pub fn new() -> Self {
+        PixelStats::default()
+    }
+
+    pub fn add(&mut self, value: f32) {
+        self.count += 1;
+        let delta = value - self.mean;
+        self.mean += delta / self.count as f32;
+        self.m2 += delta * (value - self.mean);
+    }
+
+    pub fn add_radiance(&mut self, radiance: &Vec3) {
+        self.add(0.2126 * radiance.x + 0.7152 * radiance.y + 0.0722 * radiance.z);
+    }
+
+    // Varianza muestral; con menos de dos muestras no hay estimación y se toma como infinita
+    pub fn variance(&self) -> f32 {
+        if self.count < 2 {
+            f32::INFINITY
+        } else {
+            self.m2 / (self.count - 1) as f32
+        }
+    }
+
+    // Error estándar de la media: cuánto puede cambiar todavía el píxel con más muestras
+    pub fn standard_error(&self) -> f32 {
+        (self.variance() / self.count as f32).sqrt()
+    }
+
+    // true cuando ya hay min_samples y el error estándar bajó de `threshold`
+    pub fn is_converged(&self, threshold: f32, min_samples: u32) -> bool {
+        self.count >= min_samples.max(2) && self.standard_error() < threshold
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn welford_matches_the_two_pass_mean_and_variance() {
+        let mut stats = PixelStats::new();
+        [2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0].iter().for_each(|v| stats.add(*v));
+        assert!((stats.mean - 5.0).abs() < 1e-6 && (stats.variance() - 32.0 / 7.0).abs() < 1e-5);
+        // Con un solo valor todavía no hay varianza, así que no converge
+        let mut single = PixelStats::new();
+        single.add(0.4);
+        assert!(!single.is_converged(1.0, 1));
+    }
+}
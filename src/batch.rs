@@ -0,0 +1,105 @@
+use std::path::PathBuf;
+use crate::camera::Camera;
+use crate::camera_path::CameraPath;
+use crate::scene::Scene;
+use crate::{render_to_image_with_progress, RenderSettings};
+
+// Cola de vistas para renderizar en lote (tomas de producto, turntables) con render_to_image
+pub struct RenderQueue {
+    pub cameras: Vec<Camera>,
+}
+
+impl Default for RenderQueue {
+    fn default() -> Self {
+        RenderQueue::new()
+    }
+}
+
+impl RenderQueue {
+    pub fn new() -> Self {
+        RenderQueue { cameras: Vec::new() }
+    }
+
+    pub fn add_camera(&mut self, camera: Camera) {
+        self.cameras.push(camera);
+    }
+
+    // `frames` vistas repartidas a intervalos iguales a lo largo del recorrido (incluye ambos extremos)
+    pub fn from_path(path: &CameraPath, frames: usize) -> Self {
+        let mut queue = RenderQueue::new();
+        for frame in 0..frames {
+            let t = if frames > 1 { path.duration() * frame as f32 / (frames - 1) as f32 } else { 0.0 };
+            let (eye, center, up) = path.sample(t);
+            queue.add_camera(Camera::new(eye, center, up));
+        }
+        queue
+    }
+
+    pub fn len(&self) -> usize {
+        self.cameras.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cameras.is_empty()
+    }
+
+    // Renderiza cada vista a `{prefix}_{NNN}.png` y devuelve las rutas escritas. `progress` recibe
+    // (índice de la vista, total, fracción completada de esa vista)
+    pub fn render_all(
+        &self,
+        scene: &Scene,
+        settings: &RenderSettings,
+        prefix: &str,
+        progress: &mut dyn FnMut(usize, usize, f32),
+    ) -> image::ImageResult<Vec<PathBuf>> {
+        let total = self.cameras.len();
+        let mut paths = Vec::with_capacity(total);
+        for (index, camera) in self.cameras.iter().enumerate() {
+            let image = render_to_image_with_progress(scene, camera, settings, &mut |fraction| progress(index, total, fraction));
+            let path = PathBuf::from(format!("{}_{:03}.png", prefix, index));
+            image.save(&path)?;
+            paths.push(path);
+        }
+        Ok(paths)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+    use crate::cube::Cube;
+    use crate::material::Material;
+    use nalgebra_glm::Vec3;
+
+    #[test]
+    fn queued_cameras_render_to_distinct_numbered_files() {
+        let mut scene = Scene::new();
+        let material = Material::new(Color::new(200, 120, 60), 10.0, [0.9, 0.1, 0.0, 0.0], 1.0, None, 0.0);
+        scene.add_cube(Cube { min: Vec3::new(-1.0, -1.0, -1.0), max: Vec3::new(1.0, 1.0, 1.0), material, inverted: false }, &[]);
+
+        let mut queue = RenderQueue::new();
+        for x in [-3.0, 0.0, 3.0] {
+            queue.add_camera(Camera::new(Vec3::new(x, 0.0, 6.0), Vec3::new(x * 0.5, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0)));
+        }
+        let settings = RenderSettings { width: 12, height: 9, ..RenderSettings::default() };
+        let prefix = std::env::temp_dir().join(format!("raytracing_batch_{}", std::process::id()));
+        let mut finished = Vec::new();
+        let paths = queue
+            .render_all(&scene, &settings, prefix.to_str().unwrap(), &mut |index, total, fraction| {
+                if fraction >= 1.0 {
+                    finished.push((index, total));
+                }
+            })
+            .unwrap();
+
+        let images: Vec<_> = paths.iter().map(|path| image::open(path).unwrap().to_rgb8()).collect();
+        for path in &paths {
+            std::fs::remove_file(path).ok();
+        }
+        assert_eq!(paths.len(), 3);
+        assert!(paths[2].to_str().unwrap().ends_with("_002.png"));
+        assert_eq!(finished, vec![(0, 3), (1, 3), (2, 3)]);
+        assert!(images[0] != images[1] && images[1] != images[2] && images[0] != images[2]);
+    }
+}
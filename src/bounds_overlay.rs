@@ -0,0 +1,82 @@
+use nalgebra_glm::Vec3;
+
+use crate::aabb::Aabb;
+use crate::camera::Camera;
+use crate::framebuffer::Framebuffer;
+use crate::scene::Scene;
+use crate::RenderSettings;
+
+// Profundidad máxima que se puede elegir (la raíz es el nivel 0)
+pub const MAX_OVERLAY_DEPTH: usize = 12;
+
+// Color de las aristas según el nivel; los niveles más profundos repiten la paleta
+const DEPTH_COLORS: [u32; 6] = [0xFF0000, 0xFF8000, 0xFFFF00, 0x00FF00, 0x00FFFF, 0xFF00FF];
+
+// Cajas de los nodos del BVH de la escena hasta `max_depth`, como (nivel, caja). Sin BVH (hay
+// geometría en movimiento) no hay nada que dibujar
+pub fn scene_bounds_hierarchy(scene: &Scene, max_depth: usize) -> Vec<(usize, Aabb)> {
+    scene.bvh().map_or_else(Vec::new, |bvh| bvh.node_boxes(max_depth))
+}
+
+// Proyecta las 12 aristas de cada caja con la cámara y las dibuja; las aristas con algún
+// extremo detrás de la cámara o muy fuera de pantalla se omiten (Bresenham no recorta)
+pub fn draw_bounds(framebuffer: &mut Framebuffer, camera: &Camera, settings: &RenderSettings, boxes: &[(usize, Aabb)]) {
+    let width = framebuffer.width as f32;
+    let height = framebuffer.height as f32;
+
+    for (depth, bounds) in boxes {
+        let corners: Vec<_> = (0..8)
+            .map(|i| {
+                let corner = Vec3::new(
+                    if i & 1 == 0 { bounds.min.x } else { bounds.max.x },
+                    if i & 2 == 0 { bounds.min.y } else { bounds.max.y },
+                    if i & 4 == 0 { bounds.min.z } else { bounds.max.z },
+                );
+                camera
+                    .project(&corner, width, height, settings.fov, settings.pixel_aspect)
+                    .filter(|(x, y)| x.abs() < width * 4.0 && y.abs() < height * 4.0)
+            })
+            .collect();
+        let color = DEPTH_COLORS[depth % DEPTH_COLORS.len()];
+
+        // Dos esquinas comparten arista si sus índices difieren en un solo bit
+        for a in 0..8 {
+            for bit in [1, 2, 4] {
+                let b = a | bit;
+                if a == b {
+                    continue;
+                }
+                if let (Some((x0, y0)), Some((x1, y1))) = (corners[a], corners[b]) {
+                    framebuffer.draw_line(x0 as i32, y0 as i32, x1 as i32, y1 as i32, color);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+    use crate::cube::Cube;
+    use crate::material::Material;
+
+    #[test]
+    fn two_separated_cubes_give_a_root_and_two_leaves() {
+        let material = Material::new(Color::new(200, 200, 200), 10.0, [0.9, 0.1, 0.0, 0.0], 1.0, None, 0.0);
+        let mut scene = Scene::new();
+        let left = Aabb::new(Vec3::new(-6.0, 0.0, 0.0), Vec3::new(-4.0, 1.0, 1.0));
+        let right = Aabb::new(Vec3::new(4.0, 0.0, -1.0), Vec3::new(5.0, 2.0, 1.0));
+        for bounds in [left, right] {
+            scene.add_cube(Cube { min: bounds.min, max: bounds.max, material: material.clone(), inverted: false }, &[]);
+        }
+
+        let root = Aabb::new(Vec3::new(-6.0, 0.0, -1.0), Vec3::new(5.0, 2.0, 1.0));
+        assert_eq!(scene_bounds_hierarchy(&scene, 0), vec![(0, root)]);
+        let boxes = scene_bounds_hierarchy(&scene, MAX_OVERLAY_DEPTH);
+        assert_eq!(boxes.len(), 3);
+        assert!(boxes.contains(&(0, root)));
+        assert!(boxes.contains(&(1, left)));
+        assert!(boxes.contains(&(1, right)));
+    }
+}
@@ -0,0 +1,291 @@
+use nalgebra_glm::Vec3;
+
+use crate::aabb::Aabb;
+
+// Con más primitivas que esto un nodo siempre se divide, aunque SAH prefiera dejarlo como hoja
+const MAX_LEAF_PRIMITIVES: usize = 8;
+// Costo de recorrer un nodo relativo al de intersectar una primitiva (heurística SAH)
+const TRAVERSAL_COST: f32 = 0.125;
+// Subárboles con al menos tantas primitivas se construyen en otro hilo (build_parallel)
+const PARALLEL_BUILD_THRESHOLD: usize = 4096;
+// Margen de las cajas al recorrer: los impactos de quads y SDF pueden caer apenas afuera por redondeo
+const TRAVERSAL_PADDING: f32 = 1e-4;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BvhNode {
+    Leaf { bounds: Aabb, primitives: Vec<usize> },
+    Interior { bounds: Aabb, left: Box<BvhNode>, right: Box<BvhNode> },
+}
+
+impl BvhNode {
+    pub fn bounds(&self) -> &Aabb {
+        match self {
+            BvhNode::Leaf { bounds, .. } | BvhNode::Interior { bounds, .. } => bounds,
+        }
+    }
+}
+
+// Jerarquía de cajas sobre una lista de primitivas, que se identifican por su índice en esa lista
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Bvh {
+    root: Option<BvhNode>,
+}
+
+impl Bvh {
+    // Construye el árbol con la heurística de área (SAH) a partir de la caja de cada primitiva
+    pub fn build(bounds: &[Aabb]) -> Bvh {
+        Bvh::build_with_threshold(bounds, usize::MAX)
+    }
+
+    // Igual que build, pero los subárboles grandes se construyen en paralelo. Cada nodo se divide
+    // igual que en serie, así que el árbol es idéntico
+    pub fn build_parallel(bounds: &[Aabb]) -> Bvh {
+        Bvh::build_with_threshold(bounds, PARALLEL_BUILD_THRESHOLD)
+    }
+
+    fn build_with_threshold(bounds: &[Aabb], parallel_threshold: usize) -> Bvh {
+        let primitives: Vec<usize> = (0..bounds.len()).collect();
+        Bvh { root: (!bounds.is_empty()).then(|| build_node(bounds, primitives, parallel_threshold)) }
+    }
+
+    pub fn root(&self) -> Option<&BvhNode> {
+        self.root.as_ref()
+    }
+
+    // Llama a `visit` con cada primitiva de las hojas cuya caja toca el rayo entre t_min y el límite,
+    // de cerca a lejos aproximadamente (primero el hijo izquierdo). `visit` recibe el límite (al
+    // principio t_max) y lo acorta al encontrar un impacto más cercano, así ya no se recorren los
+    // nodos cuya caja empieza más lejos; devuelve false para detenerse
+    pub fn visit(&self, ray_origin: &Vec3, ray_direction: &Vec3, t_min: f32, t_max: f32, mut visit: impl FnMut(usize, &mut f32) -> bool) {
+        let padding = Vec3::repeat(TRAVERSAL_PADDING);
+        let mut limit = t_max;
+        let mut stack: Vec<&BvhNode> = self.root.iter().collect();
+        while let Some(node) = stack.pop() {
+            let bounds = node.bounds();
+            let padded = Aabb::new(bounds.min - padding, bounds.max + padding);
+            match padded.intersect_ray_from(ray_origin, ray_direction, t_min) {
+                Some(hit) if hit.inside || hit.distance <= limit => {}
+                _ => continue,
+            }
+            match node {
+                BvhNode::Leaf { primitives, .. } => {
+                    for &primitive in primitives {
+                        if !visit(primitive, &mut limit) {
+                            return;
+                        }
+                    }
+                }
+                BvhNode::Interior { left, right, .. } => {
+                    stack.push(right);
+                    stack.push(left);
+                }
+            }
+        }
+    }
+
+    // Cajas de los nodos hasta `max_depth` (la raíz es el nivel 0), como (nivel, caja)
+    pub fn node_boxes(&self, max_depth: usize) -> Vec<(usize, Aabb)> {
+        let mut boxes = Vec::new();
+        let mut stack: Vec<(usize, &BvhNode)> = self.root.iter().map(|root| (0, root)).collect();
+        while let Some((depth, node)) = stack.pop() {
+            boxes.push((depth, *node.bounds()));
+            if let BvhNode::Interior { left, right, .. } = node {
+                if depth < max_depth {
+                    stack.push((depth + 1, right));
+                    stack.push((depth + 1, left));
+                }
+            }
+        }
+        boxes
+    }
+}
+
+fn build_node(bounds: &[Aabb], mut primitives: Vec<usize>, parallel_threshold: usize) -> BvhNode {
+    let node_bounds = primitives.iter().fold(Aabb::empty(), |node, &p| node.union(&bounds[p]));
+    let parallel = primitives.len() >= parallel_threshold;
+    match best_split(bounds, &mut primitives, &node_bounds) {
+        Some(split) => {
+            let right = primitives.split_off(split);
+            let (left, right) = if parallel {
+                // El hijo derecho en otro hilo mientras este construye el izquierdo
+                std::thread::scope(|s| {
+                    let right = s.spawn(|| build_node(bounds, right, parallel_threshold));
+                    let left = build_node(bounds, primitives, parallel_threshold);
+                    (left, right.join().expect("BVH build thread panicked"))
+                })
+            } else {
+                (build_node(bounds, primitives, parallel_threshold), build_node(bounds, right, parallel_threshold))
+            };
+            BvhNode::Interior { bounds: node_bounds, left: Box::new(left), right: Box::new(right) }
+        }
+        None => BvhNode::Leaf { bounds: node_bounds, primitives },
+    }
+}
+
+// SAH: en cada eje ordena las primitivas por centroide y evalúa cada corte con
+// TRAVERSAL_COST + (área izq * n izq + área der * n der) / área del nodo. Deja `primitives`
+// ordenadas por el mejor eje y devuelve dónde cortar, o None si una hoja cuesta menos (n)
+fn best_split(bounds: &[Aabb], primitives: &mut [usize], node_bounds: &Aabb) -> Option<usize> {
+    let count = primitives.len();
+    if count <= 1 {
+        return None;
+    }
+    let node_area = node_bounds.surface_area().max(f32::MIN_POSITIVE);
+    let middle = count / 2;
+    let mut best: Option<(f32, usize, usize)> = None; // (costo, eje, corte)
+
+    for axis in 0..3 {
+        sort_by_centroid(bounds, primitives, axis);
+        // Área de la caja de las primitivas [i, count) para cada i
+        let mut right_areas = vec![0.0; count];
+        let mut right = Aabb::empty();
+        for i in (1..count).rev() {
+            right = right.union(&bounds[primitives[i]]);
+            right_areas[i] = right.surface_area();
+        }
+
+        let mut left = Aabb::empty();
+        for split in 1..count {
+            left = left.union(&bounds[primitives[split - 1]]);
+            let cost = TRAVERSAL_COST
+                + (left.surface_area() * split as f32 + right_areas[split] * (count - split) as f32) / node_area;
+            // Con costos iguales (p. ej. cajas repetidas) gana el corte más balanceado
+            let better = match best {
+                None => true,
+                Some((best_cost, _, best_split)) => {
+                    cost < best_cost || (cost == best_cost && split.abs_diff(middle) < best_split.abs_diff(middle))
+                }
+            };
+            if better {
+                best = Some((cost, axis, split));
+            }
+        }
+    }
+
+    let (cost, axis, split) = best?;
+    if cost >= count as f32 && count <= MAX_LEAF_PRIMITIVES {
+        return None;
+    }
+    sort_by_centroid(bounds, primitives, axis);
+    Some(split)
+}
+
+// Orden estable y determinista: por centroide en el eje y, a igual centroide, por índice
+fn sort_by_centroid(bounds: &[Aabb], primitives: &mut [usize], axis: usize) {
+    primitives.sort_by(|&a, &b| {
+        bounds[a].centroid()[axis].total_cmp(&bounds[b].centroid()[axis]).then(a.cmp(&b))
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    fn random_boxes(rng: &mut StdRng, count: usize) -> Vec<Aabb> {
+        (0..count)
+            .map(|_| {
+                let min = Vec3::new(rng.gen_range(-20.0..20.0), rng.gen_range(-20.0..20.0), rng.gen_range(-20.0..20.0));
+                let size = Vec3::new(rng.gen_range(0.1..2.0), rng.gen_range(0.1..2.0), rng.gen_range(0.1..2.0));
+                Aabb::new(min, min + size)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn traversal_finds_every_box_the_ray_hits() {
+        let mut rng = StdRng::seed_from_u64(164);
+        let boxes = random_boxes(&mut rng, 300);
+        let bvh = Bvh::build(&boxes);
+
+        for _ in 0..500 {
+            let origin = Vec3::new(rng.gen_range(-30.0..30.0), rng.gen_range(-30.0..30.0), rng.gen_range(-30.0..30.0));
+            let direction = Vec3::new(rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0));
+            let expected: Vec<usize> =
+                (0..boxes.len()).filter(|&i| boxes[i].intersect_ray(&origin, &direction).is_some()).collect();
+            let mut visited = Vec::new();
+            bvh.visit(&origin, &direction, 0.0, f32::INFINITY, |i, _| {
+                visited.push(i);
+                true
+            });
+            visited.retain(|&i| boxes[i].intersect_ray(&origin, &direction).is_some());
+            visited.sort();
+            assert_eq!(visited, expected);
+        }
+    }
+
+    #[test]
+    fn parallel_build_matches_the_serial_tree_and_its_hits() {
+        let mut rng = StdRng::seed_from_u64(170);
+        let boxes = random_boxes(&mut rng, 2000);
+        let serial = Bvh::build(&boxes);
+        // Umbral bajo para que varios niveles se construyan en otros hilos
+        let parallel = Bvh::build_with_threshold(&boxes, 64);
+        assert_eq!(parallel, serial);
+        assert_eq!(Bvh::build_parallel(&boxes), serial);
+
+        let hits = |bvh: &Bvh, origin: &Vec3, direction: &Vec3| {
+            let mut hits = Vec::new();
+            bvh.visit(origin, direction, 0.0, f32::INFINITY, |i, _| {
+                if boxes[i].intersect_ray(origin, direction).is_some() {
+                    hits.push(i);
+                }
+                true
+            });
+            hits
+        };
+        for _ in 0..500 {
+            let origin = Vec3::new(rng.gen_range(-30.0..30.0), rng.gen_range(-30.0..30.0), rng.gen_range(-30.0..30.0));
+            let direction = Vec3::new(rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0));
+            assert_eq!(hits(&parallel, &origin, &direction), hits(&serial, &origin, &direction));
+        }
+    }
+
+    #[test]
+    fn sah_keeps_overlapping_boxes_together_and_splits_separated_ones() {
+        let unit = Aabb::new(Vec3::zeros(), Vec3::repeat(1.0));
+        let bvh = Bvh::build(&[unit, unit]);
+        assert!(matches!(bvh.root(), Some(BvhNode::Leaf { primitives, .. }) if primitives.len() == 2));
+
+        let far = Aabb::new(Vec3::new(10.0, 0.0, 0.0), Vec3::new(11.0, 1.0, 1.0));
+        let bvh = Bvh::build(&[far, unit]);
+        assert!(matches!(bvh.root(), Some(BvhNode::Interior { .. })));
+        assert_eq!(Bvh::build(&[]).root(), None);
+    }
+
+    #[test]
+    fn a_closer_hit_prunes_the_nodes_behind_it() {
+        // Fila de cajas a lo largo de +X: el rayo las atraviesa todas
+        let boxes: Vec<Aabb> = (0..64)
+            .map(|i| Aabb::new(Vec3::new(i as f32 * 2.0, 0.0, 0.0), Vec3::new(i as f32 * 2.0 + 1.0, 1.0, 1.0)))
+            .collect();
+        let bvh = Bvh::build(&boxes);
+        let (origin, direction) = (Vec3::new(-5.0, 0.5, 0.5), Vec3::new(1.0, 0.0, 0.0));
+
+        let mut visited = Vec::new();
+        bvh.visit(&origin, &direction, 0.0, f32::INFINITY, |i, _| {
+            visited.push(i);
+            true
+        });
+        assert_eq!(visited.len(), boxes.len());
+
+        // Acortando el límite en cada impacto, los nodos que empiezan detrás de la caja 0 se saltan
+        let mut visited = Vec::new();
+        bvh.visit(&origin, &direction, 0.0, f32::INFINITY, |i, limit| {
+            visited.push(i);
+            *limit = limit.min(boxes[i].intersect_ray(&origin, &direction).unwrap().distance);
+            true
+        });
+        assert_eq!(visited, vec![0]);
+
+        // Con un límite inicial se descarta lo que empieza después (las cajas 0 a 2 entran antes de 10)
+        let mut visited = Vec::new();
+        bvh.visit(&origin, &direction, 0.0, 10.0, |i, _| {
+            visited.push(i);
+            true
+        });
+        visited.sort();
+        assert_eq!(visited, vec![0, 1, 2]);
+    }
+}
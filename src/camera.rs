@@ -1,11 +1,109 @@
 use nalgebra_glm::{Vec3, rotate_vec3};
 use std::f32::consts::PI; 
+use std::fs;
+use std::io;
+use crate::aabb::Aabb;
+use crate::json::{json_f32, json_vec3, parse_f32, parse_vec3, split_top_level};
 
+// Vistas predefinidas de la escena
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Viewpoint {
+    Front,
+    Top,
+    Side,
+    Iso,
+}
+
+#[derive(Debug, Clone)]
 pub struct Camera {
     pub eye: Vec3,
     pub center: Vec3, 
     pub up: Vec3,
     pub has_changed: bool, 
+    pub min_distance: f32,          // zoom no acerca el ojo al centro más que esto (evita invertir la vista)
+    pub max_distance: Option<f32>,  // ... ni lo aleja más que esto
+    pub eye_velocity: Vec3,         // Desplazamiento por segundo del ojo durante el obturador (motion blur)
+    pub center_velocity: Vec3,      // ... y del punto al que mira
+}
+
+pub const DEFAULT_MIN_ZOOM_DISTANCE: f32 = 0.5;
+
+// Máximo |pitch| de orbit: lejos de los polos, donde la vista quedaría paralela a up
+pub const PITCH_LIMIT: f32 = PI / 2.0 - 0.1;
+
+// Lo necesario para reproducir un punto de vista (marcadores, archivo de escena, sidecar), sin el
+// estado interno de Camera. El fov no vive en Camera sino en RenderSettings, pero se guarda aquí
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CameraState {
+    pub eye: Vec3,
+    pub center: Vec3,
+    pub up: Vec3,
+    pub fov: f32,
+}
+
+impl CameraState {
+    pub fn to_json(&self) -> String {
+        format!(
+            "{{\n  \"eye\": {},\n  \"center\": {},\n  \"up\": {},\n  \"fov\": {}\n}}\n",
+            json_vec3(&self.eye),
+            json_vec3(&self.center),
+            json_vec3(&self.up),
+            json_f32(self.fov),
+        )
+    }
+
+    // Lee el formato de to_json; falta cualquier campo -> None
+    pub fn from_json(json: &str) -> Option<Self> {
+        let body = json.trim().strip_prefix('{')?.strip_suffix('}')?;
+        let (mut eye, mut center, mut up, mut fov) = (None, None, None, None);
+        for field in split_top_level(body) {
+            let (key, value) = field.split_once(':')?;
+            let value = value.trim();
+            match key.trim().trim_matches('"') {
+                "eye" => eye = Some(parse_vec3(value)?),
+                "center" => center = Some(parse_vec3(value)?),
+                "up" => up = Some(parse_vec3(value)?),
+                "fov" => fov = Some(parse_f32(value)?),
+                _ => {} // Campos desconocidos se ignoran
+            }
+        }
+        Some(CameraState { eye: eye?, center: center?, up: up?, fov: fov? })
+    }
+
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        fs::write(path, self.to_json())
+    }
+
+    pub fn load(path: &str) -> io::Result<Self> {
+        let json = fs::read_to_string(path)?;
+        CameraState::from_json(&json)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "Invalid camera state"))
+    }
+}
+
+// Diagonal de la caja de la escena para la que se ajustaron las velocidades (la casa, ~9 unidades)
+const REFERENCE_SCENE_DIAGONAL: f32 = 9.0;
+
+// Velocidades de navegación por segundo adaptadas al tamaño de la escena
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NavigationSpeeds {
+    pub scale: f32, // Diagonal de la escena relativa a la de referencia
+    pub orbit: f32, // Radianes por segundo: un ángulo no depende de la escala
+    pub zoom: f32,  // Unidades por segundo, proporcional a la diagonal de la escena
+    pub pan: f32,
+}
+
+impl NavigationSpeeds {
+    pub fn for_bounds(bounds: &Aabb) -> Self {
+        let diagonal = bounds.size().magnitude();
+        let scale = if diagonal.is_finite() && diagonal > 0.0 { diagonal / REFERENCE_SCENE_DIAGONAL } else { 1.0 };
+        NavigationSpeeds {
+            scale,
+            orbit: PI / 10.0,
+            zoom: 0.5 * scale,
+            pan: 1.0 * scale,
+        }
+    }
 }
 
 impl Camera {
@@ -15,9 +113,82 @@ impl Camera {
             center,
             up,
             has_changed: true,
+            min_distance: DEFAULT_MIN_ZOOM_DISTANCE,
+            max_distance: None,
+            eye_velocity: Vec3::new(0.0, 0.0, 0.0),
+            center_velocity: Vec3::new(0.0, 0.0, 0.0),
         }
     }
 
+    // Cámara en el instante `time` del obturador (segundos desde que se abre)
+    pub fn at_time(&self, time: f32) -> Camera {
+        let mut camera = self.clone();
+        camera.eye += self.eye_velocity * time;
+        camera.center += self.center_velocity * time;
+        camera
+    }
+
+    pub fn is_moving(&self) -> bool {
+        self.eye_velocity != Vec3::zeros() || self.center_velocity != Vec3::zeros()
+    }
+
+    // Cámara nueva en el punto de vista guardado (el fov se aplica aparte a RenderSettings)
+    pub fn from_state(state: &CameraState) -> Self {
+        Camera::new(state.eye, state.center, state.up)
+    }
+
+    pub fn to_state(&self, fov: f32) -> CameraState {
+        CameraState { eye: self.eye, center: self.center, up: self.up, fov }
+    }
+
+    // Como from_state, pero conserva los límites de zoom de esta cámara
+    pub fn apply_state(&mut self, state: &CameraState) {
+        self.look_at(state.eye, state.center, state.up);
+    }
+
+    pub fn look_at(&mut self, eye: Vec3, center: Vec3, up: Vec3) {
+        self.eye = eye;
+        self.center = center;
+        self.up = up;
+        self.has_changed = true;
+    }
+
+    // Calcula (eye, center, up) de una vista predefinida para que la caja quepa completa
+    pub fn viewpoint(view: Viewpoint, bounds_min: Vec3, bounds_max: Vec3) -> (Vec3, Vec3, Vec3) {
+        let center = (bounds_min + bounds_max) / 2.0;
+        let distance = (bounds_max - bounds_min).magnitude() * 1.2;
+        let up = Vec3::new(0.0, 1.0, 0.0);
+
+        match view {
+            Viewpoint::Front => (center + Vec3::new(0.0, 0.0, distance), center, up),
+            // Mirando hacia abajo el up no puede ser +Y (sería paralelo a la dirección de vista)
+            Viewpoint::Top => (center + Vec3::new(0.0, distance, 0.0), center, Vec3::new(0.0, 0.0, -1.0)),
+            Viewpoint::Side => (center + Vec3::new(distance, 0.0, 0.0), center, up),
+            Viewpoint::Iso => (center + Vec3::new(1.0, 1.0, 1.0).normalize() * distance, center, up),
+        }
+    }
+
+    pub fn set_viewpoint(&mut self, view: Viewpoint, bounds_min: Vec3, bounds_max: Vec3) {
+        let (eye, center, up) = Camera::viewpoint(view, bounds_min, bounds_max);
+        self.look_at(eye, center, up);
+    }
+
+    // Aleja el ojo sobre la dirección de vista actual hasta que toda la caja quepa en el cuadro
+    // (fov vertical), con un margen, mirando a su centro
+    pub fn frame_scene(&mut self, bbox: Aabb, fov: f32) {
+        let center = bbox.centroid();
+        let radius = (bbox.size().magnitude() / 2.0).max(0.001); // Esfera que envuelve la caja
+        let direction = self.eye - self.center;
+        let direction = if direction.magnitude() > 0.0 {
+            direction.normalize()
+        } else {
+            Vec3::new(0.0, 0.0, 1.0)
+        };
+        let margin = 1.1;
+        let distance = radius / (fov / 2.0).sin() * margin;
+        self.look_at(center + direction * distance, center, self.up);
+    }
+
     pub fn basis_change(&self, vector: &Vec3) -> Vec3 {
         let forward = (self.center - self.eye).normalize();
         let right = forward.cross(&self.up).normalize();
@@ -31,16 +202,70 @@ impl Camera {
         rotated.normalize()
     }
 
-    pub fn orbit(&mut self, delta_yaw: f32, delta_pitch: f32) {
+    // Inverso de basis_change para direcciones: de coordenadas del mundo a las de la cámara
+    // (+X derecha, +Y arriba, -Z hacia donde mira)
+    pub fn world_to_view(&self, vector: &Vec3) -> Vec3 {
+        let forward = (self.center - self.eye).normalize();
+        let right = forward.cross(&self.up).normalize();
+        let up = right.cross(&forward).normalize();
+        Vec3::new(vector.dot(&right), vector.dot(&up), -vector.dot(&forward))
+    }
+
+    // Rayo primario del píxel (x, y) en una imagen de width x height con campo de visión fov
+    pub fn get_ray(&self, x: f32, y: f32, width: f32, height: f32, fov: f32) -> (Vec3, Vec3) {
+        self.get_ray_with_pixel_aspect(x, y, width, height, fov, 1.0)
+    }
+
+    // Igual que get_ray para píxeles no cuadrados: pixel_aspect = ancho / alto de un píxel en pantalla
+    // (2.0 en un formato anamórfico que luego se estira al doble de ancho)
+    pub fn get_ray_with_pixel_aspect(&self, x: f32, y: f32, width: f32, height: f32, fov: f32, pixel_aspect: f32) -> (Vec3, Vec3) {
+        let aspect_ratio = width * pixel_aspect / height;
+        let perspective_scale = (fov / 2.0).tan();
+
+        let screen_x = (2.0 * x) / width - 1.0;
+        let screen_y = -(2.0 * y) / height + 1.0;
+        let screen_x = screen_x * aspect_ratio * perspective_scale;
+        let screen_y = screen_y * perspective_scale;
+
+        let ray_direction = Vec3::new(screen_x, screen_y, -1.0).normalize();
+        (self.eye, self.basis_change(&ray_direction))
+    }
+
+    // Ángulos de órbita actuales (radianes) del ojo alrededor del centro
+    pub fn yaw_pitch(&self) -> (f32, f32) {
         let radius_vector = self.eye - self.center;
-        let radius = radius_vector.magnitude(); 
-        let current_yaw = radius_vector.z.atan2(radius_vector.x);
-    
+        let yaw = radius_vector.z.atan2(radius_vector.x);
         let radius_xz = (radius_vector.x * radius_vector.x + radius_vector.z * radius_vector.z).sqrt();
-        let current_pitch = (-radius_vector.y).atan2(radius_xz);
+        let pitch = (-radius_vector.y).atan2(radius_xz);
+        (yaw, pitch)
+    }
+
+    // Inverso de get_ray: píxel (x, y) donde se ve el punto, o None si está detrás de la cámara
+    pub fn project(&self, point: &Vec3, width: f32, height: f32, fov: f32, pixel_aspect: f32) -> Option<(f32, f32)> {
+        let forward = (self.center - self.eye).normalize();
+        let right = forward.cross(&self.up).normalize();
+        let up = right.cross(&forward).normalize();
+
+        let local = point - self.eye;
+        let depth = local.dot(&forward);
+        if depth <= 0.0 {
+            return None;
+        }
+
+        let aspect_ratio = width * pixel_aspect / height;
+        let perspective_scale = (fov / 2.0).tan();
+        let screen_x = local.dot(&right) / depth / (aspect_ratio * perspective_scale);
+        let screen_y = local.dot(&up) / depth / perspective_scale;
+        Some(((screen_x + 1.0) * width / 2.0, (1.0 - screen_y) * height / 2.0))
+    }
+
+    pub fn orbit(&mut self, delta_yaw: f32, delta_pitch: f32) {
+        let radius = (self.eye - self.center).magnitude(); 
+        let (current_yaw, current_pitch) = self.yaw_pitch();
     
-        let new_yaw = (current_yaw + delta_yaw) % (2.0 * PI);
-        let new_pitch = (current_pitch + delta_pitch).clamp(-PI / 2.0 + 0.1, PI / 2.0 - 0.1);
+        // El yaw se lleva a [-π, π) como el de yaw_pitch (atan2), así no crece sin límite
+        let new_yaw = (current_yaw + delta_yaw + PI).rem_euclid(2.0 * PI) - PI;
+        let new_pitch = (current_pitch + delta_pitch).clamp(-PITCH_LIMIT, PITCH_LIMIT);
     
         let new_eye = self.center + Vec3::new(
             radius * new_yaw.cos() * new_pitch.cos(),
@@ -49,13 +274,34 @@ impl Camera {
         );
     
         self.eye = new_eye;
+        // La órbita gira alrededor de +Y: un up de otra vista (la superior usa -Z) la haría rodar
+        self.up = Vec3::new(0.0, 1.0, 0.0);
         self.has_changed = true;
     }
     
+    // Acerca (delta > 0) o aleja el ojo sobre la línea de vista, manteniendo la distancia al
+    // centro dentro de [min_distance, max_distance]
     pub fn zoom(&mut self, delta: f32) {
-    let direction = (self.center - self.eye).normalize();
-    self.eye += direction * delta;
-    self.has_changed = true;
+        let offset = self.eye - self.center;
+        let distance = offset.magnitude();
+        if distance == 0.0 {
+            return;
+        }
+        let max_distance = self.max_distance.unwrap_or(f32::INFINITY).max(self.min_distance);
+        let new_distance = (distance - delta).clamp(self.min_distance, max_distance);
+        self.eye = self.center + offset / distance * new_distance;
+        self.has_changed = true;
+    }
+
+    // Desplaza ojo y centro juntos sobre el plano de la cámara (derecha / arriba), sin girar la vista
+    pub fn pan(&mut self, delta_right: f32, delta_up: f32) {
+        let forward = (self.center - self.eye).normalize();
+        let right = forward.cross(&self.up).normalize();
+        let up = right.cross(&forward).normalize();
+        let offset = right * delta_right + up * delta_up;
+        self.eye += offset;
+        self.center += offset;
+        self.has_changed = true;
     }
 
     pub fn move_center(&mut self, direction: Vec3) {
@@ -84,5 +330,192 @@ impl Camera {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ray_through_the_screen_center_follows_the_forward_axis() {
+        let camera = Camera::new(Vec3::new(3.0, 4.0, -2.0), Vec3::new(-1.0, 0.5, 6.0), Vec3::new(0.0, 1.0, 0.0));
+        let forward = (camera.center - camera.eye).normalize();
+        for (width, height) in [(800.0, 600.0), (64.0, 64.0), (300.0, 900.0)] {
+            let (origin, direction) = camera.get_ray(width / 2.0, height / 2.0, width, height, PI / 3.0);
+            assert_eq!(origin, camera.eye);
+            assert!((direction.normalize() - forward).magnitude() < 1e-5);
+        }
+    }
 
+    #[test]
+    fn top_viewpoint_is_above_the_scene_center_looking_down() {
+        let (min, max) = (Vec3::new(-4.0, 0.0, -2.0), Vec3::new(6.0, 3.0, 8.0));
+        let mut camera = Camera::new(Vec3::new(0.0, 0.0, 5.0), Vec3::zeros(), Vec3::new(0.0, 1.0, 0.0));
+        camera.set_viewpoint(Viewpoint::Top, min, max);
 
+        let scene_center = (min + max) / 2.0;
+        assert!((camera.center - scene_center).magnitude() < 1e-5);
+        assert!(camera.eye.y > max.y);
+        assert!((camera.eye.x - scene_center.x).abs() < 1e-5 && (camera.eye.z - scene_center.z).abs() < 1e-5);
+        let forward = (camera.center - camera.eye).normalize();
+        assert!((forward - Vec3::new(0.0, -1.0, 0.0)).magnitude() < 1e-5);
+        // El up no es paralelo a la vista: la base de la cámara queda bien definida
+        assert!(forward.cross(&camera.up).magnitude() > 0.9);
+    }
+
+    #[test]
+    fn two_to_one_pixel_aspect_doubles_the_horizontal_spread() {
+        let camera = Camera::new(Vec3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+        let fov = std::f32::consts::PI / 3.0;
+        // Pendientes x/-z e y/-z del rayo por el píxel (x, y)
+        let slopes = |x: f32, y: f32, pixel_aspect: f32| {
+            let (_, dir) = camera.get_ray_with_pixel_aspect(x, y, 100.0, 50.0, fov, pixel_aspect);
+            (dir.x / -dir.z, dir.y / -dir.z)
+        };
+
+        let (square_x, square_y) = slopes(90.0, 10.0, 1.0);
+        let (wide_x, wide_y) = slopes(90.0, 10.0, 2.0);
+        assert!((wide_x - 2.0 * square_x).abs() < 1e-5);
+        assert!((wide_y - square_y).abs() < 1e-5);
+        assert_eq!(camera.get_ray(90.0, 10.0, 100.0, 50.0, fov), camera.get_ray_with_pixel_aspect(90.0, 10.0, 100.0, 50.0, fov, 1.0));
+    }
+
+    #[test]
+    fn framed_scene_has_every_corner_on_screen() {
+        let bbox = Aabb::new(Vec3::new(-3.0, 0.0, -8.0), Vec3::new(5.0, 4.0, 2.0));
+        let fov = std::f32::consts::PI / 3.0;
+        let mut camera = Camera::new(Vec3::new(30.0, 10.0, 2.0), Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+        camera.frame_scene(bbox, fov);
+        assert!((camera.center - bbox.centroid()).magnitude() < 1e-4);
+
+        let (width, height) = (800.0, 600.0);
+        for corner in 0..8 {
+            let point = Vec3::new(
+                if corner & 1 == 0 { bbox.min.x } else { bbox.max.x },
+                if corner & 2 == 0 { bbox.min.y } else { bbox.max.y },
+                if corner & 4 == 0 { bbox.min.z } else { bbox.max.z },
+            );
+            let (x, y) = camera.project(&point, width, height, fov, 1.0).expect("corner behind the camera");
+            assert!((0.0..=width).contains(&x) && (0.0..=height).contains(&y), "corner {:?} at ({}, {})", point, x, y);
+        }
+    }
+
+    #[test]
+    fn zooming_past_the_limits_stops_at_them_on_the_same_side() {
+        let mut camera = Camera::new(Vec3::new(0.0, 3.0, 4.0), Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+        camera.min_distance = 1.0;
+        camera.max_distance = Some(20.0);
+
+        camera.zoom(50.0);
+        assert!((camera.eye - Vec3::new(0.0, 0.6, 0.8)).magnitude() < 1e-5, "{:?}", camera.eye);
+        camera.zoom(0.5);
+        assert!(((camera.eye - camera.center).magnitude() - 1.0).abs() < 1e-5);
+
+        camera.zoom(-100.0);
+        assert!((camera.eye - Vec3::new(0.0, 12.0, 16.0)).magnitude() < 1e-4, "{:?}", camera.eye);
+    }
+
+    #[test]
+    fn world_normals_map_to_view_space_for_a_known_camera() {
+        let plus_z = Vec3::new(0.0, 0.0, 1.0);
+        // Mirando hacia -Z, el espacio de vista coincide con el del mundo
+        let front = Camera::new(Vec3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+        assert!((front.world_to_view(&plus_z) - plus_z).magnitude() < 1e-6);
+
+        // Mirando hacia -X desde +X, la derecha de la cámara es -Z: +Z queda a la izquierda
+        let side = Camera::new(Vec3::new(5.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+        assert!((side.world_to_view(&plus_z) - Vec3::new(-1.0, 0.0, 0.0)).magnitude() < 1e-6);
+        assert!((side.world_to_view(&Vec3::new(1.0, 0.0, 0.0)) - plus_z).magnitude() < 1e-6);
+    }
+
+    #[test]
+    fn navigation_speeds_scale_with_the_scene_diagonal() {
+        let small = Aabb::new(Vec3::new(-1.0, 0.0, -2.0), Vec3::new(1.0, 1.0, 0.0));
+        let large = Aabb::new(small.min * 10.0, small.max * 10.0);
+        let small_speeds = NavigationSpeeds::for_bounds(&small);
+        let large_speeds = NavigationSpeeds::for_bounds(&large);
+
+        assert!((small_speeds.scale - 3.0 / REFERENCE_SCENE_DIAGONAL).abs() < 1e-5);
+        assert!((large_speeds.zoom - 10.0 * small_speeds.zoom).abs() < 1e-4);
+        assert!((large_speeds.pan - 10.0 * small_speeds.pan).abs() < 1e-4);
+        // Los giros son ángulos: no cambian con la escala
+        assert_eq!(large_speeds.orbit, small_speeds.orbit);
+        // Escena vacía: velocidades de referencia
+        assert_eq!(NavigationSpeeds::for_bounds(&Aabb::empty()).scale, 1.0);
+    }
+
+    #[test]
+    fn light_marker_projects_to_the_expected_pixel() {
+        let camera = Camera::new(Vec3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+        let fov = std::f32::consts::PI / 2.0;
+        let light_position = Vec3::new(1.0, 1.0, 0.0);
+
+        // tan(45°) = 1: a 5 unidades de profundidad, 1 unidad es 0.2 de media pantalla
+        let (x, y) = camera.project(&light_position, 100.0, 100.0, fov, 1.0).unwrap();
+        assert!((x - 60.0).abs() < 1e-3 && (y - 40.0).abs() < 1e-3, "({}, {})", x, y);
+
+        // El rayo de ese píxel pasa por la luz
+        let (origin, direction) = camera.get_ray(x, y, 100.0, 100.0, fov);
+        assert!((direction - (light_position - origin).normalize()).magnitude() < 1e-5);
+        // Una luz detrás de la cámara no tiene marcador
+        assert!(camera.project(&Vec3::new(0.0, 2.0, 8.0), 100.0, 100.0, fov, 1.0).is_none());
+    }
+
+    #[test]
+    fn orbiting_keeps_the_radius_and_the_pitch_within_limits() {
+        let center = Vec3::new(1.0, 0.5, -2.0);
+        let mut camera = Camera::new(center + Vec3::new(3.0, 2.0, 4.0), center, Vec3::new(0.0, 1.0, 0.0));
+        let radius = (camera.eye - center).magnitude();
+
+        let mut previous_yaw = camera.yaw_pitch().0;
+        for step in 0..200 {
+            // Vueltas completas en yaw mientras el pitch empuja más allá del límite en ambos sentidos
+            let delta_pitch = if step < 100 { 0.07 } else { -0.07 };
+            camera.orbit(0.2, delta_pitch);
+            let (yaw, pitch) = camera.yaw_pitch();
+            assert!(((camera.eye - center).magnitude() - radius).abs() < 1e-3, "step {}", step);
+            assert!(pitch.abs() <= PITCH_LIMIT + 1e-4, "step {}: pitch {}", step, pitch);
+            // El yaw avanza 0.2 por paso, salvo al dar la vuelta de π a -π
+            let advance = (yaw - previous_yaw).rem_euclid(2.0 * PI);
+            assert!((advance - 0.2).abs() < 1e-3, "step {}: yaw {} -> {}", step, previous_yaw, yaw);
+            previous_yaw = yaw;
+        }
+    }
+
+    #[test]
+    fn camera_round_trips_through_state_and_json() {
+        let mut camera = Camera::new(Vec3::new(1.5, 2.25, -4.0), Vec3::new(0.1, 0.5, 0.3), Vec3::new(0.0, 1.0, 0.0));
+        camera.min_distance = 2.0;
+        let state = camera.to_state(0.9);
+        let read = CameraState::from_json(&state.to_json()).expect("camera state should parse");
+        assert_eq!(read, state);
+
+        let restored = Camera::from_state(&read);
+        assert_eq!((restored.eye, restored.center, restored.up), (camera.eye, camera.center, camera.up));
+
+        // apply_state mueve la vista pero conserva los límites de zoom propios
+        let mut other = Camera::new(Vec3::new(0.0, 0.0, 9.0), Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+        other.apply_state(&read);
+        assert_eq!((other.eye, other.center, other.up, other.min_distance), (camera.eye, camera.center, camera.up, DEFAULT_MIN_ZOOM_DISTANCE));
+    }
+
+    #[test]
+    fn camera_state_with_missing_or_malformed_fields_is_rejected() {
+        let json = Camera::new(Vec3::new(0.0, 1.0, 5.0), Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0)).to_state(1.0).to_json();
+        assert!(CameraState::from_json(&json.replace("\"fov\": 1", "\"fov\": null")).is_none());
+        assert!(CameraState::from_json(&json.replace("\"up\"", "\"upward\"")).is_none());
+        assert!(CameraState::from_json(&json.replace("[0, 1, 5]", "[0, 1]")).is_none());
+    }
+
+    #[test]
+    fn moving_camera_is_placed_along_its_velocity() {
+        let mut camera = Camera::new(Vec3::new(0.0, 0.0, 5.0), Vec3::zeros(), Vec3::new(0.0, 1.0, 0.0));
+        assert!(!camera.is_moving());
+        camera.eye_velocity = Vec3::new(2.0, 0.0, 0.0);
+        camera.center_velocity = Vec3::new(1.0, 0.0, 0.0);
+        assert!(camera.is_moving());
+
+        let later = camera.at_time(0.5);
+        assert_eq!(later.eye, Vec3::new(1.0, 0.0, 5.0));
+        assert_eq!(later.center, Vec3::new(0.5, 0.0, 0.0));
+        assert_eq!(camera.at_time(0.0).eye, camera.eye);
+    }
+}
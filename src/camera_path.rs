@@ -0,0 +1,114 @@
+use nalgebra_glm::Vec3;
+
+// Posición de la cámara en un instante del recorrido
+#[derive(Debug, Clone, Copy)]
+pub struct Keyframe {
+    pub time: f32,
+    pub eye: Vec3,
+    pub center: Vec3,
+    pub up: Vec3,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Interpolation {
+    Linear,
+    CatmullRom,
+}
+
+// Recorrido de cámara definido por keyframes ordenados por tiempo
+pub struct CameraPath {
+    keyframes: Vec<Keyframe>,
+    pub interpolation: Interpolation,
+}
+
+impl CameraPath {
+    pub fn new(interpolation: Interpolation) -> Self {
+        CameraPath {
+            keyframes: Vec::new(),
+            interpolation,
+        }
+    }
+
+    pub fn add_keyframe(&mut self, time: f32, eye: Vec3, center: Vec3, up: Vec3) {
+        // Mantener los keyframes ordenados para poder buscar el tramo en sample
+        let index = self.keyframes.partition_point(|k| k.time <= time);
+        self.keyframes.insert(index, Keyframe { time, eye, center, up });
+    }
+
+    pub fn duration(&self) -> f32 {
+        self.keyframes.last().map_or(0.0, |k| k.time)
+    }
+
+    // Devuelve (eye, center, up) en el tiempo t; fuera del rango se sujeta al primer/último keyframe
+    pub fn sample(&self, t: f32) -> (Vec3, Vec3, Vec3) {
+        assert!(!self.keyframes.is_empty(), "CameraPath sin keyframes");
+
+        let first = self.keyframes[0];
+        let last = self.keyframes[self.keyframes.len() - 1];
+        if t <= first.time {
+            return (first.eye, first.center, first.up);
+        }
+        if t >= last.time {
+            return (last.eye, last.center, last.up);
+        }
+
+        // Tramo [k1, k2] que contiene a t, con vecinos k0 y k3 para Catmull-Rom
+        let next = self.keyframes.partition_point(|k| k.time <= t);
+        let k0 = self.keyframes[next.saturating_sub(2)];
+        let k1 = self.keyframes[next - 1];
+        let k2 = self.keyframes[next];
+        let k3 = self.keyframes[(next + 1).min(self.keyframes.len() - 1)];
+
+        let s = (t - k1.time) / (k2.time - k1.time);
+
+        match self.interpolation {
+            Interpolation::Linear => (
+                k1.eye.lerp(&k2.eye, s),
+                k1.center.lerp(&k2.center, s),
+                k1.up.lerp(&k2.up, s).normalize(),
+            ),
+            Interpolation::CatmullRom => (
+                catmull_rom(&k0.eye, &k1.eye, &k2.eye, &k3.eye, s),
+                catmull_rom(&k0.center, &k1.center, &k2.center, &k3.center, s),
+                k1.up.lerp(&k2.up, s).normalize(),
+            ),
+        }
+    }
+}
+
+fn catmull_rom(p0: &Vec3, p1: &Vec3, p2: &Vec3, p3: &Vec3, t: f32) -> Vec3 {
+    let t2 = t * t;
+    let t3 = t2 * t;
+    0.5 * ((2.0 * p1)
+        + (p2 - p0) * t
+        + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * t2
+        + (3.0 * p1 - p0 - 3.0 * p2 + p3) * t3)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sampling_at_a_keyframe_time_returns_that_keyframe() {
+        let up = Vec3::new(0.0, 1.0, 0.0);
+        let keyframes = [
+            (0.0, Vec3::new(0.0, 2.0, 10.0), Vec3::new(0.0, 0.0, 0.0)),
+            (1.5, Vec3::new(7.0, 3.0, 4.0), Vec3::new(1.0, 0.5, 0.0)),
+            (4.0, Vec3::new(-3.0, 6.0, -8.0), Vec3::new(0.0, 1.0, -2.0)),
+            (5.0, Vec3::new(-9.0, 1.0, 0.0), Vec3::new(-1.0, 0.0, 0.0)),
+        ];
+        for interpolation in [Interpolation::Linear, Interpolation::CatmullRom] {
+            let mut path = CameraPath::new(interpolation);
+            for (time, eye, center) in keyframes {
+                path.add_keyframe(time, eye, center, up);
+            }
+            for (time, eye, center) in keyframes {
+                let (sampled_eye, sampled_center, sampled_up) = path.sample(time);
+                assert!((sampled_eye - eye).magnitude() < 1e-5, "{:?} at {}", interpolation, time);
+                assert!((sampled_center - center).magnitude() < 1e-5);
+                assert!((sampled_up - up).magnitude() < 1e-5);
+            }
+        }
+    }
+}
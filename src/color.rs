@@ -1,3 +1,4 @@
+use nalgebra_glm::Vec3;
 use std::fmt;
 use std::ops::Mul;
 use std::ops::Add;
@@ -19,10 +20,26 @@ impl Color {
         let b = (hex & 0xFF) as u8;
         Color { r, g, b }
     }
+    // Componentes en 0..1 (valores HDR mayores a 1 se recortan)
+    pub fn from_rgb_f32(r: f32, g: f32, b: f32) -> Self {
+        Color {
+            r: (r * 255.0).clamp(0.0, 255.0) as u8,
+            g: (g * 255.0).clamp(0.0, 255.0) as u8,
+            b: (b * 255.0).clamp(0.0, 255.0) as u8,
+        }
+    }
+    // Radiancia (1.0 = 255) a 8 bits: recorta y redondea al canal más cercano
+    pub fn from_radiance(radiance: &Vec3) -> Self {
+        let channel = |c: f32| (c * 255.0).round().clamp(0.0, 255.0) as u8;
+        Color::new(channel(radiance.x), channel(radiance.y), channel(radiance.z))
+    }
+    pub fn to_radiance(&self) -> Vec3 {
+        Vec3::new(self.r as f32, self.g as f32, self.b as f32) / 255.0
+    }
     pub const fn black() -> Self {
         Color { r: 0, g: 0, b: 0 }
     }
-    pub const fn to_hex(&self) -> u32 {
+    pub const fn to_hex(self) -> u32 {
         ((self.r as u32) << 16) | ((self.g as u32) << 8) | (self.b as u32)
     }
     pub fn r(&self) -> u8 {
@@ -36,6 +53,21 @@ impl Color {
     pub fn b(&self) -> u8 {
         self.b
     }
+
+    // Interpolación lineal hacia `other` (t = 0 -> self, t = 1 -> other), redondeando al final
+    pub fn lerp(self, other: Color, t: f32) -> Color {
+        let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round().clamp(0.0, 255.0) as u8;
+        Color {
+            r: mix(self.r, other.r),
+            g: mix(self.g, other.g),
+            b: mix(self.b, other.b),
+        }
+    }
+
+    // Luminancia relativa (Rec. 709) en el rango 0..1
+    pub fn luminance(&self) -> f32 {
+        (0.2126 * self.r as f32 + 0.7152 * self.g as f32 + 0.0722 * self.b as f32) / 255.0
+    }
 }
 
 impl Add for Color {
@@ -68,3 +100,25 @@ impl fmt::Display for Color {
         write!(f, "Color(r: {}, g: {}, b: {})", self.r, self.g, self.b)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn radiance_sums_small_fractions_without_truncating_each_step() {
+        let dim = Color::new(3, 3, 3);
+        let radiance = (0..10).fold(Vec3::zeros(), |sum, _| sum + dim.to_radiance() * 0.5);
+        let truncated = (0..10).fold(Color::black(), |sum, _| sum + dim * 0.5);
+        assert_eq!(Color::from_radiance(&radiance).r(), 15);
+        assert_eq!(truncated.r(), 10);
+    }
+
+    #[test]
+    fn radiance_round_trips_every_channel_value() {
+        for value in 0..=255u8 {
+            let color = Color::new(value, value, value);
+            assert_eq!(Color::from_radiance(&color.to_radiance()).r(), value);
+        }
+    }
+}
@@ -1,5 +1,6 @@
 use nalgebra_glm::Vec3;
 use crate::Material;
+use crate::aabb::Aabb;
 use crate::ray_intersect::{RayIntersect, Intersect};
 
 
@@ -7,114 +8,179 @@ pub struct Cube {
     pub min: Vec3, // Esquina inferior 
     pub max: Vec3, // Esquina superior
     pub material: Material, // Material del cubo 
+    pub inverted: bool, // Habitación: se ven las caras interiores, con normales hacia adentro
 }
 
 impl Cube {
-    fn calculate_normal(&self, hit_point: Vec3) -> Vec3 {
-        if (hit_point.x - self.min.x).abs() < 1e-4 {
-            return Vec3::new(-1.0, 0.0, 0.0);
-        }
-        if (hit_point.x - self.max.x).abs() < 1e-4 {
-            return Vec3::new(1.0, 0.0, 0.0);
-        }
-        if (hit_point.y - self.min.y).abs() < 1e-4 {
-            return Vec3::new(0.0, -1.0, 0.0);
-        }
-        if (hit_point.y - self.max.y).abs() < 1e-4 {
-            return Vec3::new(0.0, 1.0, 0.0);
-        }
-        if (hit_point.z - self.min.z).abs() < 1e-4 {
-            return Vec3::new(0.0, 0.0, -1.0);
-        }
-        Vec3::new(0.0, 0.0, 1.0)
+    pub fn bounds(&self) -> Aabb {
+        Aabb::new(self.min, self.max)
     }
 
-    fn get_uv(&self, point: Vec3) -> (f32, f32) {
-        if (point.y - self.max.y).abs() < 1e-4 {
-            let u = (point.x - self.min.x) / (self.max.x - self.min.x);
-            let v = (point.z - self.min.z) / (self.max.z - self.min.z);
-            (u, v)
-        } else if (point.y - self.min.y).abs() < 1e-4 {
-            let u = (point.x - self.min.x) / (self.max.x - self.min.x);
-            let v = (point.z - self.min.z) / (self.max.z - self.min.z);
-            (u, v)
-        } else if (point.x - self.min.x).abs() < 1e-4 {
-            let u = (point.z - self.min.z) / (self.max.z - self.min.z);
-            let v = (point.y - self.min.y) / (self.max.y - self.min.y);
-            (u, v)
-        } else if (point.x - self.max.x).abs() < 1e-4 {
-            let u = (point.z - self.min.z) / (self.max.z - self.min.z);
-            let v = (point.y - self.min.y) / (self.max.y - self.min.y);
-            (u, v)
-        } else if (point.z - self.min.z).abs() < 1e-4 {
-            let u = (point.x - self.min.x) / (self.max.x - self.min.x);
-            let v = (point.y - self.min.y) / (self.max.y - self.min.y);
-            (u, v)
-        } else {
-            let u = (point.x - self.min.x) / (self.max.x - self.min.x);
-            let v = (point.y - self.min.y) / (self.max.y - self.min.y);
-            (u, v)
+    fn get_uv(&self, point: Vec3, axis: usize) -> (f32, f32) {
+        let size = self.max - self.min;
+        let local = point - self.min;
+        match axis {
+            // Caras laterales en X: u a lo largo de Z, v a lo largo de Y
+            0 => (local.z / size.z, local.y / size.y),
+            // Caras superior/inferior: u a lo largo de X, v a lo largo de Z
+            1 => (local.x / size.x, local.z / size.z),
+            // Caras frontal/trasera en Z: u a lo largo de X, v a lo largo de Y
+            _ => (local.x / size.x, local.y / size.y),
         }
-    }       
+    }
 
     pub fn position(&self) -> Vec3 {
-        (self.min + self.max) / 2.0
+        self.bounds().centroid()
+    }
+
+    pub fn surface_area(&self) -> f32 {
+        self.bounds().surface_area()
     }
     
 }
 
 impl RayIntersect for Cube {
-    fn ray_intersect(&self, ray_origin: &Vec3, ray_dir: &Vec3) -> Intersect {
-        let mut tmin = (self.min.x - ray_origin.x) / ray_dir.x;
-        let mut tmax = (self.max.x - ray_origin.x) / ray_dir.x;
-
-        if tmin > tmax {
-            std::mem::swap(&mut tmin, &mut tmax);
-        }
-
-        let mut tymin = (self.min.y - ray_origin.y) / ray_dir.y;
-        let mut tymax = (self.max.y - ray_origin.y) / ray_dir.y;
+    fn ray_intersect(&self, ray_origin: &Vec3, ray_dir: &Vec3) -> Intersect<'_> {
+        self.ray_intersect_from(ray_origin, ray_dir, 0.0)
+    }
 
-        if tymin > tymax {
-            std::mem::swap(&mut tymin, &mut tymax);
+    fn ray_intersect_from(&self, ray_origin: &Vec3, ray_dir: &Vec3, t_min: f32) -> Intersect<'_> {
+        let bounds = self.bounds();
+        let hit = if self.inverted {
+            bounds.intersect_ray_inverted(ray_origin, ray_dir).filter(|hit| hit.distance >= t_min)
+        } else {
+            bounds.intersect_ray_from(ray_origin, ray_dir, t_min)
+        };
+        let hit = match hit {
+            Some(hit) => hit,
+            None => return Intersect::empty(),
+        };
+        let intersection_point = ray_origin + ray_dir * hit.distance;
+
+        let (u, v) = self
+            .material
+            .uv_projection
+            .project(&intersection_point, &bounds.centroid(), &bounds.size())
+            .unwrap_or_else(|| self.get_uv(intersection_point, hit.axis));
+        Intersect {
+            point: intersection_point,
+            distance: hit.distance,
+            normal: hit.normal,
+            material: &self.material,
+            is_intersecting: true,
+            u,  // Asigna el valor de u 
+            v,  // Asigna el valor de v 
         }
+    }
+}
 
-        if tmin > tymax || tymin > tmax {
-            return Intersect::empty();
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
 
-        tmin = tmin.max(tymin);
-        tmax = tmax.min(tymax);
+    fn random_cube(rng: &mut StdRng) -> Cube {
+        let min = Vec3::new(rng.gen_range(-5.0..5.0), rng.gen_range(-5.0..5.0), rng.gen_range(-5.0..5.0));
+        let size = Vec3::new(rng.gen_range(0.1..3.0), rng.gen_range(0.1..3.0), rng.gen_range(0.1..3.0));
+        Cube { min, max: min + size, material: Material::black(), inverted: false }
+    }
 
-        let mut tzmin = (self.min.z - ray_origin.z) / ray_dir.z;
-        let mut tzmax = (self.max.z - ray_origin.z) / ray_dir.z;
+    #[test]
+    fn two_by_three_by_four_cube_has_surface_area_52() {
+        let cube = Cube { min: Vec3::new(1.0, -1.0, 0.5), max: Vec3::new(3.0, 2.0, 4.5), material: Material::black(), inverted: false };
+        assert!((cube.surface_area() - 52.0).abs() < 1e-5);
+
+        let mut scene = crate::scene::Scene::new();
+        scene.add_cube(cube, &[]);
+        let stats = scene.stats();
+        assert_eq!((stats.object_count, stats.triangle_count), (1, 12));
+        assert!((stats.total_surface_area - 52.0).abs() < 1e-5);
+        assert_eq!((stats.bounds_min, stats.bounds_max), (Vec3::new(1.0, -1.0, 0.5), Vec3::new(3.0, 2.0, 4.5)));
+    }
 
-        if tzmin > tzmax {
-            std::mem::swap(&mut tzmin, &mut tzmax);
+    #[test]
+    fn axis_aligned_rays_get_a_finite_hit() {
+        let cube = Cube { min: Vec3::new(-1.0, -1.0, -1.0), max: Vec3::new(1.0, 1.0, 1.0), material: Material::black(), inverted: false };
+        for axis in 0..3 {
+            for sign in [-1.0, 1.0] {
+                let mut origin = Vec3::zeros();
+                origin[axis] = -5.0 * sign;
+                let mut dir = Vec3::zeros();
+                dir[axis] = sign;
+
+                let hit = cube.ray_intersect(&origin, &dir);
+                assert!(hit.is_intersecting, "axis {} sign {}", axis, sign);
+                assert_eq!(hit.distance, 4.0);
+                assert_eq!(hit.normal, -dir);
+                assert!(hit.point.iter().all(|c| c.is_finite()) && hit.u.is_finite() && hit.v.is_finite());
+            }
         }
+        // Rayo paralelo a una cara y fuera del cubo: sin impacto
+        assert!(!cube.ray_intersect(&Vec3::new(0.0, 2.0, 5.0), &Vec3::new(0.0, 0.0, -1.0)).is_intersecting);
+    }
 
-        if tmin > tzmax || tzmin > tmax {
-            return Intersect::empty();
+    #[test]
+    fn ray_inside_an_inverted_cube_hits_a_wall_facing_inward() {
+        let room = Cube { min: Vec3::new(-2.0, 0.0, -3.0), max: Vec3::new(2.0, 3.0, 3.0), material: Material::black(), inverted: true };
+        let origin = Vec3::new(0.0, 1.5, 0.0);
+        for (dir, wall_distance) in [(Vec3::new(1.0, 0.0, 0.0), 2.0), (Vec3::new(0.0, -1.0, 0.0), 1.5), (Vec3::new(0.0, 0.0, -1.0), 3.0)] {
+            let hit = room.ray_intersect(&origin, &dir);
+            assert!(hit.is_intersecting);
+            assert!((hit.distance - wall_distance).abs() < 1e-5);
+            // La normal apunta hacia el interior, de vuelta al observador
+            assert_eq!(hit.normal, -dir);
         }
 
-        tmin = tmin.max(tzmin);
-        tmax = tmax.min(tzmax);
+        // Desde afuera se ve a través de la pared cercana hasta la cara interior de la lejana
+        let hit = room.ray_intersect(&Vec3::new(0.0, 1.5, 10.0), &Vec3::new(0.0, 0.0, -1.0));
+        assert_eq!((hit.distance, hit.normal), (13.0, Vec3::new(0.0, 0.0, 1.0)));
+    }
 
-        if tmin < 0.0 && tmax < 0.0 {
-            return Intersect::empty();
+    #[test]
+    fn random_hits_have_axis_aligned_normals_surface_points_and_unit_uvs() {
+        let mut rng = StdRng::seed_from_u64(109);
+        for _ in 0..2000 {
+            let cube = random_cube(&mut rng);
+            let target = cube.min + (cube.max - cube.min).component_mul(&Vec3::new(rng.gen(), rng.gen(), rng.gen()));
+            let origin = Vec3::new(rng.gen_range(-20.0..20.0), rng.gen_range(-20.0..20.0), rng.gen_range(-20.0..20.0));
+            if cube.bounds().contains(&origin) {
+                continue;
+            }
+            let dir = (target - origin).normalize();
+
+            let hit = cube.ray_intersect(&origin, &dir);
+            assert!(hit.is_intersecting);
+            assert!((hit.normal.magnitude() - 1.0).abs() < 1e-6);
+            assert_eq!(hit.normal.iter().filter(|c| **c != 0.0).count(), 1, "normal {:?} is not axis-aligned", hit.normal);
+            assert!(hit.normal.dot(&dir) < 0.0);
+
+            let on_face = (0..3).any(|a| (hit.point[a] - cube.min[a]).abs() < 1e-3 || (hit.point[a] - cube.max[a]).abs() < 1e-3);
+            assert!(on_face, "point {:?} is not on the cube surface", hit.point);
+            assert!((-1e-3..=1.0 + 1e-3).contains(&hit.u), "u = {}", hit.u);
+            assert!((-1e-3..=1.0 + 1e-3).contains(&hit.v), "v = {}", hit.v);
         }
+    }
 
-        let intersection_point = ray_origin + ray_dir * tmin;
-
-        let (u, v) = self.get_uv(intersection_point);
-        Intersect {
-            point: intersection_point,
-            distance: tmin,
-            normal: self.calculate_normal(intersection_point),
-            material: self.material.clone(),
-            is_intersecting: true,
-            u: u,  // Asigna el valor de u 
-            v: v,  // Asigna el valor de v 
-        }
+    #[test]
+    fn near_clip_is_the_start_of_the_ray() {
+        let material = Material::new(crate::color::Color::new(200, 200, 200), 10.0, [0.9, 0.1, 0.0, 0.0], 1.0, None, 0.0);
+        let cube = Cube { min: Vec3::new(-1.0, -1.0, -1.0), max: Vec3::new(1.0, 1.0, 1.0), material, inverted: false };
+        let dir = Vec3::new(0.0, 0.0, -1.0);
+
+        // Origen al frente: la cara de entrada, salvo que el plano cercano caiga dentro del cubo
+        let front = Vec3::new(0.0, 0.0, 5.0);
+        let hit = cube.ray_intersect_from(&front, &dir, 1.0);
+        assert_eq!((hit.distance, hit.normal), (4.0, Vec3::new(0.0, 0.0, 1.0)));
+        let hit = cube.ray_intersect_from(&front, &dir, 5.0);
+        assert_eq!((hit.distance, hit.normal), (6.0, Vec3::new(0.0, 0.0, -1.0)));
+        assert!(!cube.ray_intersect_from(&front, &dir, 6.5).is_intersecting);
+
+        // Origen dentro: la cara de salida, nunca un impacto detrás del origen
+        let hit = cube.ray_intersect_from(&Vec3::new(0.0, 0.0, 0.5), &dir, 0.0);
+        assert_eq!((hit.distance, hit.normal), (1.5, Vec3::new(0.0, 0.0, -1.0)));
+
+        // Origen detrás del cubo (el cubo queda a sus espaldas)
+        assert!(!cube.ray_intersect_from(&Vec3::new(0.0, 0.0, -5.0), &dir, 0.0).is_intersecting);
     }
-}
\ No newline at end of file
+}
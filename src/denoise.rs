@@ -0,0 +1,125 @@
+use crate::color::Color;
+use crate::passes::RenderPasses;
+
+// Filtro de caja cruzado-bilateral: promedia vecinos del beauty, pero descarta
+// los que tienen una normal o profundidad distinta para no borrar los bordes
+#[derive(Debug, Clone, Copy)]
+pub struct DenoiseSettings {
+    pub radius: usize,
+    pub normal_sensitivity: f32, // Mayor valor = más estricto con cambios de normal
+    pub depth_sensitivity: f32,  // Mayor valor = más estricto con cambios relativos de profundidad
+}
+
+impl Default for DenoiseSettings {
+    fn default() -> Self {
+        DenoiseSettings {
+            radius: 2,
+            normal_sensitivity: 32.0,
+            depth_sensitivity: 16.0,
+        }
+    }
+}
+
+pub fn denoise(passes: &RenderPasses, settings: &DenoiseSettings) -> Vec<Color> {
+    let width = passes.width;
+    let height = passes.height;
+    let radius = settings.radius as isize;
+    let mut output = vec![Color::black(); width * height];
+
+    for y in 0..height {
+        for x in 0..width {
+            let center = y * width + x;
+            let center_normal = passes.normal[center];
+            let center_depth = passes.depth[center];
+
+            let (mut r, mut g, mut b, mut total_weight) = (0.0, 0.0, 0.0, 0.0);
+
+            for dy in -radius..=radius {
+                for dx in -radius..=radius {
+                    let nx = x as isize + dx;
+                    let ny = y as isize + dy;
+                    if nx < 0 || ny < 0 || nx >= width as isize || ny >= height as isize {
+                        continue;
+                    }
+                    let neighbor = ny as usize * width + nx as usize;
+                    let neighbor_depth = passes.depth[neighbor];
+
+                    // El fondo solo se mezcla con fondo, y la geometría solo con geometría
+                    if center_depth.is_finite() != neighbor_depth.is_finite() {
+                        continue;
+                    }
+
+                    let mut weight = 1.0;
+                    if center_depth.is_finite() {
+                        let normal_difference = 1.0 - center_normal.dot(&passes.normal[neighbor]);
+                        let depth_difference = (center_depth - neighbor_depth).abs() / center_depth.max(1e-4);
+                        weight = (-normal_difference * settings.normal_sensitivity).exp()
+                            * (-depth_difference * settings.depth_sensitivity).exp();
+                    }
+
+                    let color = passes.beauty[neighbor];
+                    r += color.r() as f32 * weight;
+                    g += color.g() as f32 * weight;
+                    b += color.b() as f32 * weight;
+                    total_weight += weight;
+                }
+            }
+
+            // El píxel central siempre aporta peso 1, así que total_weight > 0
+            output[center] = Color::new(
+                (r / total_weight).round() as u8,
+                (g / total_weight).round() as u8,
+                (b / total_weight).round() as u8,
+            );
+        }
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn noisy_flat_region_is_smoothed_and_the_normal_edge_is_kept() {
+        use nalgebra_glm::Vec3;
+        use rand::rngs::StdRng;
+        use rand::{Rng, SeedableRng};
+
+        // Mitad izquierda: pared ruidosa de frente; mitad derecha: otra cara, brillante y uniforme
+        let (width, height) = (16, 8);
+        let mut passes = RenderPasses::new(width, height);
+        let mut rng = StdRng::seed_from_u64(121);
+        for y in 0..height {
+            for x in 0..width {
+                let index = y * width + x;
+                passes.depth[index] = 5.0;
+                if x < width / 2 {
+                    passes.normal[index] = Vec3::new(0.0, 0.0, 1.0);
+                    let value = rng.gen_range(60..140);
+                    passes.beauty[index] = Color::new(value, value, value);
+                } else {
+                    passes.normal[index] = Vec3::new(1.0, 0.0, 0.0);
+                    passes.beauty[index] = Color::new(220, 220, 220);
+                }
+            }
+        }
+
+        let output = denoise(&passes, &DenoiseSettings::default());
+        let spread = |colors: &[Color]| {
+            let values: Vec<f32> = (0..height)
+                .flat_map(|y| (0..width / 2).map(move |x| y * width + x))
+                .map(|i| colors[i].r() as f32)
+                .collect();
+            let mean = values.iter().sum::<f32>() / values.len() as f32;
+            values.iter().map(|v| (v - mean) * (v - mean)).sum::<f32>() / values.len() as f32
+        };
+        assert!(spread(&output) < spread(&passes.beauty) * 0.3);
+        for y in 0..height {
+            // Junto al borde: la cara brillante no se filtra a la pared, y viceversa
+            assert!(output[y * width + width / 2 - 1].r() < 150);
+            assert_eq!(output[y * width + width / 2].r(), 220);
+        }
+    }
+}
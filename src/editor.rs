@@ -0,0 +1,161 @@
+use crate::material::Material;
+
+// Campos editables del material seleccionado, en el orden en que se recorren
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MaterialField {
+    Specular,
+    Diffuse,     // albedo[0]
+    SpecularMix, // albedo[1]
+    Reflection,  // albedo[2]
+    Refraction,  // albedo[3]
+    RefractiveIndex,
+    Translucency,
+}
+
+impl MaterialField {
+    pub const ALL: [MaterialField; 7] = [
+        MaterialField::Specular,
+        MaterialField::Diffuse,
+        MaterialField::SpecularMix,
+        MaterialField::Reflection,
+        MaterialField::Refraction,
+        MaterialField::RefractiveIndex,
+        MaterialField::Translucency,
+    ];
+
+    pub fn next(self) -> MaterialField {
+        let index = MaterialField::ALL.iter().position(|f| *f == self).unwrap_or(0);
+        MaterialField::ALL[(index + 1) % MaterialField::ALL.len()]
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            MaterialField::Specular => "BRILLO",
+            MaterialField::Diffuse => "ALBEDO DIFUSO",
+            MaterialField::SpecularMix => "ALBEDO ESPECULAR",
+            MaterialField::Reflection => "REFLEXION",
+            MaterialField::Refraction => "REFRACCION",
+            MaterialField::RefractiveIndex => "INDICE REFRACCION",
+            MaterialField::Translucency => "TRANSLUCIDEZ",
+        }
+    }
+
+    // Incremento por pulsación y rango válido de cada campo
+    fn step(self) -> f32 {
+        match self {
+            MaterialField::Specular => 5.0,
+            _ => 0.05,
+        }
+    }
+
+    fn range(self) -> (f32, f32) {
+        match self {
+            MaterialField::Specular => (1.0, 1000.0),
+            MaterialField::RefractiveIndex => (1.0, 3.0),
+            _ => (0.0, 1.0),
+        }
+    }
+
+    pub fn get(self, material: &Material) -> f32 {
+        match self {
+            MaterialField::Specular => material.specular,
+            MaterialField::Diffuse => material.albedo[0],
+            MaterialField::SpecularMix => material.albedo[1],
+            MaterialField::Reflection => material.albedo[2],
+            MaterialField::Refraction => material.albedo[3],
+            MaterialField::RefractiveIndex => material.refractive_index,
+            MaterialField::Translucency => material.translucency,
+        }
+    }
+
+    pub fn set(self, material: &mut Material, value: f32) {
+        let (min, max) = self.range();
+        let value = value.clamp(min, max);
+        match self {
+            MaterialField::Specular => material.specular = value,
+            MaterialField::Diffuse => material.albedo[0] = value,
+            MaterialField::SpecularMix => material.albedo[1] = value,
+            MaterialField::Reflection => material.albedo[2] = value,
+            MaterialField::Refraction => material.albedo[3] = value,
+            MaterialField::RefractiveIndex => material.refractive_index = value,
+            MaterialField::Translucency => material.translucency = value,
+        }
+    }
+}
+
+// Editor en pantalla: un objeto seleccionado y el campo activo de su material
+pub struct MaterialEditor {
+    pub selected: Option<usize>, // Índice en Scene::objects
+    pub field: MaterialField,
+}
+
+impl Default for MaterialEditor {
+    fn default() -> Self {
+        MaterialEditor::new()
+    }
+}
+
+impl MaterialEditor {
+    pub fn new() -> Self {
+        MaterialEditor {
+            selected: None,
+            field: MaterialField::Specular,
+        }
+    }
+
+    pub fn next_field(&mut self) {
+        self.field = self.field.next();
+    }
+
+    // Sube (steps > 0) o baja (steps < 0) el campo activo; devuelve true si el valor cambió
+    pub fn adjust(&self, material: &mut Material, steps: i32) -> bool {
+        let old_value = self.field.get(material);
+        self.field.set(material, old_value + self.field.step() * steps as f32);
+        self.field.get(material) != old_value
+    }
+
+    // Líneas de texto con los valores actuales; el campo activo va marcado con '>'
+    pub fn lines(&self, material: &Material) -> Vec<String> {
+        MaterialField::ALL
+            .iter()
+            .map(|field| {
+                let marker = if *field == self.field { '>' } else { ' ' };
+                format!("{} {}: {:.2}", marker, field.label(), field.get(material))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fields_cycle_and_adjust_within_their_range() {
+        let mut editor = MaterialEditor::new();
+        for expected in MaterialField::ALL.iter().skip(1).chain(MaterialField::ALL.iter().take(1)) {
+            editor.next_field();
+            assert_eq!(editor.field, *expected);
+        }
+
+        let mut material = Material::black();
+        editor.field = MaterialField::Diffuse;
+        material.albedo[0] = 0.5;
+        assert!(editor.adjust(&mut material, 2));
+        assert!((material.albedo[0] - 0.6).abs() < 1e-5);
+        assert!(editor.adjust(&mut material, -1));
+        assert!((material.albedo[0] - 0.55).abs() < 1e-5);
+
+        // En el límite el valor se recorta y ya no cambia
+        material.albedo[0] = 1.0;
+        assert!(!editor.adjust(&mut material, 1));
+        assert_eq!(material.albedo[0], 1.0);
+
+        editor.field = MaterialField::RefractiveIndex;
+        material.refractive_index = 1.0;
+        assert!(!editor.adjust(&mut material, -3));
+        let lines = editor.lines(&material);
+        assert_eq!(lines.len(), MaterialField::ALL.len());
+        assert_eq!(lines[5], "> INDICE REFRACCION: 1.00");
+    }
+}
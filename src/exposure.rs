@@ -0,0 +1,80 @@
+use crate::hdr::HdrImage;
+
+// Exposición automática: escala la radiancia para llevar su luminancia promedio hacia un objetivo,
+// adaptándose gradualmente entre cuadros (como el ojo al pasar del día a la noche). El factor
+// resultante va en RenderSettings::exposure, que display_color aplica antes de la curva de tono
+pub struct AutoExposure {
+    pub target_luminance: f32, // Luminancia media deseada de la radiancia expuesta
+    pub adaptation_speed: f32, // Velocidad de adaptación (1/segundos)
+    pub min_exposure: f32,
+    pub max_exposure: f32,
+    exposure: f32,
+}
+
+impl AutoExposure {
+    pub fn new(target_luminance: f32, adaptation_speed: f32) -> Self {
+        AutoExposure {
+            target_luminance,
+            adaptation_speed,
+            min_exposure: 0.25,
+            max_exposure: 8.0,
+            exposure: 1.0,
+        }
+    }
+
+    pub fn exposure(&self) -> f32 {
+        self.exposure
+    }
+
+    // Exposición que llevaría la luminancia promedio exactamente al objetivo
+    pub fn target_exposure(&self, average_luminance: f32) -> f32 {
+        (self.target_luminance / average_luminance.max(1e-4)).clamp(self.min_exposure, self.max_exposure)
+    }
+
+    // Mide la radiancia lineal del cuadro (sin exponer ni recortar a 8 bits) y acerca la
+    // exposición al objetivo; devuelve el factor para RenderSettings::exposure
+    pub fn update(&mut self, radiance: &HdrImage, delta_time: f32) -> f32 {
+        let average = radiance.log_average_luminance();
+        let target = self.target_exposure(average);
+        let blend = 1.0 - (-self.adaptation_speed * delta_time).exp();
+        self.exposure += (target - self.exposure) * blend;
+        self.exposure
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nalgebra_glm::Vec3;
+
+    fn uniform_frame(value: f32) -> HdrImage {
+        let mut hdr = HdrImage::new(8, 8);
+        hdr.accumulate(&vec![Vec3::new(value, value, value); 64]);
+        hdr
+    }
+
+    #[test]
+    fn bright_and_dark_frames_converge_to_the_exposure_that_hits_the_target() {
+        let target = 0.4;
+        // La radiancia 4.0 se recortaría a blanco en 8 bits; medida en HDR pide 0.1, limitada a min_exposure
+        for (value, expected) in [(4.0, 0.25), (0.1, 4.0), (0.8, 0.5)] {
+            let mut auto_exposure = AutoExposure::new(target, 100.0);
+            let frame = uniform_frame(value);
+            let mut exposure = 1.0;
+            for _ in 0..10 {
+                exposure = auto_exposure.update(&frame, 0.1);
+            }
+            assert!((exposure - expected).abs() < expected * 1e-2, "{}: {}", value, exposure);
+            assert_eq!(exposure, auto_exposure.exposure());
+        }
+    }
+
+    #[test]
+    fn radiance_above_one_is_metered_unclipped() {
+        let auto_exposure = AutoExposure::new(0.4, 1.0);
+        let average = uniform_frame(2.0).log_average_luminance();
+        assert!((average - 2.0).abs() < 1e-3);
+        let exposure = AutoExposure { min_exposure: 0.01, ..auto_exposure }.target_exposure(average);
+        assert!((exposure - 0.2).abs() < 1e-3);
+    }
+}
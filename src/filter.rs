@@ -0,0 +1,93 @@
+use nalgebra_glm::Vec3;
+
+// Filtro de reconstrucción para el supersampling: pondera cada muestra según su distancia
+// al centro del píxel
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PixelFilter {
+    Box,      // Todas las muestras pesan igual (radio 0.5 px)
+    Tent,     // Peso lineal que cae a 0 a 1 px del centro
+    Gaussian, // Campana con sigma 0.5 px, recortada a 1.5 px: más suave
+}
+
+impl PixelFilter {
+    // Radio (en píxeles) dentro del que se reparten las muestras
+    pub fn radius(&self) -> f32 {
+        match self {
+            PixelFilter::Box => 0.5,
+            PixelFilter::Tent => 1.0,
+            PixelFilter::Gaussian => 1.5,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            PixelFilter::Box => "box",
+            PixelFilter::Tent => "tent",
+            PixelFilter::Gaussian => "gaussian",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<PixelFilter> {
+        match name {
+            "box" => Some(PixelFilter::Box),
+            "tent" => Some(PixelFilter::Tent),
+            "gaussian" => Some(PixelFilter::Gaussian),
+            _ => None,
+        }
+    }
+
+    // Peso sin normalizar de una muestra desplazada (dx, dy) del centro del píxel
+    pub fn weight(&self, dx: f32, dy: f32) -> f32 {
+        match self {
+            PixelFilter::Box => 1.0,
+            PixelFilter::Tent => {
+                let r = self.radius();
+                (1.0 - dx.abs() / r).max(0.0) * (1.0 - dy.abs() / r).max(0.0)
+            }
+            PixelFilter::Gaussian => {
+                let sigma = 0.5;
+                (-(dx * dx + dy * dy) / (2.0 * sigma * sigma)).exp()
+            }
+        }
+    }
+
+    // Pesos de un conjunto de desplazamientos, normalizados para sumar 1
+    pub fn normalized_weights(&self, offsets: &[(f32, f32)]) -> Vec<f32> {
+        let weights: Vec<f32> = offsets.iter().map(|(dx, dy)| self.weight(*dx, *dy)).collect();
+        let total: f32 = weights.iter().sum();
+        if total <= 0.0 {
+            // Todas las muestras cayeron fuera del soporte: promedio simple
+            return vec![1.0 / offsets.len().max(1) as f32; offsets.len()];
+        }
+        weights.iter().map(|w| w / total).collect()
+    }
+}
+
+// Suma ponderada de muestras de radiancia (sin recortar)
+pub fn weighted_sum(samples: &[Vec3], weights: &[f32]) -> Vec3 {
+    samples
+        .iter()
+        .zip(weights)
+        .fold(Vec3::new(0.0, 0.0, 0.0), |sum, (sample, weight)| sum + sample * *weight)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gaussian_favors_center_samples_and_weights_sum_to_one() {
+        let offsets = [(0.0, 0.0), (0.1, -0.1), (0.6, 0.2), (-1.0, 1.0), (1.5, 0.2)];
+        let weights = PixelFilter::Gaussian.normalized_weights(&offsets);
+        assert!((weights.iter().sum::<f32>() - 1.0).abs() < 1e-5);
+        assert!(weights.windows(2).all(|pair| pair[0] > pair[1]), "{:?}", weights);
+
+        let boxed = PixelFilter::Box.normalized_weights(&offsets);
+        assert!(boxed.iter().all(|w| (w - 0.2).abs() < 1e-6));
+        // Fuera del soporte de la carpa: promedio simple
+        assert_eq!(PixelFilter::Tent.normalized_weights(&[(2.0, 0.0), (0.0, 3.0)]), vec![0.5, 0.5]);
+
+        let samples = [Vec3::new(0.8, 0.0, 0.0), Vec3::new(0.0, 0.8, 0.0)];
+        assert!((weighted_sum(&samples, &[0.25, 0.75]) - Vec3::new(0.2, 0.6, 0.0)).magnitude() < 1e-6);
+    }
+}
@@ -0,0 +1,128 @@
+use nalgebra_glm::Vec3;
+
+use crate::color::Color;
+use crate::scene::Scene;
+use crate::sampling;
+use crate::shadow_along;
+
+// Niebla volumétrica con dispersión simple: a lo largo del rayo de cámara se toman muestras y en
+// cada una se mira cuánta luz la alcanza (rayo de sombra, atenuado por superficies transparentes).
+// Donde la luz entra por un hueco (una ventana) la niebla se ilumina y aparecen los haces de luz
+#[derive(Debug, Clone, Copy)]
+pub struct VolumetricFog {
+    pub density: f32,      // Coeficiente de extinción por unidad de distancia
+    pub color: Color,      // Albedo del medio: tiñe la luz dispersada
+    pub anisotropy: f32,   // g de Henyey-Greenstein: 0 isotrópica, > 0 brilla mirando hacia la luz
+    pub steps: u32,        // Muestras por rayo
+    pub max_distance: f32, // Largo de la marcha para los rayos que no tocan nada
+}
+
+impl VolumetricFog {
+    pub fn new(density: f32, color: Color) -> Self {
+        VolumetricFog {
+            density,
+            color,
+            anisotropy: 0.3,
+            steps: 16,
+            max_distance: 20.0,
+        }
+    }
+
+    // Henyey-Greenstein escalada por 4π para que la fase isotrópica valga 1
+    pub fn phase(&self, cos_theta: f32) -> f32 {
+        let g = self.anisotropy.clamp(-0.99, 0.99);
+        let denominator = (1.0 + g * g - 2.0 * g * cos_theta).max(1e-6);
+        (1.0 - g * g) / (denominator * denominator.sqrt())
+    }
+
+    // Luz dispersada hacia la cámara (RGB en 0..1, antes del albedo) y transmitancia que queda
+    // después de `distance` unidades de ray_direction
+    pub fn in_scatter(&self, ray_origin: &Vec3, ray_direction: &Vec3, distance: f32, scene: &Scene, time: f32) -> (Vec3, f32) {
+        let length = ray_direction.magnitude();
+        if self.density <= 0.0 || self.steps == 0 || length <= 0.0 {
+            return (Vec3::new(0.0, 0.0, 0.0), 1.0);
+        }
+        let direction = ray_direction / length;
+        let march = (distance * length).min(self.max_distance);
+        let step = march / self.steps as f32;
+        let step_transmittance = (-self.density * step).exp();
+
+        let mut scattered = Vec3::new(0.0, 0.0, 0.0);
+        let mut transmittance = 1.0;
+        let jitter = sampling::random_f32(); // Desplaza las muestras para cambiar bandas por ruido
+        for i in 0..self.steps {
+            let point = ray_origin + direction * ((i as f32 + jitter) * step);
+            for light in &scene.lights {
+                let to_light = light.position - point;
+                let light_distance = to_light.magnitude();
+                if light_distance <= 0.0 {
+                    continue;
+                }
+                let light_dir = to_light / light_distance;
+                // Igual que en las superficies: un vidrio deja pasar parte de la luz al haz y las
+                // luces de relleno no proyectan sombra
+                let visibility = if light.casts_shadows {
+                    (1.0 - shadow_along(&point, scene, &light_dir, light_distance, time)).clamp(0.0, 1.0)
+                } else {
+                    1.0
+                };
+                if visibility <= 0.0 {
+                    continue;
+                }
+                let intensity = light.intensity_towards(&point) * visibility * self.phase(light_dir.dot(&direction));
+                scattered += light.color.to_radiance() * (intensity * transmittance * self.density * step);
+            }
+            transmittance *= step_transmittance;
+        }
+        (scattered, transmittance)
+    }
+
+    // Compone la niebla sobre la radiancia de lo que se ve a `distance` (el ambiente aporta un velo parejo)
+    pub fn apply(&self, surface: Vec3, ray_origin: &Vec3, ray_direction: &Vec3, distance: f32, scene: &Scene, time: f32) -> Vec3 {
+        let (scattered, transmittance) = self.in_scatter(ray_origin, ray_direction, distance, scene, time);
+        let albedo = self.color.to_radiance();
+        let haze = scene.ambient_intensity * (1.0 - transmittance);
+        let light = scattered.component_mul(&albedo) + albedo * haze;
+        surface * transmittance + light
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cube::Cube;
+    use crate::light::Light;
+    use crate::material::Material;
+
+    // Rayo horizontal bajo una luz, con una losa entre ambos (None = sin losa)
+    fn scattered_under(slab_transmission: Option<f32>, casts_shadows: bool) -> f32 {
+        let mut scene = Scene::new();
+        let mut light = Light::new(Vec3::new(0.0, 5.0, 0.0), Color::new(255, 255, 255), 1.0);
+        light.casts_shadows = casts_shadows;
+        scene.lights = vec![light];
+        if let Some(transmission) = slab_transmission {
+            let material = Material::new(Color::new(200, 200, 200), 10.0, [0.5, 0.0, 0.0, transmission], 1.0, None, 0.0);
+            scene.add_cube(Cube { min: Vec3::new(-10.0, 2.0, -10.0), max: Vec3::new(10.0, 2.2, 10.0), material, inverted: false }, &[]);
+        }
+        let fog = VolumetricFog::new(0.2, Color::new(255, 255, 255));
+        sampling::seed_pixel(0, 0, 0, 0);
+        let (scattered, _) = fog.in_scatter(&Vec3::new(-3.0, 0.0, 0.0), &Vec3::new(1.0, 0.0, 0.0), 6.0, &scene, 0.0);
+        scattered.x
+    }
+
+    #[test]
+    fn lit_volume_scatters_more_than_shadowed_and_glass_lets_part_through() {
+        let open = scattered_under(None, true);
+        let glass = scattered_under(Some(0.5), true);
+        let opaque = scattered_under(Some(0.0), true);
+
+        assert!(open > 0.0);
+        assert_eq!(opaque, 0.0);
+        assert!((glass - open * 0.5).abs() < open * 1e-3, "{} {}", glass, open);
+    }
+
+    #[test]
+    fn fill_light_reaches_the_fog_through_an_opaque_slab() {
+        assert_eq!(scattered_under(Some(0.0), false), scattered_under(None, true));
+    }
+}
@@ -1,9 +1,47 @@
+use image::{Rgb, RgbImage};
+use crate::color::Color;
+use crate::font::for_each_text_pixel;
+use crate::RenderRegion;
+
+// Qué hace clear() al empezar un cuadro
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ClearMode {
+    Full,     // Rellena todo con background_color
+    Preserve, // No toca nada: el cuadro anterior sigue ahí (acumulación, render por regiones)
+}
+
+// Cómo se interpolan los píxeles al escalar con blit_scaled
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScaleFilter {
+    Nearest,  // Bloques nítidos, sin mezclar
+    Bilinear, // Suave, mezcla los 4 píxeles vecinos
+}
+
+// Diferencias por canal menores o iguales a esto no cuentan como píxel distinto en compare
+pub const COMPARE_TOLERANCE: u8 = 2;
+
+// Resultado de comparar dos framebuffers (p. ej. un render contra una imagen de referencia)
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageDiff {
+    pub same_size: bool,         // Con tamaños distintos el resto de campos no tiene sentido
+    pub max_channel_diff: u8,    // Mayor diferencia absoluta en un canal
+    pub mean_squared_error: f32, // Promedio del error cuadrático por canal, en escala 0..255
+    pub differing_pixels: usize, // Píxeles con algún canal fuera de la tolerancia
+}
+
+impl ImageDiff {
+    pub fn is_identical(&self) -> bool {
+        self.same_size && self.differing_pixels == 0
+    }
+}
+
 pub struct Framebuffer {
     pub width: usize,
     pub height: usize,
     pub buffer: Vec<u32>,
     pub background_color: u32,
     pub current_color: u32,
+    pub clear_mode: ClearMode,
 }
 
 impl Framebuffer {
@@ -14,6 +52,7 @@ impl Framebuffer {
             buffer: vec![0; width * height],
             background_color: 0x000000,
             current_color: 0xFFFFFF,
+            clear_mode: ClearMode::Full,
         }
     }
     pub fn get_pixel_color(&self, x: usize, y: usize) -> u32 {
@@ -25,11 +64,28 @@ impl Framebuffer {
         }
     }
     pub fn clear(&mut self) {
+        if self.clear_mode == ClearMode::Preserve {
+            return;
+        }
         for pixel in self.buffer.iter_mut() {
             *pixel = self.background_color;
         }
     }
 
+    // Rellena con background_color solo el rectángulo (x0, y0, x1, y1), con x1/y1 exclusivos y
+    // recortado al framebuffer. No depende de clear_mode: es un borrado pedido explícitamente
+    pub fn clear_region(&mut self, (x0, y0, x1, y1): RenderRegion) {
+        let x1 = x1.min(self.width);
+        let y1 = y1.min(self.height);
+        if x0 >= x1 {
+            return;
+        }
+        for y in y0..y1 {
+            let row = y * self.width;
+            self.buffer[row + x0..row + x1].fill(self.background_color);
+        }
+    }
+
     pub fn point(&mut self, x: usize, y: usize) {
         if x < self.width && y < self.height {
             self.buffer[y * self.width + x] = self.current_color;
@@ -43,4 +99,256 @@ impl Framebuffer {
     pub fn set_current_color(&mut self, color: u32) {
         self.current_color = color;
     }
-}
\ No newline at end of file
+
+    // Línea de Bresenham entre dos píxeles; los puntos fuera del framebuffer se recortan
+    pub fn draw_line(&mut self, x0: i32, y0: i32, x1: i32, y1: i32, color: u32) {
+        let dx = (x1 - x0).abs();
+        let dy = -(y1 - y0).abs();
+        let step_x = if x0 < x1 { 1 } else { -1 };
+        let step_y = if y0 < y1 { 1 } else { -1 };
+        let mut error = dx + dy;
+        let (mut x, mut y) = (x0, y0);
+
+        loop {
+            if x >= 0 && y >= 0 && (x as usize) < self.width && (y as usize) < self.height {
+                self.buffer[y as usize * self.width + x as usize] = color;
+            }
+            if x == x1 && y == y1 {
+                break;
+            }
+            let doubled = 2 * error;
+            if doubled >= dy {
+                error += dy;
+                x += step_x;
+            }
+            if doubled <= dx {
+                error += dx;
+                y += step_y;
+            }
+        }
+    }
+
+    // Dibuja texto con la fuente bitmap 5x7; los píxeles fuera del framebuffer se recortan
+    pub fn draw_text(&mut self, x: usize, y: usize, text: &str, color: u32) {
+        let (width, height) = (self.width, self.height);
+        for_each_text_pixel(x, y, text, |px, py| {
+            if px < width && py < height {
+                self.buffer[py * width + px] = color;
+            }
+        });
+    }
+
+    // Copia este framebuffer a `target` estirándolo a su tamaño (render a resolución interna menor)
+    pub fn blit_scaled(&self, target: &mut Framebuffer, filter: ScaleFilter) {
+        if self.width == 0 || self.height == 0 {
+            return;
+        }
+        let scale_x = self.width as f32 / target.width as f32;
+        let scale_y = self.height as f32 / target.height as f32;
+
+        for y in 0..target.height {
+            for x in 0..target.width {
+                let pixel = match filter {
+                    ScaleFilter::Nearest => {
+                        let sx = ((x as f32 * scale_x) as usize).min(self.width - 1);
+                        let sy = ((y as f32 * scale_y) as usize).min(self.height - 1);
+                        self.buffer[sy * self.width + sx]
+                    }
+                    ScaleFilter::Bilinear => {
+                        // Centros de píxel alineados entre ambas resoluciones
+                        let fx = ((x as f32 + 0.5) * scale_x - 0.5).clamp(0.0, (self.width - 1) as f32);
+                        let fy = ((y as f32 + 0.5) * scale_y - 0.5).clamp(0.0, (self.height - 1) as f32);
+                        let (x0, y0) = (fx as usize, fy as usize);
+                        let (x1, y1) = ((x0 + 1).min(self.width - 1), (y0 + 1).min(self.height - 1));
+                        let (tx, ty) = (fx - x0 as f32, fy - y0 as f32);
+                        let texel = |px: usize, py: usize| Color::from_hex(self.buffer[py * self.width + px]);
+                        let top = texel(x0, y0).lerp(texel(x1, y0), tx);
+                        let bottom = texel(x0, y1).lerp(texel(x1, y1), tx);
+                        top.lerp(bottom, ty).to_hex()
+                    }
+                };
+                target.buffer[y * target.width + x] = pixel;
+            }
+        }
+    }
+
+    pub fn compare(&self, other: &Framebuffer) -> ImageDiff {
+        self.compare_with_tolerance(other, COMPARE_TOLERANCE)
+    }
+
+    pub fn compare_with_tolerance(&self, other: &Framebuffer, tolerance: u8) -> ImageDiff {
+        if self.width != other.width || self.height != other.height {
+            return ImageDiff {
+                same_size: false,
+                max_channel_diff: u8::MAX,
+                mean_squared_error: f32::INFINITY,
+                differing_pixels: self.buffer.len().max(other.buffer.len()),
+            };
+        }
+
+        let mut max_channel_diff = 0;
+        let mut squared_error = 0.0;
+        let mut differing_pixels = 0;
+        for (&a, &b) in self.buffer.iter().zip(&other.buffer) {
+            let (a, b) = (Color::from_hex(a), Color::from_hex(b));
+            let diffs = [a.r().abs_diff(b.r()), a.g().abs_diff(b.g()), a.b().abs_diff(b.b())];
+            let pixel_max = diffs.iter().copied().max().unwrap_or(0);
+            max_channel_diff = max_channel_diff.max(pixel_max);
+            squared_error += diffs.iter().map(|&d| (d as f32) * (d as f32)).sum::<f32>();
+            if pixel_max > tolerance {
+                differing_pixels += 1;
+            }
+        }
+
+        let channel_count = (self.buffer.len() * 3).max(1);
+        ImageDiff {
+            same_size: true,
+            max_channel_diff,
+            mean_squared_error: squared_error / channel_count as f32,
+            differing_pixels,
+        }
+    }
+
+    // Cuántos píxeles caen en cada uno de `bins` intervalos iguales de luminancia (0..1)
+    pub fn luminance_histogram(&self, bins: usize) -> Vec<u32> {
+        let mut histogram = vec![0; bins];
+        if bins == 0 {
+            return histogram;
+        }
+        for &pixel in &self.buffer {
+            let luminance = Color::from_hex(pixel).luminance();
+            let bin = ((luminance * bins as f32) as usize).min(bins - 1);
+            histogram[bin] += 1;
+        }
+        histogram
+    }
+
+    pub fn average_luminance(&self) -> f32 {
+        if self.buffer.is_empty() {
+            return 0.0;
+        }
+        let sum: f32 = self.buffer.iter().map(|&pixel| Color::from_hex(pixel).luminance()).sum();
+        sum / self.buffer.len() as f32
+    }
+
+    pub fn to_image(&self) -> RgbImage {
+        let mut image = RgbImage::new(self.width as u32, self.height as u32);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let pixel = self.buffer[y * self.width + x];
+                let r = ((pixel >> 16) & 0xFF) as u8;
+                let g = ((pixel >> 8) & 0xFF) as u8;
+                let b = (pixel & 0xFF) as u8;
+                image.put_pixel(x as u32, y as u32, Rgb([r, g, b]));
+            }
+        }
+        image
+    }
+
+    pub fn save_png(&self, file_path: &str) -> image::ImageResult<()> {
+        self.to_image().save(file_path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drawing_a_sets_the_glyph_pixels_at_its_origin() {
+        let mut framebuffer = Framebuffer::new(12, 10);
+        framebuffer.draw_text(3, 2, "A", 0xFFFFFF);
+        let expected = [
+            " ### ",
+            "#   #",
+            "#   #",
+            "#   #",
+            "#####",
+            "#   #",
+            "#   #",
+        ];
+        for y in 0..10 {
+            for x in 0..12 {
+                let inside = (3..8).contains(&x) && (2..9).contains(&y);
+                let lit = inside && expected[y - 2].as_bytes()[x - 3] == b'#';
+                assert_eq!(framebuffer.get_pixel_color(x, y), if lit { 0xFFFFFF } else { 0 }, "({}, {})", x, y);
+            }
+        }
+    }
+
+    #[test]
+    fn half_black_half_white_buffer_splits_the_histogram_and_averages_half() {
+        let mut framebuffer = Framebuffer::new(4, 4);
+        framebuffer.clear();
+        framebuffer.set_current_color(0xFFFFFF);
+        for y in 0..4 {
+            for x in 2..4 {
+                framebuffer.point(x, y);
+            }
+        }
+        assert_eq!(framebuffer.luminance_histogram(4), vec![8, 0, 0, 8]);
+        assert!((framebuffer.average_luminance() - 0.5).abs() < 1e-4);
+        assert!(framebuffer.luminance_histogram(0).is_empty());
+    }
+
+    #[test]
+    fn identical_buffers_have_no_diff_and_one_changed_pixel_is_found() {
+        let mut reference = Framebuffer::new(4, 3);
+        reference.set_background_color(0x336699);
+        reference.clear();
+        let mut other = Framebuffer::new(4, 3);
+        other.set_background_color(0x336699);
+        other.clear();
+
+        let diff = reference.compare(&other);
+        assert!(diff.is_identical());
+        assert_eq!((diff.max_channel_diff, diff.mean_squared_error), (0, 0.0));
+
+        other.set_current_color(0x33669F); // +6 en azul
+        other.point(2, 1);
+        let diff = reference.compare(&other);
+        assert!(!diff.is_identical());
+        assert_eq!((diff.max_channel_diff, diff.differing_pixels), (6, 1));
+        assert!((diff.mean_squared_error - 36.0 / 36.0).abs() < 1e-6);
+        // Dentro de la tolerancia no cuenta como distinto
+        assert_eq!(reference.compare_with_tolerance(&other, 6).differing_pixels, 0);
+        assert!(!reference.compare(&Framebuffer::new(3, 4)).same_size);
+    }
+
+    #[test]
+    fn nearest_blit_of_two_by_two_replicates_each_pixel_into_a_block() {
+        let mut source = Framebuffer::new(2, 2);
+        source.buffer = vec![0xFF0000, 0x00FF00, 0x0000FF, 0xFFFFFF];
+        let mut target = Framebuffer::new(4, 4);
+        source.blit_scaled(&mut target, ScaleFilter::Nearest);
+
+        #[rustfmt::skip]
+        let expected = vec![
+            0xFF0000, 0xFF0000, 0x00FF00, 0x00FF00,
+            0xFF0000, 0xFF0000, 0x00FF00, 0x00FF00,
+            0x0000FF, 0x0000FF, 0xFFFFFF, 0xFFFFFF,
+            0x0000FF, 0x0000FF, 0xFFFFFF, 0xFFFFFF,
+        ];
+        assert_eq!(target.buffer, expected);
+    }
+
+    #[test]
+    fn clear_region_only_clears_its_rectangle() {
+        let mut framebuffer = Framebuffer::new(5, 4);
+        framebuffer.buffer.fill(0xABCDEF);
+        framebuffer.clear_region((1, 1, 3, 3));
+        for y in 0..4 {
+            for x in 0..5 {
+                let expected = if (1..3).contains(&x) && (1..3).contains(&y) { 0x000000 } else { 0xABCDEF };
+                assert_eq!(framebuffer.get_pixel_color(x, y), expected, "({}, {})", x, y);
+            }
+        }
+
+        // Recortado al framebuffer, y Preserve no afecta a un borrado explícito
+        framebuffer.clear_mode = ClearMode::Preserve;
+        framebuffer.clear_region((4, 3, 10, 10));
+        assert_eq!(framebuffer.get_pixel_color(4, 3), 0x000000);
+        framebuffer.clear();
+        assert_eq!(framebuffer.get_pixel_color(0, 0), 0xABCDEF);
+    }
+}
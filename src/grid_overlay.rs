@@ -0,0 +1,93 @@
+use nalgebra_glm::Vec3;
+
+use crate::camera::Camera;
+use crate::framebuffer::Framebuffer;
+use crate::RenderSettings;
+
+// Medio ancho de la cuadrícula en unidades de mundo (líneas de -extent a +extent en X y Z)
+pub const DEFAULT_GRID_EXTENT: i32 = 10;
+// Largo de cada eje del marcador de origen
+pub const ORIGIN_AXIS_LENGTH: f32 = 1.5;
+
+const GRID_COLOR: u32 = 0x606060;
+const AXIS_COLORS: [u32; 3] = [0xFF3030, 0x30FF30, 0x3080FF]; // X rojo, Y verde, Z azul
+
+// Líneas del piso en y = 0, una por cada coordenada entera de X y de Z dentro de [-extent, extent]
+pub fn grid_lines(extent: i32) -> Vec<(Vec3, Vec3)> {
+    let extent = extent.max(0);
+    let limit = extent as f32;
+    let mut lines = Vec::new();
+    for i in -extent..=extent {
+        let i = i as f32;
+        lines.push((Vec3::new(i, 0.0, -limit), Vec3::new(i, 0.0, limit)));
+        lines.push((Vec3::new(-limit, 0.0, i), Vec3::new(limit, 0.0, i)));
+    }
+    lines
+}
+
+// Ejes X, Y y Z desde el origen, con su color
+pub fn origin_axes(length: f32) -> [(Vec3, Vec3, u32); 3] {
+    let origin = Vec3::new(0.0, 0.0, 0.0);
+    [
+        (origin, Vec3::new(length, 0.0, 0.0), AXIS_COLORS[0]),
+        (origin, Vec3::new(0.0, length, 0.0), AXIS_COLORS[1]),
+        (origin, Vec3::new(0.0, 0.0, length), AXIS_COLORS[2]),
+    ]
+}
+
+// Dibuja la cuadrícula y encima el marcador de origen. Cada línea se parte en tramos de una
+// unidad: así una línea que pasa detrás de la cámara se sigue viendo en la parte de adelante
+pub fn draw_grid(framebuffer: &mut Framebuffer, camera: &Camera, settings: &RenderSettings, extent: i32) {
+    for (start, end) in grid_lines(extent) {
+        draw_world_line(framebuffer, camera, settings, &start, &end, GRID_COLOR);
+    }
+    for (start, end, color) in origin_axes(ORIGIN_AXIS_LENGTH) {
+        draw_world_line(framebuffer, camera, settings, &start, &end, color);
+    }
+}
+
+fn draw_world_line(framebuffer: &mut Framebuffer, camera: &Camera, settings: &RenderSettings, start: &Vec3, end: &Vec3, color: u32) {
+    let width = framebuffer.width as f32;
+    let height = framebuffer.height as f32;
+    let project = |point: &Vec3| {
+        camera
+            .project(point, width, height, settings.fov, settings.pixel_aspect)
+            .filter(|(x, y)| x.abs() < width * 4.0 && y.abs() < height * 4.0)
+    };
+
+    let segments = ((end - start).magnitude().ceil() as usize).max(1);
+    let mut previous = project(start);
+    for step in 1..=segments {
+        let point = start.lerp(end, step as f32 / segments as f32);
+        let current = project(&point);
+        if let (Some((x0, y0)), Some((x1, y1))) = (previous, current) {
+            framebuffer.draw_line(x0 as i32, y0 as i32, x1 as i32, y1 as i32, color);
+        }
+        previous = current;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grid_has_a_line_at_every_integer_coordinate_within_the_extent() {
+        let lines = grid_lines(2);
+        assert_eq!(lines.len(), 10);
+        for (start, end) in &lines {
+            assert_eq!((start.y, end.y), (0.0, 0.0));
+            // Cada línea corre a lo largo de X o de Z a una coordenada entera fija del otro eje
+            let (fixed, from, to) = if start.x == end.x { (start.x, start.z, end.z) } else { (start.z, start.x, end.x) };
+            assert_eq!(fixed.fract(), 0.0);
+            assert!((-2.0..=2.0).contains(&fixed));
+            assert_eq!((from, to), (-2.0, 2.0));
+        }
+        for i in -2..=2 {
+            let i = i as f32;
+            assert!(lines.contains(&(Vec3::new(i, 0.0, -2.0), Vec3::new(i, 0.0, 2.0))));
+            assert!(lines.contains(&(Vec3::new(-2.0, 0.0, i), Vec3::new(2.0, 0.0, i))));
+        }
+        assert_eq!(grid_lines(-3), vec![(Vec3::zeros(), Vec3::zeros()); 2]);
+    }
+}
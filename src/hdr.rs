@@ -0,0 +1,116 @@
+use image::{Rgb, Rgb32FImage};
+use nalgebra_glm::Vec3;
+use crate::color::Color;
+use crate::framebuffer::Framebuffer;
+use crate::tonemap::ToneMap;
+
+// Imagen en punto flotante lineal (sin recortar a 0..1) para exportar a EXR y componer
+pub struct HdrImage {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<Vec3>,
+    pub frames: u32, // Cuadros acumulados; pixels guarda la suma, average() el promedio
+}
+
+impl HdrImage {
+    pub fn new(width: usize, height: usize) -> Self {
+        HdrImage {
+            width,
+            height,
+            pixels: vec![Vec3::new(0.0, 0.0, 0.0); width * height],
+            frames: 0,
+        }
+    }
+
+    // Suma un cuadro de radiancia (mismo tamaño) a la acumulación
+    pub fn accumulate(&mut self, frame: &[Vec3]) {
+        assert_eq!(frame.len(), self.pixels.len(), "The frame must match the accumulation size");
+        for (sum, sample) in self.pixels.iter_mut().zip(frame) {
+            *sum += sample;
+        }
+        self.frames += 1;
+    }
+
+    // Descarta lo acumulado (la cámara o la escena cambiaron)
+    pub fn reset(&mut self) {
+        self.pixels.iter_mut().for_each(|sum| *sum = Vec3::new(0.0, 0.0, 0.0));
+        self.frames = 0;
+    }
+
+    pub fn average(&self) -> Vec<Vec3> {
+        let frames = self.frames.max(1) as f32;
+        self.pixels.iter().map(|sum| sum / frames).collect()
+    }
+
+    // Promedio logarítmico de la luminancia (Rec. 709) de la radiancia promedio, sin exposición
+    // ni curva: un brillo mayor a 1.0 cuenta como tal en lugar de recortarse
+    pub fn log_average_luminance(&self) -> f32 {
+        if self.pixels.is_empty() {
+            return 0.0;
+        }
+        let delta = 1e-4;
+        let sum: f32 = self
+            .average()
+            .iter()
+            .map(|radiance| (delta + 0.2126 * radiance.x + 0.7152 * radiance.y + 0.0722 * radiance.z).ln())
+            .sum();
+        (sum / self.pixels.len() as f32).exp()
+    }
+
+    pub fn to_image(&self) -> Rgb32FImage {
+        let average = self.average();
+        Rgb32FImage::from_fn(self.width as u32, self.height as u32, |x, y| {
+            let pixel = average[y as usize * self.width + x as usize];
+            Rgb([pixel.x, pixel.y, pixel.z])
+        })
+    }
+
+    // Cuadro de 8 bits para mostrar o guardar en PNG: promedio * exposure pasado por la curva
+    pub fn to_framebuffer(&self, tone_map: ToneMap, exposure: f32) -> Framebuffer {
+        let mut framebuffer = Framebuffer::new(self.width, self.height);
+        for (pixel, radiance) in framebuffer.buffer.iter_mut().zip(self.average()) {
+            let mapped = tone_map.apply(&(radiance * exposure));
+            *pixel = Color::from_rgb_f32(mapped.x, mapped.y, mapped.z).to_hex();
+        }
+        framebuffer
+    }
+
+    // EXR lineal en f32: conserva los valores mayores a 1.0
+    pub fn save_exr(&self, file_path: &str) -> image::ImageResult<()> {
+        self.to_image().save(file_path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn values_above_one_survive_an_exr_round_trip() {
+        let mut hdr = HdrImage::new(2, 2);
+        let mut frame = vec![Vec3::new(0.25, 0.5, 1.0); 4];
+        frame[3] = Vec3::new(3.5, 0.0, 12.25);
+        hdr.accumulate(&frame);
+
+        let path = std::env::temp_dir().join(format!("raytracing_roundtrip_{}.exr", std::process::id()));
+        hdr.save_exr(path.to_str().unwrap()).unwrap();
+        let reloaded = image::open(&path).unwrap().to_rgb32f();
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(reloaded.get_pixel(1, 1).0, [3.5, 0.0, 12.25]);
+        assert_eq!(reloaded.get_pixel(0, 0).0, [0.25, 0.5, 1.0]);
+    }
+
+    #[test]
+    fn accumulation_averages_frames_until_reset() {
+        let mut hdr = HdrImage::new(1, 1);
+        hdr.accumulate(&[Vec3::new(1.0, 2.0, 3.0)]);
+        hdr.accumulate(&[Vec3::new(3.0, 2.0, 1.0)]);
+        assert_eq!(hdr.average(), vec![Vec3::new(2.0, 2.0, 2.0)]);
+
+        hdr.reset();
+        assert_eq!(hdr.frames, 0);
+        hdr.accumulate(&[Vec3::new(5.0, 5.0, 5.0)]);
+        assert_eq!(hdr.average(), vec![Vec3::new(5.0, 5.0, 5.0)]);
+    }
+}
@@ -0,0 +1,49 @@
+use crate::camera::Camera;
+use crate::RenderSettings;
+
+// Líneas del HUD de depuración: estado de la cámara y del render
+pub fn hud_lines(camera: &Camera, settings: &RenderSettings, frame_time: f32) -> Vec<String> {
+    let (yaw, pitch) = camera.yaw_pitch();
+    // Rayos por píxel: aa_samples por motion_samples (con umbral adaptativo, el máximo)
+    let motion_samples = if settings.shutter > 0.0 { settings.motion_samples.max(1) } else { 1 };
+    let samples = settings.aa_samples.max(1) * motion_samples;
+    vec![
+        format!("EYE: ({:.2}, {:.2}, {:.2})", camera.eye.x, camera.eye.y, camera.eye.z),
+        format!("CENTER: ({:.2}, {:.2}, {:.2})", camera.center.x, camera.center.y, camera.center.z),
+        format!("YAW: {:.1}  PITCH: {:.1}", yaw.to_degrees(), pitch.to_degrees()),
+        format!("FOV: {:.1}", settings.fov.to_degrees()),
+        format!("FRAME: {:.1} MS", frame_time * 1000.0),
+        format!("SAMPLES: {}", samples),
+        format!("TONE MAP: {}", settings.tone_map.name().to_uppercase()),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lines_show_camera_state_and_render_stats() {
+        use nalgebra_glm::Vec3;
+
+        let camera = Camera::new(Vec3::new(0.0, 3.0, 3.0), Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+        let settings = RenderSettings { shutter: 0.5, motion_samples: 6, ..RenderSettings::default() };
+        assert_eq!(
+            hud_lines(&camera, &settings, 0.0125),
+            vec![
+                "EYE: (0.00, 3.00, 3.00)",
+                "CENTER: (0.00, 0.00, 0.00)",
+                "YAW: 90.0  PITCH: -45.0",
+                "FOV: 60.0",
+                "FRAME: 12.5 MS",
+                "SAMPLES: 6",
+                "TONE MAP: CLAMP",
+            ]
+        );
+
+        // Con anti-aliasing las muestras de movimiento se multiplican por las del píxel
+        let samples_line = |settings: &RenderSettings| hud_lines(&camera, settings, 0.0)[5].clone();
+        assert_eq!(samples_line(&RenderSettings { aa_samples: 4, ..settings.clone() }), "SAMPLES: 24");
+        assert_eq!(samples_line(&RenderSettings { aa_samples: 4, shutter: 0.0, ..settings }), "SAMPLES: 4");
+    }
+}
@@ -0,0 +1,108 @@
+use nalgebra_glm::Vec3;
+use crate::aabb::Aabb;
+use crate::camera::Camera;
+
+// Eje "arriba" del archivo de origen; la escena usa Y hacia arriba
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UpAxis {
+    Y,
+    Z, // Común en OBJ exportados desde herramientas CAD / Blender sin conversión
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Handedness {
+    Right, // La de la escena
+    Left,
+}
+
+// Conversión de coordenadas externas a las de la escena (Y arriba, mano derecha), aplicable a
+// puntos, direcciones, cajas y cámaras importadas
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ImportTransform {
+    pub up_axis: UpAxis,
+    pub handedness: Handedness,
+}
+
+impl Default for ImportTransform {
+    fn default() -> Self {
+        ImportTransform {
+            up_axis: UpAxis::Y,
+            handedness: Handedness::Right,
+        }
+    }
+}
+
+impl ImportTransform {
+    pub fn new(up_axis: UpAxis, handedness: Handedness) -> Self {
+        ImportTransform { up_axis, handedness }
+    }
+
+    // Las direcciones se convierten igual que los puntos: la transformación es lineal
+    pub fn apply(&self, v: &Vec3) -> Vec3 {
+        // Mano izquierda -> derecha: se invierte la profundidad del archivo de origen
+        let v = match self.handedness {
+            Handedness::Right => *v,
+            Handedness::Left => match self.up_axis {
+                UpAxis::Y => Vec3::new(v.x, v.y, -v.z),
+                UpAxis::Z => Vec3::new(v.x, -v.y, v.z),
+            },
+        };
+        match self.up_axis {
+            UpAxis::Y => v,
+            // Rotación de -90° alrededor de X: +Z pasa a +Y y +Y pasa a -Z
+            UpAxis::Z => Vec3::new(v.x, v.z, -v.y),
+        }
+    }
+
+    // true si la conversión es un reflejo (determinante negativo): el producto cruz de dos aristas
+    // transformadas apunta al lado contrario, así que hay que invertir el orden de las caras
+    pub fn flips_winding(&self) -> bool {
+        self.handedness == Handedness::Left
+    }
+
+    // Caja alineada a los ejes: la conversión solo permuta / invierte ejes, así que sigue alineada
+    pub fn apply_aabb(&self, bounds: &Aabb) -> Aabb {
+        let a = self.apply(&bounds.min);
+        let b = self.apply(&bounds.max);
+        Aabb::new(
+            Vec3::new(a.x.min(b.x), a.y.min(b.y), a.z.min(b.z)),
+            Vec3::new(a.x.max(b.x), a.y.max(b.y), a.z.max(b.z)),
+        )
+    }
+
+    pub fn apply_camera(&self, camera: &Camera) -> Camera {
+        let mut converted = camera.clone();
+        converted.look_at(self.apply(&camera.eye), self.apply(&camera.center), self.apply(&camera.up));
+        converted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn z_up_triangle_is_placed_upright() {
+        let triangle = [Vec3::new(0.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 2.0)];
+        let converted = triangle.map(|v| ImportTransform::new(UpAxis::Z, Handedness::Right).apply(&v));
+        assert_eq!(converted, [Vec3::new(0.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 2.0, 0.0)]);
+
+        // Un punto al frente (+Y en Z arriba) queda al frente de la cámara por defecto (-Z)
+        assert_eq!(ImportTransform::new(UpAxis::Z, Handedness::Right).apply(&Vec3::new(0.0, 3.0, 0.0)), Vec3::new(0.0, 0.0, -3.0));
+        assert_eq!(ImportTransform::new(UpAxis::Z, Handedness::Left).apply(&Vec3::new(0.0, 3.0, 0.0)), Vec3::new(0.0, 0.0, 3.0));
+    }
+
+    #[test]
+    fn only_mirroring_conversions_flip_the_winding() {
+        let determinant = |transform: ImportTransform| {
+            let [x, y, z] = [Vec3::x(), Vec3::y(), Vec3::z()].map(|axis| transform.apply(&axis));
+            x.cross(&y).dot(&z)
+        };
+        for up_axis in [UpAxis::Y, UpAxis::Z] {
+            for handedness in [Handedness::Right, Handedness::Left] {
+                let transform = ImportTransform::new(up_axis, handedness);
+                assert_eq!(transform.flips_winding(), determinant(transform) < 0.0);
+            }
+        }
+    }
+}
@@ -0,0 +1,115 @@
+use nalgebra_glm::Vec3;
+use crate::aabb::Aabb;
+use crate::cube::Cube;
+use crate::ray_intersect::{RayIntersect, Intersect};
+
+// Instancia ligera: referencia un cubo plantilla y solo guarda su traslación
+#[derive(Debug, Clone, Copy)]
+pub struct CubeInstance {
+    pub template_index: usize, // Índice del cubo plantilla
+    pub transform: Vec3,       // Traslación de la instancia
+}
+
+impl CubeInstance {
+    pub fn new(template_index: usize, transform: Vec3) -> Self {
+        CubeInstance {
+            template_index,
+            transform,
+        }
+    }
+
+    pub fn ray_intersect<'a>(&self, templates: &'a [Cube], ray_origin: &Vec3, ray_dir: &Vec3) -> Intersect<'a> {
+        self.ray_intersect_from(templates, ray_origin, ray_dir, 0.0)
+    }
+
+    // Igual, aceptando solo impactos con distancia >= t_min
+    pub fn ray_intersect_from<'a>(&self, templates: &'a [Cube], ray_origin: &Vec3, ray_dir: &Vec3, t_min: f32) -> Intersect<'a> {
+        // Llevar el rayo al espacio de la plantilla (solo traslación, la dirección no cambia)
+        let local_origin = ray_origin - self.transform;
+        let mut intersect = templates[self.template_index].ray_intersect_from(&local_origin, ray_dir, t_min);
+
+        // Regresar el punto de impacto al espacio del mundo
+        if intersect.is_intersecting {
+            intersect.point += self.transform;
+        }
+        intersect
+    }
+}
+
+// Conjunto de plantillas compartidas por muchas instancias (ej. la base de césped)
+#[derive(Default)]
+pub struct InstancedCubes {
+    pub templates: Vec<Cube>,
+    pub instances: Vec<CubeInstance>,
+}
+
+impl InstancedCubes {
+    pub fn new() -> Self {
+        InstancedCubes {
+            templates: Vec::new(),
+            instances: Vec::new(),
+        }
+    }
+
+    pub fn add_template(&mut self, template: Cube) -> usize {
+        self.templates.push(template);
+        self.templates.len() - 1
+    }
+
+    pub fn add_instance(&mut self, template_index: usize, transform: Vec3) {
+        self.instances.push(CubeInstance::new(template_index, transform));
+    }
+
+    pub fn instance_bounds(&self, index: usize) -> Aabb {
+        let instance = &self.instances[index];
+        let template = &self.templates[instance.template_index];
+        Aabb::new(template.min + instance.transform, template.max + instance.transform)
+    }
+}
+
+impl RayIntersect for InstancedCubes {
+    fn ray_intersect(&self, ray_origin: &Vec3, ray_dir: &Vec3) -> Intersect<'_> {
+        let mut closest = Intersect::empty();
+        let mut zbuffer = f32::INFINITY;
+
+        for instance in &self.instances {
+            let i = instance.ray_intersect(&self.templates, ray_origin, ray_dir);
+            if i.is_intersecting && i.distance < zbuffer {
+                zbuffer = i.distance;
+                closest = i;
+            }
+        }
+
+        closest
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn translated_instance_is_hit_where_the_translated_cube_would_be() {
+        let material = crate::material::Material::new(crate::color::Color::new(200, 200, 200), 10.0, [0.9, 0.1, 0.0, 0.0], 1.0, None, 0.0);
+        let template = Cube { min: Vec3::new(-1.0, -1.0, -1.0), max: Vec3::new(1.0, 1.0, 1.0), material: material.clone(), inverted: false };
+        let translated = Cube { min: Vec3::new(4.0, -1.0, -1.0), max: Vec3::new(6.0, 1.0, 1.0), material, inverted: false };
+        let mut group = InstancedCubes::new();
+        let index = group.add_template(template);
+        group.add_instance(index, Vec3::new(5.0, 0.0, 0.0));
+
+        for (origin, direction) in [
+            (Vec3::new(5.5, 0.2, 6.0), Vec3::new(0.0, 0.0, -1.0)),
+            (Vec3::new(0.0, 0.5, 0.0), Vec3::new(1.0, 0.0, 0.0)),
+            (Vec3::new(9.0, 4.0, 3.0), Vec3::new(-1.0, -1.0, -0.7)),
+        ] {
+            let instance_hit = group.ray_intersect(&origin, &direction);
+            let cube_hit = translated.ray_intersect(&origin, &direction);
+            assert!(instance_hit.is_intersecting && cube_hit.is_intersecting);
+            assert!((instance_hit.distance - cube_hit.distance).abs() < 1e-5);
+            assert!((instance_hit.point - cube_hit.point).magnitude() < 1e-5);
+            assert_eq!(instance_hit.normal, cube_hit.normal);
+        }
+        // En el origen (donde está la plantilla) la instancia no existe
+        assert!(!group.ray_intersect(&Vec3::new(0.0, 0.0, 6.0), &Vec3::new(0.0, 0.0, -1.0)).is_intersecting);
+    }
+}
@@ -0,0 +1,99 @@
+use nalgebra_glm::Vec3;
+use std::str::FromStr;
+
+// Lectura y escritura mínima de JSON para los archivos que escribe el propio programa
+// (sidecar de render, marcadores de cámara): objetos planos con números, arreglos, cadenas y null
+
+// JSON no admite infinitos: far_clip infinito se guarda como null
+pub(crate) fn json_f32(value: f32) -> String {
+    if value.is_finite() {
+        format!("{}", value)
+    } else {
+        "null".to_string()
+    }
+}
+
+pub(crate) fn json_vec3(v: &Vec3) -> String {
+    format!("[{}, {}, {}]", json_f32(v.x), json_f32(v.y), json_f32(v.z))
+}
+
+pub(crate) fn parse_f32(value: &str) -> Option<f32> {
+    value.parse().ok()
+}
+
+// Campo opcional: null -> Some(None); un valor que no se puede leer -> None (error)
+pub(crate) fn parse_optional<T: FromStr>(value: &str) -> Option<Option<T>> {
+    if value == "null" {
+        Some(None)
+    } else {
+        value.parse().ok().map(Some)
+    }
+}
+
+// Arreglo de valores del tipo pedido: con T entero, 2.5 o -1 son un error
+pub(crate) fn parse_array<T: FromStr>(value: &str) -> Option<Vec<T>> {
+    let inner = value.strip_prefix('[')?.strip_suffix(']')?;
+    inner.split(',').map(|n| n.trim().parse().ok()).collect()
+}
+
+pub(crate) fn parse_vec3(value: &str) -> Option<Vec3> {
+    match parse_array(value)?.as_slice() {
+        [x, y, z] => Some(Vec3::new(*x, *y, *z)),
+        _ => None,
+    }
+}
+
+// Separa los campos por comas que no estén dentro de arreglos ni cadenas
+pub(crate) fn split_top_level(body: &str) -> Vec<&str> {
+    let mut fields = Vec::new();
+    let mut depth = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut start = 0;
+    for (i, c) in body.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            '[' if !in_string => depth += 1,
+            ']' if !in_string => depth -= 1,
+            ',' if !in_string && depth == 0 => {
+                fields.push(&body[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    if !body[start..].trim().is_empty() {
+        fields.push(&body[start..]);
+    }
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn top_level_split_ignores_commas_inside_arrays_and_strings() {
+        let fields = split_top_level(r#""a": [1, 2, 3], "b": "x, \"y\"", "c": null"#);
+        assert_eq!(fields.len(), 3);
+        assert_eq!(fields[0].trim(), r#""a": [1, 2, 3]"#);
+        assert_eq!(fields[2].trim(), r#""c": null"#);
+    }
+
+    #[test]
+    fn values_round_trip_and_null_is_only_accepted_as_optional() {
+        let v = Vec3::new(1.5, -2.0, 0.125);
+        assert_eq!(parse_vec3(&json_vec3(&v)), Some(v));
+        assert_eq!(json_f32(f32::INFINITY), "null");
+        assert_eq!(parse_f32("null"), None);
+        assert_eq!(parse_optional::<f32>("null"), Some(None));
+        assert_eq!(parse_optional::<f32>("2.5"), Some(Some(2.5)));
+        assert_eq!(parse_optional::<f32>("abc"), None);
+        assert_eq!(parse_array::<f32>("[1, x]"), None);
+        assert_eq!(parse_array::<usize>("[2, 3, 20, 15]"), Some(vec![2, 3, 20, 15]));
+        assert_eq!(parse_array::<usize>("[2, 3.5]"), None);
+        assert_eq!(parse_array::<usize>("[2, -3]"), None);
+    }
+}
@@ -0,0 +1,2176 @@
+use core::f32;
+use nalgebra_glm::Vec3;
+use std::f32::consts::PI;
+use image::RgbImage;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+pub mod framebuffer;
+use framebuffer::Framebuffer;
+
+pub mod font;
+
+pub mod hud;
+
+pub mod overlay;
+
+pub mod exposure;
+
+pub mod hdr;
+use hdr::HdrImage;
+
+pub mod tonemap;
+use tonemap::ToneMap;
+
+pub mod aabb;
+
+pub mod bvh;
+
+pub mod bounds_overlay;
+
+pub mod grid_overlay;
+
+pub mod cube;
+
+pub mod instance;
+
+pub mod quad;
+
+pub mod meshing;
+
+pub mod sdf;
+
+pub mod specular_aa;
+
+pub mod particle;
+
+pub mod scene;
+use scene::Scene;
+
+pub mod ray_intersect;
+use ray_intersect::Intersect;
+
+pub mod color;
+use color::Color;
+
+pub mod camera;
+use camera::Camera;
+
+pub mod camera_path;
+
+pub mod batch;
+
+pub mod clock;
+
+pub mod filter;
+
+pub mod adaptive;
+use adaptive::PixelStats;
+use filter::PixelFilter;
+
+pub mod passes;
+
+pub mod denoise;
+use passes::{NormalSpace, RenderPasses};
+
+pub mod reprojection;
+
+pub mod stereo;
+
+pub mod material;
+use material::Material;
+
+pub mod presets;
+
+pub mod editor;
+
+pub mod light;
+use light::Light;
+
+pub mod texture;
+
+pub mod sky;
+
+pub mod fog;
+
+pub mod sampling;
+
+pub mod json;
+
+pub mod sidecar;
+
+pub mod import;
+
+pub mod trace;
+use trace::{BounceKind, BounceTrace, HitTrace, LightTrace, PixelTrace, ShadeTrace};
+use texture::Texture;
+
+const BIAS: f32 = 0.001;
+const BLOCKER_SEARCH_SAMPLES: u32 = 4; // Rayos para estimar la distancia al bloqueador en sombras suaves
+const SKYBOX_COLOR: Color = Color::new(135, 206, 235); // Light sky blue
+const SUN_SPECULAR_EXPONENT: f32 = 2000.0; // Brillo del sol en superficies pulidas: muy concentrado
+const MAX_DEPTH: u32 = 3; // Rebotes de reflexión/refracción; trace_ray y record_bounces cortan en el mismo punto
+
+
+fn reflect(incident: &Vec3, normal: &Vec3) -> Vec3 {
+    incident - 2.0 * incident.dot(normal) * normal
+}
+
+// Dirección refractada (Snell) al cruzar una superficie con índice `refractive_index` desde el aire;
+// si el rayo sale del material se invierten normal e índices. None = reflexión total interna
+pub fn refract(incident: &Vec3, normal: &Vec3, refractive_index: f32) -> Option<Vec3> {
+    let mut cos_incident = incident.dot(normal).clamp(-1.0, 1.0);
+    let (eta, n) = if cos_incident < 0.0 {
+        cos_incident = -cos_incident;
+        (1.0 / refractive_index, *normal)
+    } else {
+        (refractive_index, -normal)
+    };
+    let k = 1.0 - eta * eta * (1.0 - cos_incident * cos_incident);
+    if k < 0.0 {
+        None
+    } else {
+        Some((incident * eta + n * (eta * cos_incident - k.sqrt())).normalize())
+    }
+}
+
+// Una dirección refractada por canal (R, G, B) según la dispersión del material
+pub fn refract_dispersed(incident: &Vec3, normal: &Vec3, material: &Material) -> [Option<Vec3>; 3] {
+    material
+        .channel_refractive_indices()
+        .map(|refractive_index| refract(incident, normal, refractive_index))
+}
+
+// Lóbulo especular anisotrópico (tipo Ward, sin normalizar para quedar en 0..1 como el de Phong):
+// el brillo se extiende más en la dirección con mayor rugosidad
+fn anisotropic_specular(intersect: &Intersect, view_dir: &Vec3, light_dir: &Vec3, normal_length: f32) -> f32 {
+    let material = &intersect.material;
+    let normal = intersect.normal;
+    let half = (view_dir + light_dir).normalize();
+    let n_dot_h = normal.dot(&half);
+    if n_dot_h <= 0.0 || normal.dot(light_dir) <= 0.0 {
+        return 0.0;
+    }
+
+    // Tangente del material proyectada sobre la superficie (o la de la superficie si no hay / es paralela a la normal)
+    let tangent = material
+        .tangent
+        .map(|t| t - normal * normal.dot(&t))
+        .filter(|t| t.magnitude() > 1e-4)
+        .map(|t| t.normalize())
+        .unwrap_or_else(|| intersect.tangent_basis().0);
+    let bitangent = normal.cross(&tangent);
+
+    let hu = half.dot(&tangent) / specular_aa::widened_roughness(material.roughness_u, normal_length);
+    let hv = half.dot(&bitangent) / specular_aa::widened_roughness(material.roughness_v, normal_length);
+    (-(hu * hu + hv * hv) / (n_dot_h * n_dot_h)).exp()
+}
+
+fn cast_shadow(
+    intersect: &Intersect,
+    scene: &Scene,
+    light_dir: &Vec3,
+    light_distance: f32,
+    time: f32,
+) -> f32 {
+    shadow_along(&offset_origin(intersect, light_dir), scene, light_dir, light_distance, time)
+}
+
+// Sombra (0 = sin bloqueo, 1 = opaca) del tramo desde `shadow_ray_origin` hasta la luz; los
+// bloqueadores transparentes dejan pasar parte. El bloqueador más cercano decide si la luz se
+// atenúa por emisión o por transparencia. También la usa la niebla para sus haces de luz
+pub(crate) fn shadow_along(
+    shadow_ray_origin: &Vec3,
+    scene: &Scene,
+    light_dir: &Vec3,
+    light_distance: f32,
+    time: f32,
+) -> f32 {
+    let mut shadow_intensity = 0.0;
+
+    if let Some(shadow_intersect) = scene.nearest_shadow_blocker(shadow_ray_origin, light_dir, light_distance, time) {
+        // Si el objeto intersectado emite luz, reduce la sombra, pero no la elimina completamente
+        if let Some(_emission) = shadow_intersect.material.emission_color {
+            let distance_ratio = shadow_intersect.distance / light_distance;
+            let emission_intensity = 1.0 / (distance_ratio * distance_ratio);
+            shadow_intensity = emission_intensity; // Ajustar la sombra según la intensidad de la emisión
+        } else if shadow_intersect.material.albedo[3] <= 0.0 {
+            shadow_intensity = 1.0;
+        } else {
+            // Bloqueador transparente: la luz atraviesa cada capa (ej. dos vidrios) perdiendo
+            // una parte en cada una, hasta la primera superficie opaca
+            let mut transmittance = 1.0;
+            for layer in scene.intersect_all_at(shadow_ray_origin, light_dir, time) {
+                if layer.distance >= light_distance {
+                    break;
+                }
+                transmittance *= layer.material.albedo[3].clamp(0.0, 1.0);
+                if transmittance <= 0.0 {
+                    break;
+                }
+            }
+            shadow_intensity = 1.0 - transmittance;
+        }
+    }
+
+    shadow_intensity
+}
+
+// Sombra de una luz con área (disco de radio light.radius) con penumbra que se endurece en el contacto:
+// se estima la distancia al bloqueador y el radio de muestreo crece con la separación
+// entre bloqueador y receptor, así la sombra es nítida junto al objeto y difusa lejos de él
+fn cast_soft_shadow(intersect: &Intersect, scene: &Scene, light: &Light, time: f32) -> f32 {
+    let to_light = light.position - intersect.point;
+    let light_distance = to_light.magnitude();
+    let light_dir = to_light / light_distance;
+    if light.radius <= 0.0 || light.shadow_samples <= 1 {
+        return cast_shadow(intersect, scene, &light_dir, light_distance, time);
+    }
+
+    let (tangent, bitangent) = sampling::tangent_basis(&light_dir);
+    let point_on_light = |radius: f32| {
+        // Punto uniforme en el disco de la luz, perpendicular a la dirección hacia ella
+        let r = radius * sampling::random_f32().sqrt();
+        let theta = sampling::random_f32() * 2.0 * PI;
+        light.position + tangent * (r * theta.cos()) + bitangent * (r * theta.sin())
+    };
+
+    // Búsqueda de bloqueadores sobre todo el disco: distancia promedio desde el receptor al
+    // bloqueador más cercano de cada rayo (con placas apiladas manda la más próxima)
+    let origin = offset_origin(intersect, &light_dir);
+    let mut blocker_distance = 0.0;
+    let mut blockers = 0;
+    for i in 0..BLOCKER_SEARCH_SAMPLES {
+        let target = if i == 0 { light.position } else { point_on_light(light.radius) };
+        let dir = (target - origin).normalize();
+        if let Some(blocker) = scene.nearest_shadow_blocker(&origin, &dir, (target - origin).magnitude(), time) {
+            blocker_distance += blocker.distance;
+            blockers += 1;
+        }
+    }
+    if blockers == 0 {
+        return 0.0;
+    }
+    let blocker_distance = blocker_distance / blockers as f32;
+
+    // Semejanza de triángulos: con el bloqueador pegado al receptor el radio efectivo tiende a 0
+    let gap_ratio = blocker_distance / (light_distance - blocker_distance).max(BIAS);
+    let sample_radius = light.radius * gap_ratio.min(1.0);
+
+    let mut shadow = 0.0;
+    for _ in 0..light.shadow_samples {
+        let target = point_on_light(sample_radius);
+        let to_target = target - intersect.point;
+        let distance = to_target.magnitude();
+        shadow += cast_shadow(intersect, scene, &(to_target / distance), distance, time);
+    }
+    shadow / light.shadow_samples as f32
+}
+
+// Mapeo equirectangular de una dirección a coordenadas UV del skybox
+fn skybox_uv(ray_direction: &Vec3) -> (f32, f32) {
+    let dir = ray_direction.normalize();
+    let u = 0.5 + (dir.x.atan2(dir.z) / (2.0 * PI));
+    let v = 0.5 - (dir.y.asin() / PI);
+    (u, v)
+}
+
+// Radiancia del entorno en la dirección del rayo (puede superar 1.0 con mapas HDR)
+pub fn get_skybox_radiance(ray_direction: &Vec3, skybox: &Texture) -> Vec3 {
+    let (u, v) = skybox_uv(ray_direction);
+    skybox.get_radiance_bilinear_wrapped(u, v)
+}
+
+// Gira una dirección alrededor del eje Y; en el mapeo equirectangular desplaza u en angle / 2π
+fn rotate_y(direction: &Vec3, angle: f32) -> Vec3 {
+    let (sin, cos) = angle.sin_cos();
+    Vec3::new(
+        direction.x * cos + direction.z * sin,
+        direction.y,
+        -direction.x * sin + direction.z * cos,
+    )
+}
+
+// Radiancia de los rayos que no chocan: textura del skybox (sin recortar si es HDR), cielo
+// analítico según el sol, o un color fijo
+fn background_radiance(ray_direction: &Vec3, scene: &Scene) -> Vec3 {
+    match (&scene.skybox, &scene.sun_direction) {
+        (Some(skybox), _) => get_skybox_radiance(&rotate_y(ray_direction, scene.skybox_yaw), skybox),
+        // El disco del sol solo va sobre el cielo analítico: una textura ya trae su propio sol.
+        // hits_sun_disc descarta el sol bajo el horizonte (de noche)
+        (None, Some(sun_direction)) if sky::hits_sun_disc(ray_direction, sun_direction) => scene.sun_color.to_radiance(),
+        (None, Some(sun_direction)) => sky::analytic_sky_color(ray_direction, sun_direction).to_radiance(),
+        (None, None) => SKYBOX_COLOR.to_radiance(),
+    }
+}
+
+fn generate_random_direction() -> Vec3 {
+    let theta = sampling::random_f32() * 2.0 * std::f32::consts::PI;
+    let z: f32 = sampling::random_f32() * 2.0 - 1.0;  // Random valor entre -1 y 1
+    let r = (1.0 - z * z).sqrt();
+    let x = r * theta.cos();
+    let y = r * theta.sin();
+    Vec3::new(x, y, z).normalize()
+}
+
+fn offset_origin(intersect: &Intersect, direction: &Vec3) -> Vec3 {
+    let offset = intersect.normal * BIAS;
+    if direction.dot(&intersect.normal) < 0.0 {
+        intersect.point - offset
+    } else {
+        intersect.point + offset
+    }
+}
+
+
+pub fn cast_ray(
+    ray_origin: &Vec3,
+    ray_direction: &Vec3,
+    scene: &Scene,
+    settings: &RenderSettings,
+    depth: u32,
+) -> Color {
+    cast_ray_at(ray_origin, ray_direction, scene, settings, depth, 0.0)
+}
+
+// Igual que cast_ray, pero con los objetos en movimiento ubicados en el instante `time`
+pub fn cast_ray_at(
+    ray_origin: &Vec3,
+    ray_direction: &Vec3,
+    scene: &Scene,
+    settings: &RenderSettings,
+    depth: u32,
+    time: f32,
+) -> Color {
+    display_color(&trace_ray(ray_origin, ray_direction, scene, settings, depth, time, None), settings)
+}
+
+// Rayo de cámara que pasa por la posición de pantalla screen_uv = (x / ancho, y / alto): si no
+// toca nada y la escena tiene fondo plano (backdrop), se ve el texel de esa posición
+pub fn cast_camera_ray_at(
+    ray_origin: &Vec3,
+    ray_direction: &Vec3,
+    screen_uv: (f32, f32),
+    scene: &Scene,
+    settings: &RenderSettings,
+    time: f32,
+) -> Color {
+    display_color(&trace_ray(ray_origin, ray_direction, scene, settings, 0, time, Some(screen_uv)), settings)
+}
+
+// Texel del fondo plano en (u, v) de pantalla; v crece hacia abajo igual que las filas de la imagen
+fn backdrop_radiance(backdrop: &Texture, screen_u: f32, screen_v: f32) -> Vec3 {
+    let x = (screen_u.clamp(0.0, 1.0) * (backdrop.width as f32 - 1.0)).round() as usize;
+    let y = (screen_v.clamp(0.0, 1.0) * (backdrop.height as f32 - 1.0)).round() as usize;
+    backdrop.get_radiance(x, y)
+}
+
+fn trace_ray(
+    ray_origin: &Vec3,
+    ray_direction: &Vec3,
+    scene: &Scene,
+    settings: &RenderSettings,
+    depth: u32,
+    time: f32,
+    screen_uv: Option<(f32, f32)>,
+) -> Vec3 {
+    if depth >= MAX_DEPTH {
+        return SKYBOX_COLOR.to_radiance();
+    }
+
+    // Comprobación de intersección con los objetos visibles; el recorte near/far solo aplica a los rayos de cámara
+    let intersect = if depth == 0 {
+        scene.intersect_clipped(ray_origin, ray_direction, time, settings.near_clip, settings.far_clip)
+    } else {
+        scene.intersect_at(ray_origin, ray_direction, time)
+    };
+    radiance_at(ray_origin, ray_direction, &intersect, scene, settings, depth, time, screen_uv)
+}
+
+// Rayo de cámara: la radiancia más los datos del primer impacto para los pases auxiliares,
+// con una sola intersección para ambos
+fn trace_camera_ray(
+    ray_origin: &Vec3,
+    ray_direction: &Vec3,
+    scene: &Scene,
+    settings: &RenderSettings,
+    time: f32,
+    screen_uv: (f32, f32),
+) -> (Vec3, Option<SurfaceSample>) {
+    let intersect = scene.intersect_clipped(ray_origin, ray_direction, time, settings.near_clip, settings.far_clip);
+    let surface = intersect.is_intersecting.then(|| SurfaceSample {
+        point: intersect.point,
+        normal: intersect.normal,
+        albedo: intersect.material.get_diffuse_color(intersect.u, intersect.v, &intersect.point).to_radiance(),
+        depth: intersect.distance,
+        material_id: intersect.material.id(),
+    });
+    let radiance = radiance_at(ray_origin, ray_direction, &intersect, scene, settings, 0, time, Some(screen_uv));
+    (radiance, surface)
+}
+
+// Radiancia que llega por el rayo dado su impacto (o su falta de impacto) ya calculado
+#[allow(clippy::too_many_arguments)]
+fn radiance_at(
+    ray_origin: &Vec3,
+    ray_direction: &Vec3,
+    intersect: &Intersect,
+    scene: &Scene,
+    settings: &RenderSettings,
+    depth: u32,
+    time: f32,
+    screen_uv: Option<(f32, f32)>,
+) -> Vec3 {
+    // El fondo plano solo reemplaza al cielo en los rayos de cámara; reflejos y
+    // refracciones siguen viendo el entorno por dirección
+    let miss_color = || match (&scene.backdrop, screen_uv) {
+        (Some(backdrop), Some((u, v))) => backdrop_radiance(backdrop, u, v),
+        _ => background_radiance(ray_direction, scene),
+    };
+
+    let (near, far) = if depth == 0 { (settings.near_clip, settings.far_clip) } else { (0.0, f32::INFINITY) };
+    let color = if !intersect.is_intersecting {
+        miss_color()
+    } else if settings.preview {
+        intersect.material.get_diffuse_radiance(intersect.u, intersect.v, &intersect.point)
+    } else if intersect.material.albedo[3] > 0.0 && intersect.material.refractive_index > 1.0 {
+        refract_transparent(ray_origin, ray_direction, intersect, scene, settings, depth, time)
+    } else if intersect.material.albedo[3] > 0.0 {
+        composite_transparent(ray_origin, ray_direction, scene, settings, time, (near, far), miss_color)
+    } else {
+        shade(ray_origin, intersect, scene, settings, time, None)
+    };
+
+    let surface_distance = if intersect.is_intersecting { intersect.distance } else { far };
+    let color = if scene.particles.is_empty() {
+        color
+    } else {
+        scene.composite_particles(ray_origin, ray_direction, time, surface_distance, color)
+    };
+
+    // La niebla solo se marcha en los rayos de cámara: en reflejos sería demasiado costosa
+    match &scene.fog {
+        Some(fog) if depth == 0 => fog.apply(color, ray_origin, ray_direction, surface_distance, scene, time),
+        _ => color,
+    }
+}
+
+// Material transparente con índice de refracción: la luz transmitida sigue el rayo desviado por
+// Snell (uno por canal si hay dispersión, así los bordes se tiñen como en un prisma). Al entrar se
+// mezcla con el color sombreado según albedo[3]; dentro del material el rayo se atenúa por
+// Beer-Lambert hasta la cara de salida, que no se sombrea. Reflexión total interna -> rayo reflejado
+fn refract_transparent(
+    ray_origin: &Vec3,
+    ray_direction: &Vec3,
+    intersect: &Intersect,
+    scene: &Scene,
+    settings: &RenderSettings,
+    depth: u32,
+    time: f32,
+) -> Vec3 {
+    let material = intersect.material;
+    let direction = ray_direction.normalize();
+    let trace_along = |refracted: Option<Vec3>| {
+        let dir = refracted.unwrap_or_else(|| reflect(&direction, &intersect.normal));
+        trace_ray(&offset_origin(intersect, &dir), &dir, scene, settings, depth + 1, time, None)
+    };
+    let transmitted = if material.dispersion > 0.0 {
+        let [r, g, b] = refract_dispersed(&direction, &intersect.normal, material).map(trace_along);
+        Vec3::new(r.x, g.y, b.z)
+    } else {
+        trace_along(refract(&direction, &intersect.normal, material.refractive_index))
+    };
+
+    if direction.dot(&intersect.normal) < 0.0 {
+        let surface = shade(ray_origin, intersect, scene, settings, time, None);
+        let transmission = material.albedo[3].clamp(0.0, 1.0);
+        surface * (1.0 - transmission) + transmitted * transmission
+    } else {
+        let thickness = intersect.distance * ray_direction.magnitude();
+        transmitted * (-material.absorption * thickness).exp()
+    }
+}
+
+// Superficies transparentes apiladas sin refracción (índice 1.0, p. ej. dos ventanas): se componen de adelante hacia atrás.
+// Cada capa aporta su color sombreado con opacidad 1 - albedo[3] y deja pasar albedo[3] de lo que
+// hay detrás, atenuado además por Beer-Lambert según su espesor (exp(-absorption * espesor))
+fn composite_transparent(
+    ray_origin: &Vec3,
+    ray_direction: &Vec3,
+    scene: &Scene,
+    settings: &RenderSettings,
+    time: f32,
+    (near, far): (f32, f32),
+    miss_color: impl Fn() -> Vec3,
+) -> Vec3 {
+    let mut color = Vec3::new(0.0, 0.0, 0.0);
+    let mut transmittance = 1.0;
+
+    let layers = scene.intersect_all_at(ray_origin, ray_direction, time);
+    for layer in layers.iter().filter(|i| i.distance >= near && i.distance < far) {
+        let surface = shade(ray_origin, layer, scene, settings, time, None);
+        let transmission = layer.material.albedo[3].clamp(0.0, 1.0);
+        color += surface * (transmittance * (1.0 - transmission));
+        transmittance *= transmission * (-layer.material.absorption * layer_thickness(layer, ray_direction, scene, time)).exp();
+        if transmittance < 1e-3 {
+            return color;
+        }
+    }
+
+    color + miss_color() * transmittance
+}
+
+// Espesor que recorre el rayo dentro del objeto: distancia hasta la cara de salida. Si lo siguiente
+// que toca no es una cara de salida (normal en el sentido del rayo) la superficie es delgada (un quad)
+fn layer_thickness(layer: &Intersect, ray_direction: &Vec3, scene: &Scene, time: f32) -> f32 {
+    if layer.material.absorption <= 0.0 {
+        return 0.0;
+    }
+    let inside = layer.point + ray_direction.normalize() * BIAS;
+    let exit = scene.intersect_at(&inside, ray_direction, time);
+    if exit.is_intersecting && exit.normal.dot(ray_direction) > 0.0 {
+        exit.distance + BIAS
+    } else {
+        0.0
+    }
+}
+
+// Color en un punto ya intersectado (luces, sombras y emisión); con `trace` se registra cada aporte
+fn shade(
+    ray_origin: &Vec3,
+    intersect: &Intersect,
+    scene: &Scene,
+    settings: &RenderSettings,
+    time: f32,
+    mut trace: Option<&mut ShadeTrace>,
+) -> Vec3 {
+    let ambient_light = scene.ambient_light(&intersect.normal);
+    // Toda la suma se hace en f32 y sin recortar: el paso a 8 bits ocurre una sola vez al final
+    let mut total_light = ambient_light.to_radiance();
+
+    // Anti-aliasing especular: solo vale la pena si el material tiene brillo
+    let normal_length = if settings.specular_aa && intersect.material.albedo[1] > 0.0 {
+        specular_aa::pixel_normal_length(ray_origin, intersect, scene, settings, time)
+    } else {
+        1.0
+    };
+    // El lóbulo ensanchado se escala para conservar su energía (∫cosⁿ ~ 1 / (n + 2))
+    let specular_exponent = specular_aa::toksvig_exponent(intersect.material.specular, normal_length);
+    let specular_energy = (specular_exponent + 2.0) / (intersect.material.specular + 2.0);
+    if let Some(trace) = trace.as_deref_mut() {
+        trace.ambient = ambient_light;
+    }
+
+    // Calcular la luz total desde las luces (o desde una sola, elegida al azar, si hay muchas)
+    for (light_index, selection_scale) in light::select_lights(&scene.lights, &intersect.point, settings.max_direct_lights) {
+        let light = &scene.lights[light_index];
+        let light_dir = (light.position - intersect.point).normalize();
+        let light_distance = (light.position - intersect.point).magnitude();
+        let view_dir = (ray_origin - intersect.point).normalize();
+        let reflect_dir = reflect(&-light_dir, &intersect.normal).normalize();
+
+        // Luces cuyo aporte atenuado es despreciable se saltan antes de lanzar rayos de sombra
+        let incoming_intensity = light.intensity_towards(&intersect.point) * selection_scale;
+        if incoming_intensity < settings.min_light_intensity {
+            continue;
+        }
+
+        // Calcular la intensidad de sombra para esta luz usando cast_shadow (las luces de relleno no proyectan sombra)
+        let shadow_intensity = if light.casts_shadows {
+            cast_soft_shadow(intersect, scene, light, time)
+        } else {
+            0.0
+        };
+        let light_intensity = incoming_intensity * (1.0 - shadow_intensity);
+
+        // Cálculo de la luz difusa
+        let diffuse_intensity = intersect.normal.dot(&light_dir).clamp(0.0, 1.0);
+        let diffuse_color = intersect.material.get_diffuse_radiance(intersect.u, intersect.v, &intersect.point);
+        let diffuse_factor = (intersect.material.albedo[0] * diffuse_intensity * light_intensity).max(0.0);
+
+        // Cálculo de la luz especular
+        let specular_intensity = if !light.specular {
+            0.0
+        } else if intersect.material.is_anisotropic() {
+            anisotropic_specular(intersect, &view_dir, &light_dir, normal_length)
+        } else {
+            view_dir.dot(&reflect_dir).max(0.0).powf(specular_exponent) * specular_energy
+        };
+        let specular_factor = (intersect.material.albedo[1] * specular_intensity * light_intensity).max(0.0);
+
+        // Translucidez: si la luz llega por detrás de la superficie, parte de ella la atraviesa.
+        // Se ignora la sombra porque el rayo de sombra chocaría con el propio objeto.
+        let mut translucent = Vec3::new(0.0, 0.0, 0.0);
+        if intersect.material.translucency > 0.0 && view_dir.dot(&intersect.normal) > 0.0 {
+            let backlight = (-intersect.normal.dot(&light_dir)).max(0.0);
+            translucent = diffuse_color * intersect.material.translucency * backlight * incoming_intensity;
+        }
+
+        if let Some(trace) = trace.as_deref_mut() {
+            trace.lights.push(LightTrace {
+                light_index,
+                distance: light_distance,
+                shadow: shadow_intensity,
+                diffuse_factor,
+                specular_factor,
+            });
+        }
+
+        total_light += diffuse_color * diffuse_factor;
+        total_light += light.color.to_radiance() * specular_factor;
+        total_light += translucent;
+
+        // Sombra teñida: la parte bloqueada de la luz difusa se reemplaza por el color de tinte
+        if let Some(tint) = light.shadow_tint {
+            let shadowed_factor = intersect.material.albedo[0] * diffuse_intensity * incoming_intensity * shadow_intensity;
+            total_light += tint.to_radiance() * shadowed_factor.max(0.0);
+        }
+    }
+
+    // Destello del sol: lóbulo especular muy estrecho hacia la dirección del sol (agua, vidrio, metal).
+    // La luz puntual que hace de sol lleva specular = false para no sumar su brillo dos veces
+    if let Some(sun_direction) = &scene.sun_direction {
+        let sun = sun_direction.normalize();
+        let view_dir = (ray_origin - intersect.point).normalize();
+        if sun.y > 0.0 && intersect.normal.dot(&sun) > 0.0 {
+            let sun_exponent = specular_aa::toksvig_exponent(SUN_SPECULAR_EXPONENT, normal_length);
+            let sun_energy = (sun_exponent + 2.0) / (SUN_SPECULAR_EXPONENT + 2.0);
+            let glint = view_dir.dot(&reflect(&-sun, &intersect.normal)).max(0.0).powf(sun_exponent) * sun_energy;
+            let glint_factor = intersect.material.albedo[1] * glint;
+            if glint_factor > 1e-3 {
+                let origin = offset_origin(intersect, &sun);
+                if scene.nearest_shadow_blocker(&origin, &sun, f32::INFINITY, time).is_none() {
+                    total_light += scene.sun_color.to_radiance() * glint_factor;
+                }
+            }
+        }
+    }
+
+    // Añadir la luz de emisión
+    let num_rays = 16;  // Número de direcciones para emitir luz
+    // Límite de fireflies por muestra, expresado sobre la muestra ya dividida entre num_rays
+    let max_sample_luminance = settings.max_sample_luminance.map(|max_luminance| max_luminance * num_rays as f32);
+    let mut emission_contribution = Vec3::new(0.0, 0.0, 0.0);
+    let mut samples = Vec::with_capacity(num_rays);
+    for (emission_origin, material) in scene.emitters(time) {
+        if let Some(emission) = material.emission_color {
+            // Emisores por debajo del umbral se tratan como no emisivos (no vale la pena muestrearlos)
+            if material.emission_intensity * emission.luminance() < settings.min_emission_luminance {
+                continue;
+            }
+            // La distancia al emisor, y con ella la caída, es la misma para todas sus direcciones
+            let emission_distance = (emission_origin - intersect.point).magnitude();
+            let emission_color = emission.to_radiance() * material.emission_falloff.attenuation(emission_distance);
+            samples.clear();
+
+            for _ in 0..num_rays {
+                let emission_dir = generate_random_direction();
+                let emission_diffuse_intensity = intersect.normal.dot(&emission_dir).max(0.0);
+                let mut sample = emission_color * emission_diffuse_intensity;
+
+                // Limitar muestras atípicamente brillantes (fireflies) antes de acumularlas
+                if let Some(max_luminance) = max_sample_luminance {
+                    sample = sampling::clamp_sample_luminance(sample, max_luminance);
+                }
+                samples.push(sample);
+            }
+
+            let average = if settings.tonemapped_emission_average {
+                sampling::average_tonemapped(&samples)
+            } else {
+                samples.iter().fold(Vec3::new(0.0, 0.0, 0.0), |sum, s| sum + s) / num_rays as f32
+            };
+            // El brillo propio del material escala el aporte ya promediado, así que es lineal
+            // (el límite de fireflies y el promedio tonemapeado no lo alteran)
+            let average = average * material.emission_intensity;
+            emission_contribution += average;
+        }
+    }
+
+    if let Some(trace) = trace {
+        trace.emission = Color::from_radiance(&emission_contribution);
+    }
+
+    total_light + emission_contribution
+}
+
+
+
+// Región de render (x0, y0, x1, y1), con x1/y1 exclusivos
+pub type RenderRegion = (usize, usize, usize, usize);
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderSettings {
+    pub width: usize,  // Resolución usada por render_to_image
+    pub height: usize,
+    pub fov: f32,
+    pub region: Option<RenderRegion>,
+    pub shutter: f32,        // Tiempo de obturación en segundos (0 = sin motion blur)
+    pub motion_samples: u32, // Muestras temporales por píxel cuando shutter > 0
+    pub max_sample_luminance: Option<f32>, // Límite de luminancia por muestra de emisión (None = sin límite)
+    pub near_clip: f32, // Los rayos primarios ignoran impactos más cercanos que esto
+    pub far_clip: f32,  // ... o más lejanos que esto (se ve el fondo)
+    pub preview: bool,  // Vista previa: solo el color del material, sin luces, sombras ni emisión
+    pub tonemapped_emission_average: bool, // Promediar la emisión en espacio x/(1+x) para suprimir fireflies
+    pub time_budget: Option<Duration>,     // Tiempo máximo por cuadro; al agotarse se entrega un cuadro parcial
+    pub pixel_aspect: f32,                 // Ancho / alto de cada píxel (1.0 = píxeles cuadrados)
+    pub max_direct_lights: usize,          // Con más luces se muestrea una sola por punto según su aporte
+    pub min_emission_luminance: f32,       // intensidad * luminancia mínima para que un material emita luz
+    pub aa_samples: u32,                   // Muestras por píxel con desplazamiento aleatorio (1 = sin anti-aliasing)
+    pub filter: PixelFilter,               // Cómo se ponderan esas muestras según su distancia al centro
+    pub normal_space: NormalSpace,         // Espacio de las normales en render_passes
+    pub min_light_intensity: f32,          // Luces con menos intensidad atenuada en el punto no se evalúan
+    pub sample_sequence: SampleSequence,   // Origen de los desplazamientos de anti-aliasing
+    pub adaptive_threshold: Option<f32>,   // Error estándar de luminancia bajo el que un píxel deja de muestrear (None = siempre aa_samples)
+    pub min_adaptive_samples: u32,         // Muestras mínimas antes de evaluar adaptive_threshold
+    pub specular_aa: bool,                 // Ensancha brillos más chicos que el píxel (Toksvig) para que no parpadeen
+    pub frame_index: u32,                  // Cuadros ya acumulados: con Halton cada cuadro usa muestras nuevas
+    pub tone_map: ToneMap,                 // Curva con la que la radiancia pasa a los 8 bits del framebuffer
+    pub exposure: f32,                     // Multiplica la radiancia antes de la curva
+    pub seed: u64,                         // Semilla del muestreo aleatorio: misma semilla, misma imagen
+}
+
+// Cómo se eligen los desplazamientos de las muestras dentro del píxel
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SampleSequence {
+    Random, // Independientes en cada cuadro
+    Halton, // Baja discrepancia, continuando la secuencia según frame_index (converge al acumular)
+}
+
+impl SampleSequence {
+    pub fn name(&self) -> &'static str {
+        match self {
+            SampleSequence::Random => "random",
+            SampleSequence::Halton => "halton",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<SampleSequence> {
+        match name {
+            "random" => Some(SampleSequence::Random),
+            "halton" => Some(SampleSequence::Halton),
+            _ => None,
+        }
+    }
+}
+
+impl Default for RenderSettings {
+    fn default() -> Self {
+        RenderSettings {
+            width: 800,
+            height: 600,
+            fov: PI / 3.0,
+            region: None,
+            shutter: 0.0,
+            motion_samples: 8,
+            max_sample_luminance: None,
+            near_clip: 0.0,
+            far_clip: f32::INFINITY,
+            preview: false,
+            tonemapped_emission_average: false,
+            time_budget: None,
+            pixel_aspect: 1.0,
+            max_direct_lights: 8,
+            min_emission_luminance: 0.001,
+            aa_samples: 1,
+            filter: PixelFilter::Tent,
+            normal_space: NormalSpace::World,
+            min_light_intensity: 1e-3,
+            sample_sequence: SampleSequence::Random,
+            adaptive_threshold: None,
+            min_adaptive_samples: 4,
+            specular_aa: false,
+            frame_index: 0,
+            tone_map: ToneMap::Clamp,
+            exposure: 1.0,
+            seed: 0,
+        }
+    }
+}
+
+impl RenderSettings {
+    // true si ambos ajustes trazan la misma imagen salvo por el cuadro de la secuencia de muestras
+    // y la exposición (que se aplica al mostrar): el historial temporal y la acumulación siguen valiendo
+    pub fn traces_same_image(&self, other: &RenderSettings) -> bool {
+        *self == RenderSettings { frame_index: self.frame_index, exposure: self.exposure, ..other.clone() }
+    }
+}
+
+// Radiancia a 8 bits para mostrar: exposición y curva de tono de los ajustes
+pub fn display_color(radiance: &Vec3, settings: &RenderSettings) -> Color {
+    Color::from_radiance(&settings.tone_map.apply(&(radiance * settings.exposure)))
+}
+
+// Primer impacto de un rayo de cámara, para los pases auxiliares (normal, albedo, profundidad, ID)
+// y el historial de la reproyección
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct SurfaceSample {
+    point: Vec3,
+    normal: Vec3,
+    albedo: Vec3,
+    depth: f32,
+    material_id: u32,
+}
+
+// Color de un píxel a través de la posición (px, py) en pantalla, con motion blur si hay obturador,
+// y el impacto de su primer rayo (None si ve el fondo)
+fn render_sample(
+    px: f32,
+    py: f32,
+    width: f32,
+    height: f32,
+    scene: &Scene,
+    camera: &Camera,
+    settings: &RenderSettings,
+) -> (Vec3, Option<SurfaceSample>) {
+    let (ray_origin, ray_direction) = camera.get_ray_with_pixel_aspect(px, py, width, height, settings.fov, settings.pixel_aspect);
+    let screen_uv = (px / width, py / height);
+    if settings.shutter > 0.0 && settings.motion_samples > 1 {
+        // Motion blur: promediar muestras en instantes aleatorios dentro del obturador; los objetos
+        // y la cámara (si se mueve) se ubican en ese instante. Los pases toman el primer instante
+        let mut first_surface = None;
+        let mut sum = Vec3::new(0.0, 0.0, 0.0);
+        for i in 0..settings.motion_samples {
+            let time = sampling::random_f32() * settings.shutter;
+            let (ray_origin, ray_direction) = if camera.is_moving() {
+                camera.at_time(time).get_ray_with_pixel_aspect(px, py, width, height, settings.fov, settings.pixel_aspect)
+            } else {
+                (ray_origin, ray_direction)
+            };
+            let (radiance, surface) = trace_camera_ray(&ray_origin, &ray_direction, scene, settings, time, screen_uv);
+            if i == 0 {
+                first_surface = surface;
+            }
+            sum += radiance;
+        }
+        (sum / settings.motion_samples as f32, first_surface)
+    } else {
+        trace_camera_ray(&ray_origin, &ray_direction, scene, settings, 0.0, screen_uv)
+    }
+}
+
+// Supersampling: aa_samples rayos desplazados al azar dentro del radio del filtro, combinados
+// con los pesos normalizados del filtro. Con settings.adaptive_threshold el píxel deja de
+// muestrear en cuanto su luminancia converge, y los rayos se concentran en bordes y zonas ruidosas
+pub(crate) fn render_pixel(x: usize, y: usize, width: f32, height: f32, scene: &Scene, camera: &Camera, settings: &RenderSettings) -> Vec3 {
+    render_pixel_samples(x, y, width, height, scene, camera, settings).0
+}
+
+// Núcleo de render_pixel: la radiancia filtrada y el impacto de cada muestra con su peso
+pub(crate) fn render_pixel_samples(
+    x: usize,
+    y: usize,
+    width: f32,
+    height: f32,
+    scene: &Scene,
+    camera: &Camera,
+    settings: &RenderSettings,
+) -> (Vec3, Vec<(Option<SurfaceSample>, f32)>) {
+    sampling::seed_pixel(settings.seed, x, y, settings.frame_index);
+    if settings.aa_samples <= 1 {
+        let (radiance, surface) = render_sample(x as f32, y as f32, width, height, scene, camera, settings);
+        return (radiance, vec![(surface, 1.0)]);
+    }
+    let radius = settings.filter.radius();
+    let rotation = sampling::pixel_rotation(x, y);
+    let offset = |i: u32| {
+        let (u, v) = match settings.sample_sequence {
+            SampleSequence::Random => (sampling::random_f32(), sampling::random_f32()),
+            SampleSequence::Halton => {
+                // Índice global: el cuadro N continúa donde terminó el N - 1
+                let index = settings.frame_index.wrapping_mul(settings.aa_samples).wrapping_add(i + 1);
+                let (u, v) = sampling::halton_2d(index);
+                ((u + rotation.0).fract(), (v + rotation.1).fract())
+            }
+        };
+        ((u * 2.0 - 1.0) * radius, (v * 2.0 - 1.0) * radius)
+    };
+
+    let mut offsets = Vec::with_capacity(settings.aa_samples as usize);
+    let mut samples = Vec::with_capacity(settings.aa_samples as usize);
+    let mut surfaces = Vec::with_capacity(settings.aa_samples as usize);
+    let mut stats = PixelStats::new();
+    for i in 0..settings.aa_samples {
+        let (dx, dy) = offset(i);
+        let (sample, surface) = render_sample(x as f32 + dx, y as f32 + dy, width, height, scene, camera, settings);
+        offsets.push((dx, dy));
+        samples.push(sample);
+        surfaces.push(surface);
+        stats.add_radiance(&sample);
+        if let Some(threshold) = settings.adaptive_threshold {
+            if stats.is_converged(threshold, settings.min_adaptive_samples) {
+                break;
+            }
+        }
+    }
+    let weights = settings.filter.normalized_weights(&offsets);
+    (filter::weighted_sum(&samples, &weights), surfaces.into_iter().zip(weights).collect())
+}
+
+// Impacto de la muestra de mayor peso del píxel (la primera si empatan), None si esa muestra ve el fondo
+pub(crate) fn heaviest_surface(surfaces: &[(Option<SurfaceSample>, f32)]) -> Option<&SurfaceSample> {
+    let heaviest = surfaces.iter().fold(None::<&(Option<SurfaceSample>, f32)>, |best, sample| match best {
+        Some(best) if best.1 >= sample.1 => Some(best),
+        _ => Some(sample),
+    });
+    heaviest.and_then(|(surface, _)| surface.as_ref())
+}
+
+// Orden de filas de grueso a fino (cada 8, luego cada 4, 2 y 1): si el render se corta por
+// tiempo, las filas ya trazadas cubren todo el cuadro de forma pareja
+fn progressive_row_order(y0: usize, y1: usize) -> Vec<usize> {
+    let mut order = Vec::with_capacity(y1.saturating_sub(y0));
+    let mut done = vec![false; y1.saturating_sub(y0)];
+    for step in [8, 4, 2, 1] {
+        for y in (y0..y1).step_by(step) {
+            if !done[y - y0] {
+                done[y - y0] = true;
+                order.push(y);
+            }
+        }
+    }
+    order
+}
+
+// Región de settings recortada al tamaño del búfer; una región fuera de la imagen o invertida
+// queda vacía (x0 = x1 o y0 = y1) en lugar de producir rangos que se salen del búfer
+pub(crate) fn clipped_region(settings: &RenderSettings, width: usize, height: usize) -> RenderRegion {
+    let (x0, y0, x1, y1) = settings.region.unwrap_or((0, 0, width, height));
+    let (x1, y1) = (x1.min(width), y1.min(height));
+    (x0.min(x1), y0.min(y1), x1, y1)
+}
+
+// Núcleo paralelo del render: evalúa pixel(x, y) para x en x0..x1 en cada fila de `rows`.
+// Los hilos toman la fila siguiente de un contador compartido (las filas de cielo son baratas y
+// las de la casa caras); el resultado conserva el orden de `rows`
+pub(crate) fn trace_rows<T, F>(rows: &[usize], (x0, x1): (usize, usize), pixel: F) -> Vec<Vec<T>>
+where
+    T: Send,
+    F: Fn(usize, usize) -> T + Sync,
+{
+    let next_row = AtomicUsize::new(0);
+    let threads = std::thread::available_parallelism().map_or(1, |n| n.get()).min(rows.len().max(1));
+
+    let mut results: Vec<Option<Vec<T>>> = (0..rows.len()).map(|_| None).collect();
+    std::thread::scope(|s| {
+        let workers: Vec<_> = (0..threads)
+            .map(|_| {
+                s.spawn(|| {
+                    let mut traced = Vec::new();
+                    loop {
+                        let index = next_row.fetch_add(1, Ordering::Relaxed);
+                        let Some(&y) = rows.get(index) else { break };
+                        traced.push((index, (x0..x1).map(|x| pixel(x, y)).collect::<Vec<T>>()));
+                    }
+                    traced
+                })
+            })
+            .collect();
+        for worker in workers {
+            for (index, row) in worker.join().expect("Render thread panicked") {
+                results[index] = Some(row);
+            }
+        }
+    });
+    results.into_iter().map(|row| row.unwrap_or_default()).collect()
+}
+
+// Traza la región de settings en `radiance` (búfer de width x height, fila por fila). Con
+// settings.time_budget se detiene al agotar el tiempo y rellena las filas pendientes con la fila
+// trazada más cercana; devuelve false si el cuadro quedó parcial
+fn render_radiance(
+    radiance: &mut [Vec3],
+    width: usize,
+    height: usize,
+    scene: &Scene,
+    camera: &Camera,
+    settings: &RenderSettings,
+    progress: &mut dyn FnMut(f32),
+) -> bool {
+    let start = Instant::now();
+    // El tamaño del píxel (anti-aliasing especular) sale del búfer real: resolución interna
+    // reducida o la mitad de cada ojo en estéreo, no la resolución de la ventana
+    let settings = &RenderSettings { width, height, ..settings.clone() };
+    let (x0, y0, x1, y1) = clipped_region(settings, width, height);
+    let order = progressive_row_order(y0, y1);
+
+    // Lotes de unas pocas filas por hilo: entre lote y lote se revisa el tiempo y se informa el avance
+    let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let batch_size = threads * 2;
+
+    // La primera pasada (una fila de cada 8) siempre se completa: aun con un presupuesto mínimo
+    // el cuadro muestra la escena a baja resolución en lugar de quedar vacío
+    let first_pass = (y0..y1).step_by(8).count();
+    let (first, rest) = order.split_at(first_pass);
+
+    let mut traced = vec![false; order.len()];
+    let mut rows_done = 0;
+    let mut complete = true;
+
+    for batch in first.chunks(batch_size).chain(rest.chunks(batch_size)) {
+        if let (Some(budget), true) = (settings.time_budget, rows_done >= first_pass) {
+            if start.elapsed() > budget {
+                complete = false;
+                break;
+            }
+        }
+
+        let rows = trace_rows(batch, (x0, x1), |x, y| {
+            render_pixel(x, y, width as f32, height as f32, scene, camera, settings)
+        });
+        for (y, row) in batch.iter().zip(rows) {
+            radiance[y * width + x0..y * width + x1].copy_from_slice(&row);
+            traced[y - y0] = true;
+        }
+        rows_done += batch.len();
+        if rows_done < order.len() {
+            progress(rows_done as f32 / order.len() as f32);
+        }
+    }
+
+    if !complete {
+        // Cada fila pendiente copia la fila trazada más cercana por encima (o la primera, si no hay)
+        let first_traced = traced.iter().position(|t| *t);
+        let mut source = first_traced.map(|row| row + y0);
+        for y in y0..y1 {
+            if traced[y - y0] {
+                source = Some(y);
+            } else if let Some(source_y) = source {
+                radiance.copy_within(source_y * width + x0..source_y * width + x1, y * width + x0);
+            }
+        }
+    }
+
+    // El cuadro está listo (aunque sea parcial): siempre se informa 1.0 al final
+    progress(1.0);
+    complete
+}
+
+// Traza la región del framebuffer. Con settings.time_budget se detiene al agotar el tiempo y
+// rellena las filas pendientes con la fila trazada más cercana; devuelve false si el cuadro quedó parcial
+pub fn render(framebuffer: &mut Framebuffer, scene: &Scene, camera: &Camera, settings: &RenderSettings) -> bool {
+    render_with_progress(framebuffer, scene, camera, settings, &mut |_| {})
+}
+
+// Igual que render, llamando a `progress` con la fracción completada (0..1) a medida que avanza
+pub fn render_with_progress(
+    framebuffer: &mut Framebuffer,
+    scene: &Scene,
+    camera: &Camera,
+    settings: &RenderSettings,
+    progress: &mut dyn FnMut(f32),
+) -> bool {
+    let (width, height) = (framebuffer.width, framebuffer.height);
+    let mut radiance = vec![Vec3::new(0.0, 0.0, 0.0); width * height];
+    let complete = render_radiance(&mut radiance, width, height, scene, camera, settings, progress);
+
+    // Solo se escriben los píxeles dentro de la región; el resto del framebuffer queda intacto
+    let (x0, y0, x1, y1) = clipped_region(settings, width, height);
+    for y in y0..y1 {
+        for x in x0..x1 {
+            framebuffer.buffer[y * width + x] = display_color(&radiance[y * width + x], settings).to_hex();
+        }
+    }
+    complete
+}
+
+// Suma un cuadro de radiancia lineal (sin curva de tono ni recorte) a `hdr`, que define el
+// tamaño; con settings.frame_index avanzando cada cuadro aporta muestras nuevas
+pub fn render_accumulate(hdr: &mut HdrImage, scene: &Scene, camera: &Camera, settings: &RenderSettings) -> bool {
+    let mut radiance = vec![Vec3::new(0.0, 0.0, 0.0); hdr.width * hdr.height];
+    let complete = render_radiance(&mut radiance, hdr.width, hdr.height, scene, camera, settings, &mut |_| {});
+    hdr.accumulate(&radiance);
+    complete
+}
+
+// Render completo en radiancia lineal, para exportar a EXR los valores mayores a 1.0
+pub fn render_to_hdr(scene: &Scene, camera: &Camera, settings: &RenderSettings) -> HdrImage {
+    let mut hdr = HdrImage::new(settings.width, settings.height);
+    render_accumulate(&mut hdr, scene, camera, settings);
+    hdr
+}
+
+// Traza el píxel (x, y) registrando cada rebote: el rayo de cámara y los rayos de refracción,
+// reflexión y capas transparentes que lanza, con el aporte y la sombra de cada luz en cada impacto
+pub fn trace_pixel(x: usize, y: usize, scene: &Scene, camera: &Camera, settings: &RenderSettings) -> PixelTrace {
+    let width = settings.width as f32;
+    let height = settings.height as f32;
+    let (ray_origin, ray_direction) =
+        camera.get_ray_with_pixel_aspect(x as f32, y as f32, width, height, settings.fov, settings.pixel_aspect);
+
+    // El color es el mismo que daría el render (sin anti-aliasing ni motion blur)
+    sampling::seed_pixel(settings.seed, x, y, settings.frame_index);
+    let radiance = trace_ray(&ray_origin, &ray_direction, scene, settings, 0, 0.0, Some((x as f32 / width, y as f32 / height)));
+    let color = display_color(&radiance, settings);
+
+    let mut bounces = Vec::new();
+    record_bounces(&ray_origin, &ray_direction, scene, settings, 0, BounceKind::Camera, &mut bounces);
+    PixelTrace { x, y, bounces, color }
+}
+
+// Registra el rayo y los secundarios que trace_ray lanzaría desde su impacto (hasta MAX_DEPTH)
+fn record_bounces(
+    ray_origin: &Vec3,
+    ray_direction: &Vec3,
+    scene: &Scene,
+    settings: &RenderSettings,
+    depth: u32,
+    kind: BounceKind,
+    bounces: &mut Vec<BounceTrace>,
+) {
+    if depth >= MAX_DEPTH {
+        return;
+    }
+    let intersect = if depth == 0 {
+        scene.intersect_clipped(ray_origin, ray_direction, 0.0, settings.near_clip, settings.far_clip)
+    } else {
+        scene.intersect_at(ray_origin, ray_direction, 0.0)
+    };
+    let bounce = |kind, hit| BounceTrace { depth, kind, ray_origin: *ray_origin, ray_direction: *ray_direction, hit };
+    if !intersect.is_intersecting {
+        bounces.push(bounce(kind, None));
+        return;
+    }
+    bounces.push(bounce(kind, Some(hit_trace(ray_origin, &intersect, scene, settings))));
+    let material = intersect.material;
+    if settings.preview || material.albedo[3] <= 0.0 {
+        return;
+    }
+
+    if material.refractive_index > 1.0 {
+        // Un rayo por canal con dispersión; reflexión total interna -> rayo reflejado
+        let direction = ray_direction.normalize();
+        let refracted = if material.dispersion > 0.0 {
+            refract_dispersed(&direction, &intersect.normal, material).to_vec()
+        } else {
+            vec![refract(&direction, &intersect.normal, material.refractive_index)]
+        };
+        for refracted in refracted {
+            let (kind, dir) = match refracted {
+                Some(dir) => (BounceKind::Refraction, dir),
+                None => (BounceKind::Reflection, reflect(&direction, &intersect.normal)),
+            };
+            record_bounces(&offset_origin(&intersect, &dir), &dir, scene, settings, depth + 1, kind, bounces);
+        }
+    } else {
+        // Capas transparentes sin refracción: las siguientes superficies a lo largo del mismo rayo
+        let (near, far) = if depth == 0 { (settings.near_clip, settings.far_clip) } else { (0.0, f32::INFINITY) };
+        let layers = scene.intersect_all_at(ray_origin, ray_direction, 0.0);
+        for layer in layers.iter().filter(|i| i.distance >= near && i.distance < far).skip(1) {
+            bounces.push(bounce(BounceKind::Transparency, Some(hit_trace(ray_origin, layer, scene, settings))));
+        }
+    }
+}
+
+fn hit_trace(ray_origin: &Vec3, intersect: &Intersect, scene: &Scene, settings: &RenderSettings) -> HitTrace {
+    let mut shading = ShadeTrace::new();
+    shade(ray_origin, intersect, scene, settings, 0.0, Some(&mut shading));
+    let material = &intersect.material;
+    HitTrace {
+        distance: intersect.distance,
+        point: intersect.point,
+        normal: intersect.normal,
+        u: intersect.u,
+        v: intersect.v,
+        material: format!(
+            "albedo {:?} specular {} ior {} texture {}",
+            material.albedo,
+            material.specular,
+            material.refractive_index,
+            material.texture.as_ref().map_or("none", |t| t.id.as_str())
+        ),
+        shading,
+    }
+}
+
+// Traza un lote arbitrario de rayos (lidar, horneado de luz...) sin cámara ni framebuffer.
+// El lote se reparte entre los hilos disponibles; el resultado conserva el orden de entrada.
+pub fn trace_rays(origins: &[Vec3], directions: &[Vec3], scene: &Scene, settings: &RenderSettings) -> Vec<Color> {
+    assert_eq!(origins.len(), directions.len(), "Each ray needs an origin and a direction");
+
+    let mut colors = vec![Color::black(); origins.len()];
+    if origins.is_empty() {
+        return colors;
+    }
+    let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let chunk_size = origins.len().div_ceil(threads);
+
+    std::thread::scope(|s| {
+        for (chunk_index, chunk) in colors.chunks_mut(chunk_size).enumerate() {
+            let start = chunk_index * chunk_size;
+            s.spawn(move || {
+                for (offset, color) in chunk.iter_mut().enumerate() {
+                    let i = start + offset;
+                    // Cada rayo siembra su propio generador por índice: el resultado no depende
+                    // del hilo que lo traza ni de lo que el hilo trazó antes
+                    sampling::seed_pixel(settings.seed, i, 0, settings.frame_index);
+                    *color = cast_ray(&origins[i], &directions[i], scene, settings, 0);
+                }
+            });
+        }
+    });
+
+    colors
+}
+
+// Render completo sin ventana, para usar el raytracer como librería
+pub fn render_to_image(scene: &Scene, camera: &Camera, settings: &RenderSettings) -> RgbImage {
+    render_to_image_with_progress(scene, camera, settings, &mut |_| {})
+}
+
+pub fn render_to_image_with_progress(
+    scene: &Scene,
+    camera: &Camera,
+    settings: &RenderSettings,
+    progress: &mut dyn FnMut(f32),
+) -> RgbImage {
+    let mut framebuffer = Framebuffer::new(settings.width, settings.height);
+    framebuffer.clear();
+    render_with_progress(&mut framebuffer, scene, camera, settings, progress);
+    framebuffer.to_image()
+}
+
+// Render de varios pases en una sola traza: beauty, normal, albedo, profundidad e ID de material.
+// Usa el mismo núcleo que render (muestras del filtro, obturador, región, fondo y niebla) y cada
+// muestra aporta a los pases el impacto de su rayo de cámara: normal y albedo se promedian con los
+// pesos del filtro, profundidad e ID salen de la muestra de mayor peso
+pub fn render_passes(scene: &Scene, camera: &Camera, settings: &RenderSettings) -> RenderPasses {
+    let mut passes = RenderPasses::new(settings.width, settings.height);
+    let (width, height) = (settings.width as f32, settings.height as f32);
+    let (x0, y0, x1, y1) = clipped_region(settings, settings.width, settings.height);
+    let rows: Vec<usize> = (y0..y1).collect();
+
+    let traced = trace_rows(&rows, (x0, x1), |x, y| render_pixel_samples(x, y, width, height, scene, camera, settings));
+    for (y, row) in rows.iter().zip(traced) {
+        for (x, (radiance, surfaces)) in (x0..x1).zip(row) {
+            let index = y * settings.width + x;
+            passes.beauty[index] = display_color(&radiance, settings);
+
+            let mut normal = Vec3::new(0.0, 0.0, 0.0);
+            let mut albedo = Vec3::new(0.0, 0.0, 0.0);
+            for (surface, weight) in &surfaces {
+                if let Some(surface) = surface {
+                    normal += surface.normal * *weight;
+                    albedo += surface.albedo * *weight;
+                }
+            }
+            if normal.magnitude() > 0.0 {
+                let normal = normal.normalize();
+                passes.normal[index] = match settings.normal_space {
+                    NormalSpace::World => normal,
+                    NormalSpace::View => camera.world_to_view(&normal),
+                };
+            }
+            passes.albedo[index] = Color::from_radiance(&albedo);
+
+            if let Some(surface) = heaviest_surface(&surfaces) {
+                passes.depth[index] = surface.depth;
+                passes.material_id[index] = surface.material_id;
+            }
+        }
+    }
+
+    passes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cube::Cube;
+    use crate::light::Light;
+    use crate::material::{EmissionFalloff, Material};
+
+    fn small_settings(width: usize, height: usize) -> RenderSettings {
+        RenderSettings { width, height, ..RenderSettings::default() }
+    }
+
+    fn cube_scene() -> (Scene, Camera) {
+        let mut scene = Scene::new();
+        let material = Material::new(Color::new(200, 120, 60), 10.0, [0.9, 0.1, 0.0, 0.0], 1.0, None, 0.0);
+        scene.add_cube(Cube { min: Vec3::new(-1.0, -1.0, -1.0), max: Vec3::new(1.0, 1.0, 1.0), material, inverted: false }, &[]);
+        let camera = Camera::new(Vec3::new(0.0, 0.0, 6.0), Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+        (scene, camera)
+    }
+
+    // Impacto en el origen sobre un piso gris difuso (normal +Y) y el origen del rayo que lo ve a
+    // 5 unidades, para probar shade sin armar una escena
+    fn grey_floor_hit() -> (Vec3, Intersect<'static>) {
+        static GREY: std::sync::LazyLock<Material> =
+            std::sync::LazyLock::new(|| Material::new(Color::new(180, 180, 180), 10.0, [0.9, 0.0, 0.0, 0.0], 1.0, None, 0.0));
+        (Vec3::new(0.0, 3.0, 4.0), Intersect::new(Vec3::zeros(), Vec3::new(0.0, 1.0, 0.0), 5.0, &GREY, 0.0, 0.0))
+    }
+
+    #[test]
+    fn render_to_image_uses_settings_size_and_shows_sky() {
+        let scene = Scene::new();
+        let camera = Camera::new(Vec3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+        let image = render_to_image(&scene, &camera, &small_settings(8, 6));
+
+        assert_eq!((image.width(), image.height()), (8, 6));
+        let pixel = image.get_pixel(4, 3);
+        assert_eq!((pixel[0], pixel[1], pixel[2]), (SKYBOX_COLOR.r(), SKYBOX_COLOR.g(), SKYBOX_COLOR.b()));
+    }
+
+    #[test]
+    fn region_render_fills_only_the_pixels_inside_it() {
+        let (scene, camera) = cube_scene();
+        let full = render_to_image(&scene, &camera, &small_settings(16, 12));
+
+        let mut framebuffer = Framebuffer::new(16, 12);
+        framebuffer.buffer.fill(0x123456);
+        let settings = RenderSettings { region: Some((4, 2, 12, 7)), ..small_settings(16, 12) };
+        render(&mut framebuffer, &scene, &camera, &settings);
+        for y in 0..12 {
+            for x in 0..16 {
+                let pixel = framebuffer.get_pixel_color(x, y);
+                if (4..12).contains(&x) && (2..7).contains(&y) {
+                    let [r, g, b] = full.get_pixel(x as u32, y as u32).0;
+                    assert_eq!(pixel, Color::new(r, g, b).to_hex());
+                } else {
+                    assert_eq!(pixel, 0x123456);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn regions_outside_the_image_or_inverted_render_nothing() {
+        let settings = RenderSettings { region: Some((500, 0, 900, 10)), ..small_settings(400, 300) };
+        assert_eq!(clipped_region(&settings, 400, 300), (400, 0, 400, 10));
+        let inverted = RenderSettings { region: Some((12, 7, 4, 2)), ..small_settings(16, 12) };
+        assert_eq!(clipped_region(&inverted, 16, 12), (4, 2, 4, 2));
+
+        // Ninguna de las dos toca el framebuffer ni los pases
+        let (scene, camera) = cube_scene();
+        for settings in [RenderSettings { region: Some((20, 0, 40, 10)), ..small_settings(16, 12) }, inverted] {
+            let mut framebuffer = Framebuffer::new(16, 12);
+            framebuffer.buffer.fill(0x123456);
+            render(&mut framebuffer, &scene, &camera, &settings);
+            assert!(framebuffer.buffer.iter().all(|&pixel| pixel == 0x123456));
+            let (passes, empty) = (render_passes(&scene, &camera, &settings), RenderPasses::new(16, 12));
+            assert_eq!(passes.depth, empty.depth);
+            assert_eq!(passes.material_id, empty.material_id);
+        }
+    }
+
+    #[test]
+    fn glass_blocker_casts_a_partial_shadow_and_wood_a_full_one() {
+        let black = Material::black();
+        let surface = Intersect {
+            point: Vec3::zeros(),
+            normal: Vec3::new(0.0, 1.0, 0.0),
+            distance: 0.0,
+            is_intersecting: true,
+            material: &black,
+            u: 0.0,
+            v: 0.0,
+        };
+        let shadow_through = |material: Material| {
+            let mut scene = Scene::new();
+            scene.add_cube(Cube { min: Vec3::new(-1.0, 1.0, -1.0), max: Vec3::new(1.0, 1.5, 1.0), material, inverted: false }, &[]);
+            cast_shadow(&surface, &scene, &Vec3::new(0.0, 1.0, 0.0), 5.0, 0.0)
+        };
+        let glass = Material::new(Color::new(200, 220, 255), 0.3, [0.7, 0.1, 0.0, 0.5], 1.5, None, 0.0);
+        let wood = Material::new(Color::new(140, 90, 50), 0.2, [0.9, 0.05, 0.0, 0.0], 1.0, None, 0.0);
+        assert!((shadow_through(glass) - 0.5).abs() < 1e-5);
+        assert_eq!(shadow_through(wood), 1.0);
+    }
+
+    #[test]
+    fn moving_cube_blurs_its_edge_only_with_an_open_shutter() {
+        let (mut scene, camera) = cube_scene();
+        scene.objects_mut()[0].velocity = Vec3::new(1.5, 0.0, 0.0);
+        let sharp_settings = small_settings(16, 12);
+        let blur_settings = RenderSettings { shutter: 1.0, motion_samples: 16, ..small_settings(16, 12) };
+
+        // Sin obturador cada píxel es cubo o cielo; con obturador el borde mezcla ambos
+        let sky = SKYBOX_COLOR;
+        let cube_color = |image: &RgbImage| image.get_pixel(8, 6).0;
+        let is_pure = |pixel: [u8; 3], cube: [u8; 3]| pixel == [sky.r(), sky.g(), sky.b()] || pixel == cube;
+        let sharp = render_to_image(&scene, &camera, &sharp_settings);
+        assert!(sharp.pixels().all(|p| is_pure(p.0, cube_color(&sharp))));
+
+        let blurred = render_to_image(&scene, &camera, &blur_settings);
+        let row: Vec<[u8; 3]> = (0..16).map(|x| blurred.get_pixel(x, 6).0).collect();
+        assert!(row.iter().any(|p| !is_pure(*p, cube_color(&sharp))), "{:?}", row);
+    }
+
+    #[test]
+    fn backlit_translucent_surface_receives_light_and_opaque_one_does_not() {
+        let shade_front = |translucency: f32| {
+            let mut leaf = Material::new(Color::new(80, 200, 60), 10.0, [0.9, 0.0, 0.0, 0.0], 1.0, None, 0.0);
+            leaf.translucency = translucency;
+            let mut scene = Scene::new();
+            scene.add_cube(Cube { min: Vec3::new(-1.0, -1.0, -0.05), max: Vec3::new(1.0, 1.0, 0.05), material: leaf, inverted: false }, &[]);
+            scene.lights = vec![Light::new(Vec3::new(0.0, 0.0, -4.0), Color::new(255, 255, 255), 2.0)];
+            cast_ray(&Vec3::new(0.0, 0.0, 5.0), &Vec3::new(0.0, 0.0, -1.0), &scene, &small_settings(4, 4), 0)
+        };
+        // Sin translucidez solo queda la luz ambiental
+        let ambient = Scene::new().ambient_color * Scene::new().ambient_intensity;
+        let opaque = shade_front(0.0);
+        assert_eq!((opaque.r(), opaque.g(), opaque.b()), (ambient.r(), ambient.g(), ambient.b()));
+        let lit = shade_front(0.5);
+        let gain = |a: u8, b: u8| a as i32 - b as i32;
+        let (dr, dg, db) = (gain(lit.r(), ambient.r()), gain(lit.g(), ambient.g()), gain(lit.b(), ambient.b()));
+        assert!(dg > 0);
+        // La luz que atraviesa toma el color de la hoja
+        assert!(dg > dr && dr > db, "{:?}", lit);
+    }
+
+    #[test]
+    fn passes_of_a_single_cube_hold_its_color_and_face_normal() {
+        let (scene, camera) = cube_scene();
+        let passes = render_passes(&scene, &camera, &small_settings(16, 12));
+        let center = 6 * 16 + 8;
+        let albedo = passes.albedo[center];
+        assert_eq!((albedo.r(), albedo.g(), albedo.b()), (200, 120, 60));
+        assert_eq!(passes.normal[center], Vec3::new(0.0, 0.0, 1.0));
+        assert!((passes.depth[center] - 5.0).abs() < 0.1);
+
+        // Esquina: el rayo sale al cielo
+        assert_eq!(passes.normal[0], Vec3::zeros());
+        assert_eq!(passes.albedo[0].to_hex(), 0);
+        assert_eq!(passes.depth[0], f32::INFINITY);
+    }
+
+    #[test]
+    fn blue_ambient_tint_makes_shadowed_faces_bluer() {
+        let (mut scene, camera) = cube_scene();
+        // Luz detrás del cubo: la cara visible solo recibe luz ambiental
+        scene.lights = vec![Light::new(Vec3::new(0.0, 0.0, -6.0), Color::new(255, 255, 255), 1.0)];
+        let settings = small_settings(16, 12);
+        let neutral = render_to_image(&scene, &camera, &settings).get_pixel(8, 6).0;
+        scene.ambient_color = Color::new(60, 90, 255);
+        let tinted = render_to_image(&scene, &camera, &settings).get_pixel(8, 6).0;
+
+        let blueness = |[r, g, b]: [u8; 3]| b as f32 / (r as f32 + g as f32 + b as f32).max(1.0);
+        assert!(blueness(tinted) > blueness(neutral) + 0.1, "{:?} -> {:?}", neutral, tinted);
+    }
+
+    #[test]
+    fn far_clip_hides_geometry_beyond_it_from_camera_rays() {
+        let (scene, camera) = cube_scene();
+        let direction = Vec3::new(0.0, 0.0, -1.0);
+        // La cara frontal del cubo está a 5 unidades del ojo
+        let clipped_at = |far_clip: f32| RenderSettings { far_clip, ..small_settings(8, 8) };
+
+        let hit = cast_ray(&camera.eye, &direction, &scene, &clipped_at(5.5), 0);
+        assert_ne!(hit.to_hex(), SKYBOX_COLOR.to_hex());
+        let clipped = cast_ray(&camera.eye, &direction, &scene, &clipped_at(4.5), 0);
+        assert_eq!(clipped.to_hex(), SKYBOX_COLOR.to_hex());
+    }
+
+    #[test]
+    fn preview_returns_the_material_color_whatever_the_light() {
+        let (mut scene, camera) = cube_scene();
+        let settings = RenderSettings { preview: true, ..small_settings(8, 8) };
+        let mut colors = Vec::new();
+        for light_position in [Vec3::new(0.0, 0.0, 5.0), Vec3::new(5.0, 5.0, 0.0), Vec3::new(0.0, -5.0, -5.0)] {
+            scene.lights = vec![Light::new(light_position, Color::new(255, 255, 255), 2.0)];
+            let color = cast_ray(&camera.eye, &Vec3::new(0.0, 0.0, -1.0), &scene, &settings, 0);
+            colors.push((color.r(), color.g(), color.b()));
+        }
+        assert_eq!(colors, vec![(200, 120, 60); 3]);
+    }
+
+    #[test]
+    fn quarter_turn_skybox_yaw_shifts_u_by_a_quarter() {
+        for direction in [Vec3::new(0.0, 0.0, 1.0), Vec3::new(0.3, 0.4, -0.8), Vec3::new(-0.7, -0.2, 0.1)] {
+            let (u, v) = skybox_uv(&direction);
+            let (rotated_u, rotated_v) = skybox_uv(&rotate_y(&direction, PI / 2.0));
+            let shift = (rotated_u - u).rem_euclid(1.0);
+            assert!((shift - 0.25).abs() < 1e-5, "{:?}: {}", direction, shift);
+            assert!((rotated_v - v).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn light_without_shadows_reaches_points_behind_a_blocker() {
+        let material = || Material::new(Color::new(200, 200, 200), 10.0, [0.9, 0.0, 0.0, 0.0], 1.0, None, 0.0);
+        let floor = || Cube { min: Vec3::new(-5.0, -1.0, -5.0), max: Vec3::new(5.0, 0.0, 5.0), material: material(), inverted: false };
+        let mut open = Scene::new();
+        open.add_cube(floor(), &[]);
+        let mut blocked = Scene::new();
+        blocked.add_cube(floor(), &[]);
+        blocked.add_cube(Cube { min: Vec3::new(-1.0, 2.0, -1.0), max: Vec3::new(1.0, 2.5, 1.0), material: material(), inverted: false }, &[]);
+
+        // Rayo al punto del suelo justo debajo del bloqueador, sin pasar por él
+        let settings = small_settings(8, 8);
+        let origin = Vec3::new(0.0, 1.0, 4.0);
+        let direction = (Vec3::zeros() - origin).normalize();
+        let floor_color = |scene: &mut Scene, casts_shadows: bool| {
+            let mut light = Light::new(Vec3::new(0.0, 6.0, 0.0), Color::new(255, 255, 255), 1.0);
+            light.casts_shadows = casts_shadows;
+            scene.lights = vec![light];
+            cast_ray(&origin, &direction, scene, &settings, 0).to_hex()
+        };
+
+        let lit = floor_color(&mut open, true);
+        assert_eq!(floor_color(&mut blocked, false), lit);
+        assert_ne!(floor_color(&mut blocked, true), lit);
+    }
+
+    #[test]
+    fn trace_rays_returns_the_hit_and_miss_colors_in_order() {
+        let (scene, _) = cube_scene();
+        let settings = RenderSettings { preview: true, ..small_settings(8, 8) };
+        let origins = [Vec3::new(0.0, 0.0, 6.0), Vec3::new(0.0, 5.0, 6.0)];
+        let directions = [Vec3::new(0.0, 0.0, -1.0), Vec3::new(0.0, 0.0, -1.0)];
+
+        let colors: Vec<u32> = trace_rays(&origins, &directions, &scene, &settings).iter().map(|c| c.to_hex()).collect();
+        assert_eq!(colors, vec![Color::new(200, 120, 60).to_hex(), SKYBOX_COLOR.to_hex()]);
+        assert!(trace_rays(&[], &[], &scene, &settings).is_empty());
+    }
+
+    #[test]
+    fn trace_rays_is_deterministic_for_a_seed() {
+        // Penumbra de una luz de área con pocas muestras: el resultado depende del generador
+        let mut scene = Scene::new();
+        let white = Material::new(Color::new(200, 200, 200), 10.0, [0.9, 0.1, 0.0, 0.0], 1.0, None, 0.0);
+        scene.add_cube(Cube { min: Vec3::new(-20.0, -1.0, -20.0), max: Vec3::new(20.0, 0.0, 20.0), material: white.clone(), inverted: false }, &[]);
+        scene.add_cube(Cube { min: Vec3::new(-20.0, 3.0, -20.0), max: Vec3::new(0.0, 3.1, 20.0), material: white, inverted: false }, &[]);
+        let mut light = Light::new(Vec3::new(0.0, 10.0, 0.0), Color::new(255, 255, 255), 1.0);
+        light.radius = 2.0;
+        light.shadow_samples = 4;
+        scene.lights = vec![light];
+
+        let origins: Vec<Vec3> = (0..64).map(|i| Vec3::new(-1.0 + i as f32 / 32.0, 1.0, 0.0)).collect();
+        let directions = vec![Vec3::new(0.0, -1.0, 0.0); origins.len()];
+        let trace = |seed: u64| -> Vec<u32> {
+            let settings = RenderSettings { seed, ..small_settings(8, 8) };
+            trace_rays(&origins, &directions, &scene, &settings).iter().map(|c| c.to_hex()).collect()
+        };
+
+        // Otro lote trazado antes en el mismo hilo no cambia el resultado
+        let first = trace(143);
+        trace(7);
+        assert_eq!(trace(143), first);
+        assert_ne!(trace(7), first);
+    }
+
+    #[test]
+    fn nearer_blocker_casts_a_narrower_penumbra() {
+        let mut light = Light::new(Vec3::new(0.0, 10.0, 0.0), Color::new(255, 255, 255), 1.0);
+        light.radius = 2.0;
+        light.shadow_samples = 64;
+        let floor = Material::black();
+
+        // Cantidad de puntos del suelo en penumbra bajo el borde (x = 0) de una placa a `height`
+        let penumbra_points = |height: f32| {
+            let mut scene = Scene::new();
+            let plate = Material::new(Color::new(200, 200, 200), 10.0, [0.9, 0.1, 0.0, 0.0], 1.0, None, 0.0);
+            scene.add_cube(Cube { min: Vec3::new(-20.0, height, -20.0), max: Vec3::new(0.0, height + 0.1, 20.0), material: plate, inverted: false }, &[]);
+            (-30..=30)
+                .filter(|i| {
+                    let point = Vec3::new(*i as f32 * 0.1, 0.0, 0.0);
+                    let intersect = Intersect::new(point, Vec3::new(0.0, 1.0, 0.0), 1.0, &floor, 0.0, 0.0);
+                    let shadow = cast_soft_shadow(&intersect, &scene, &light, 0.0);
+                    shadow > 0.05 && shadow < 0.95
+                })
+                .count()
+        };
+
+        let near = penumbra_points(0.5);
+        let far = penumbra_points(5.0);
+        assert!(near * 4 < far, "near {} far {}", near, far);
+    }
+
+    #[test]
+    fn progress_callback_is_monotonic_and_ends_at_one() {
+        let (scene, camera) = cube_scene();
+        let mut reports = Vec::new();
+        render_to_image_with_progress(&scene, &camera, &small_settings(16, 40), &mut |fraction| reports.push(fraction));
+
+        assert!(reports.len() > 1, "{:?}", reports);
+        assert!(reports.windows(2).all(|pair| pair[0] <= pair[1]), "{:?}", reports);
+        assert!(reports.iter().all(|fraction| (0.0..=1.0).contains(fraction)));
+        assert_eq!(reports.last(), Some(&1.0));
+    }
+
+    #[test]
+    fn emitters_below_the_threshold_add_no_light() {
+        let (origin, intersect) = grey_floor_hit();
+        let settings = RenderSettings { min_emission_luminance: 0.5, ..small_settings(8, 8) };
+
+        let emission_added = |intensity: f32| {
+            let mut scene = Scene::new();
+            let unlit = shade(&origin, &intersect, &scene, &settings, 0.0, None);
+            let emitter = Material::new_emissive(Color::new(255, 255, 255), intensity);
+            scene.add_cube(Cube { min: Vec3::new(2.0, 1.0, -1.0), max: Vec3::new(3.0, 2.0, 0.0), material: emitter, inverted: false }, &[]);
+            shade(&origin, &intersect, &scene, &settings, 0.0, None) != unlit
+        };
+
+        assert!(!emission_added(0.1));
+        assert!(emission_added(2.0));
+    }
+
+    #[test]
+    fn emissive_quads_instances_and_sdfs_light_the_point_like_cubes() {
+        let (origin, intersect) = grey_floor_hit();
+        let settings = small_settings(8, 8);
+        let lamp = Material::new_emissive(Color::new(255, 255, 255), 2.0);
+        let cube = Cube { min: Vec3::new(2.0, 1.0, -1.0), max: Vec3::new(3.0, 2.0, 0.0), material: lamp.clone(), inverted: false };
+
+        let mut instanced = Scene::new();
+        let mut group = crate::instance::InstancedCubes::new();
+        let template = group.add_template(Cube { min: Vec3::zeros(), max: Vec3::repeat(1.0), material: lamp.clone(), inverted: false });
+        group.add_instance(template, Vec3::new(2.0, 1.0, -1.0));
+        instanced.add_instances(group, &[]);
+        let mut quad = Scene::new();
+        let (corner, u_edge, v_edge) = (Vec3::new(2.0, 1.5, -1.0), Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 1.0));
+        quad.add_quad(crate::quad::Quad { corner, u_edge, v_edge, material: lamp.clone(), uv_repeat: (1.0, 1.0) }, &[]);
+        let mut sdf = Scene::new();
+        let sphere = crate::sdf::SdfShape::Sphere { radius: 0.5 };
+        sdf.add_sdf(crate::sdf::SignedDistance::new(Vec3::new(2.5, 1.5, -0.5), sphere, lamp), &[]);
+        let mut cubes = Scene::new();
+        cubes.add_cube(cube, &[]);
+
+        // Todos tienen el centro en (2.5, 1.5, -0.5): con la misma secuencia aleatoria aportan lo mismo
+        let shade_seeded = |scene: &Scene| {
+            sampling::seed_pixel(165, 0, 0, 0);
+            shade(&origin, &intersect, scene, &settings, 0.0, None)
+        };
+        let unlit = shade_seeded(&Scene::new());
+        let from_cube = shade_seeded(&cubes);
+        assert!(from_cube.x > unlit.x);
+        for scene in [&instanced, &quad, &sdf] {
+            assert!((shade_seeded(scene) - from_cube).magnitude() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn falloff_applies_to_emitters_that_are_not_cubes() {
+        let (origin, intersect) = grey_floor_hit();
+        let settings = RenderSettings { max_sample_luminance: None, tonemapped_emission_average: false, ..small_settings(8, 8) };
+        let center = Vec3::new(2.5, 1.5, -0.5);
+        let emitted = |falloff: EmissionFalloff| {
+            let mut lamp = Material::new_emissive(Color::new(255, 255, 255), 2.0);
+            lamp.emission_falloff = falloff;
+            let mut scene = Scene::new();
+            scene.add_sdf(crate::sdf::SignedDistance::new(center, crate::sdf::SdfShape::Sphere { radius: 0.5 }, lamp), &[]);
+            sampling::seed_pixel(190, 0, 0, 0);
+            let lit = shade(&origin, &intersect, &scene, &settings, 0.0, None).x;
+            sampling::seed_pixel(190, 0, 0, 0);
+            lit - shade(&origin, &intersect, &Scene::new(), &settings, 0.0, None).x
+        };
+
+        // Con la misma secuencia aleatoria, el aporte de la esfera escala con la atenuación de su material
+        let constant = emitted(EmissionFalloff::Constant);
+        assert!(constant > 0.0);
+        let expected = EmissionFalloff::InverseSquare.attenuation(center.magnitude());
+        assert!((emitted(EmissionFalloff::InverseSquare) / constant - expected).abs() < 1e-4);
+    }
+
+    #[test]
+    fn doubling_emission_intensity_doubles_the_emitted_light() {
+        let (origin, intersect) = grey_floor_hit();
+        let settings = RenderSettings { max_sample_luminance: None, tonemapped_emission_average: false, ..small_settings(8, 8) };
+        let unlit = shade(&origin, &intersect, &Scene::new(), &settings, 0.0, None).x;
+
+        // Rojo emitido promedio (las direcciones de emisión son aleatorias)
+        let emitted = |intensity: f32| {
+            let mut scene = Scene::new();
+            let emitter = Material::new_emissive(Color::new(255, 180, 90), intensity);
+            scene.add_cube(Cube { min: Vec3::new(2.0, 1.0, -1.0), max: Vec3::new(3.0, 2.0, 0.0), material: emitter, inverted: false }, &[]);
+            let calls = 2000;
+            (0..calls).map(|_| shade(&origin, &intersect, &scene, &settings, 0.0, None).x - unlit).sum::<f32>() / calls as f32
+        };
+
+        let single = emitted(6.0);
+        assert!(single > 0.05, "{}", single);
+        let double = emitted(12.0);
+        assert!((double / single - 2.0).abs() < 0.1, "{} {}", single, double);
+    }
+
+    #[test]
+    fn lights_below_the_minimum_intensity_are_skipped() {
+        let mut scene = Scene::new();
+        scene.lights.push(Light::new(Vec3::new(0.0, 5.0, 0.0), Color::new(255, 255, 255), 5e-4));
+        scene.lights.push(Light::new(Vec3::new(2.0, 5.0, 0.0), Color::new(255, 255, 255), 0.8));
+        let (origin, intersect) = grey_floor_hit();
+
+        // Solo las luces evaluadas (las que lanzan rayo de sombra) quedan en la traza
+        let settings = RenderSettings { min_light_intensity: 1e-3, ..small_settings(8, 8) };
+        let mut trace = ShadeTrace::new();
+        let culled = shade(&origin, &intersect, &scene, &settings, 0.0, Some(&mut trace));
+        let evaluated: Vec<usize> = trace.lights.iter().map(|light| light.light_index).collect();
+        assert_eq!(evaluated, vec![1]);
+
+        let settings = RenderSettings { min_light_intensity: 0.0, ..small_settings(8, 8) };
+        let mut trace = ShadeTrace::new();
+        let full = shade(&origin, &intersect, &scene, &settings, 0.0, Some(&mut trace));
+        assert_eq!(trace.lights.len(), 2);
+        // Lo que se pierde es despreciable
+        assert!((full - culled).magnitude() < 1e-3);
+    }
+
+    #[test]
+    fn missed_camera_ray_shows_the_backdrop_texel_at_its_screen_position() {
+        let mut scene = Scene::new();
+        let backdrop = std::sync::Arc::new(Texture::new("assets/sky.png"));
+        scene.backdrop = Some(backdrop.clone());
+        let settings = small_settings(8, 8);
+        let (origin, direction) = (Vec3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+
+        // (0, 0) de pantalla es el primer texel y (1, 1) el último, sin importar la dirección del rayo
+        let top_left = cast_camera_ray_at(&origin, &direction, (0.0, 0.0), &scene, &settings, 0.0);
+        assert_eq!(top_left.to_hex(), backdrop.get_color(0, 0).to_hex());
+        let bottom_right = cast_camera_ray_at(&origin, &direction, (1.0, 1.0), &scene, &settings, 0.0);
+        assert_eq!(bottom_right.to_hex(), backdrop.get_color(backdrop.width - 1, backdrop.height - 1).to_hex());
+        // Los rayos secundarios siguen viendo el cielo
+        assert_eq!(cast_ray(&origin, &direction, &scene, &settings, 1).to_hex(), SKYBOX_COLOR.to_hex());
+    }
+
+    #[test]
+    fn anisotropic_highlight_is_wider_along_the_tangent() {
+        let material = presets::brushed_metal(Color::new(200, 200, 200), Vec3::new(1.0, 0.0, 0.0));
+        assert!(material.roughness_u > material.roughness_v);
+        let intersect = Intersect::new(Vec3::zeros(), Vec3::new(0.0, 1.0, 0.0), 1.0, &material, 0.0, 0.0);
+        let view_dir = Vec3::new(0.0, 1.0, 0.0);
+
+        // La luz se aleja del reflejo perfecto el mismo ángulo a lo largo de la tangente y de la bitangente
+        let tilt = 0.3_f32;
+        let along_tangent = Vec3::new(tilt.sin(), tilt.cos(), 0.0);
+        let along_bitangent = Vec3::new(0.0, tilt.cos(), tilt.sin());
+        let peak = anisotropic_specular(&intersect, &view_dir, &view_dir, 1.0);
+        let tangent = anisotropic_specular(&intersect, &view_dir, &along_tangent, 1.0);
+        let bitangent = anisotropic_specular(&intersect, &view_dir, &along_bitangent, 1.0);
+
+        assert!((peak - 1.0).abs() < 1e-5);
+        assert!(tangent > 0.5, "{}", tangent);
+        assert!(bitangent < 0.01 && tangent > bitangent * 10.0, "{} vs {}", tangent, bitangent);
+    }
+
+    #[test]
+    fn two_glass_panes_let_through_half_of_what_one_does() {
+        // Paneles negros que dejan pasar la mitad de la luz, sin absorción ni refracción
+        let pane = |z: f32| {
+            let material = Material::new(Color::new(0, 0, 0), 10.0, [0.5, 0.0, 0.0, 0.5], 1.0, None, 0.0);
+            Cube { min: Vec3::new(-2.0, -2.0, z - 0.05), max: Vec3::new(2.0, 2.0, z + 0.05), material, inverted: false }
+        };
+        // Sin luz ambiental los paneles no aportan color propio: solo filtran el cielo
+        let mut one = Scene::new();
+        one.ambient_intensity = 0.0;
+        one.add_cube(pane(0.0), &[]);
+        let mut two = Scene::new();
+        two.ambient_intensity = 0.0;
+        two.add_cube(pane(0.0), &[]);
+        two.add_cube(pane(-1.0), &[]);
+
+        let settings = small_settings(8, 8);
+        let (origin, direction) = (Vec3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+        let sky = trace_ray(&origin, &direction, &Scene::new(), &settings, 0, 0.0, None);
+        let through_one = trace_ray(&origin, &direction, &one, &settings, 0, 0.0, None);
+        let through_two = trace_ray(&origin, &direction, &two, &settings, 0, 0.0, None);
+
+        assert!((through_one - sky * 0.5).magnitude() < 1e-5, "{:?}", through_one);
+        assert!((through_two - through_one * 0.5).magnitude() < 1e-5, "{:?}", through_two);
+    }
+
+    #[test]
+    fn particle_appears_at_its_projected_pixel_and_grows_with_size() {
+        let camera = Camera::new(Vec3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+        let settings = small_settings(40, 40);
+        let position = Vec3::new(0.6, 0.4, 0.0);
+
+        let red_pixels = |size: f32| {
+            let mut scene = Scene::new();
+            scene.add_particle(particle::Particle::new(position, size, Color::new(255, 0, 0)), &[]);
+            let image = render_to_image(&scene, &camera, &settings);
+            let (x, y) = camera.project(&position, 40.0, 40.0, settings.fov, 1.0).unwrap();
+            let center = image.get_pixel(x as u32, y as u32);
+            assert_eq!((center[0], center[1], center[2]), (255, 0, 0), "size {}", size);
+            image.pixels().filter(|p| p[0] == 255 && p[1] == 0 && p[2] == 0).count()
+        };
+
+        let small = red_pixels(0.3);
+        let large = red_pixels(0.9);
+        // El área del disco crece con el cuadrado del tamaño (~9 veces)
+        assert!(small > 0 && large > small * 6, "{} vs {}", small, large);
+    }
+
+    #[test]
+    fn two_materials_get_distinct_ids_and_the_background_is_zero() {
+        let mut scene = Scene::new();
+        let red = Material::new(Color::new(200, 30, 30), 10.0, [0.9, 0.1, 0.0, 0.0], 1.0, None, 0.0);
+        let blue = Material::new(Color::new(30, 30, 200), 10.0, [0.9, 0.1, 0.0, 0.0], 1.0, None, 0.0);
+        scene.add_cube(Cube { min: Vec3::new(-2.0, -1.0, -1.0), max: Vec3::new(-0.2, 1.0, 1.0), material: red, inverted: false }, &[]);
+        scene.add_cube(Cube { min: Vec3::new(0.2, -1.0, -1.0), max: Vec3::new(2.0, 1.0, 1.0), material: blue, inverted: false }, &[]);
+        let camera = Camera::new(Vec3::new(0.0, 0.0, 6.0), Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+        let passes = render_passes(&scene, &camera, &small_settings(32, 16));
+
+        let (left, right) = (passes.material_id[8 * 32 + 12], passes.material_id[8 * 32 + 20]);
+        assert_ne!(left, passes::BACKGROUND_ID);
+        assert_ne!(right, passes::BACKGROUND_ID);
+        assert_ne!(left, right);
+        assert_eq!(passes.material_id[0], passes::BACKGROUND_ID);
+        assert_ne!(passes::id_color(left).to_hex(), passes::id_color(right).to_hex());
+        assert_eq!(passes::id_color(passes::BACKGROUND_ID).to_hex(), 0);
+    }
+
+    // Escena vacía con un cielo HDR uniforme de radiancia `rgb`
+    fn hdr_sky_scene(name: &str, rgb: [f32; 3]) -> Scene {
+        let path = std::env::temp_dir().join(format!("raytracing_{}_{}.hdr", name, std::process::id()));
+        image::Rgb32FImage::from_pixel(4, 2, image::Rgb(rgb)).save(&path).unwrap();
+        let mut scene = Scene::new();
+        scene.skybox = Some(std::sync::Arc::new(Texture::try_new_hdr(path.to_str().unwrap()).unwrap()));
+        std::fs::remove_file(&path).ok();
+        scene
+    }
+
+    #[test]
+    fn tiny_time_budget_still_traces_the_first_pass_and_large_budget_completes() {
+        let (scene, camera) = cube_scene();
+        let full = render_to_image(&scene, &camera, &small_settings(24, 32));
+
+        let mut partial = Framebuffer::new(24, 32);
+        partial.clear();
+        let settings = RenderSettings { time_budget: Some(Duration::ZERO), ..small_settings(24, 32) };
+        assert!(!render(&mut partial, &scene, &camera, &settings));
+        let partial = partial.to_image();
+        // Cada 8 filas hay una trazada de verdad; las demás copian la anterior
+        for y in (0..32).step_by(8) {
+            for x in 0..24 {
+                assert_eq!(partial.get_pixel(x, y), full.get_pixel(x, y));
+            }
+        }
+        assert_ne!(partial, full);
+
+        let mut complete = Framebuffer::new(24, 32);
+        let settings = RenderSettings { time_budget: Some(Duration::from_secs(60)), ..small_settings(24, 32) };
+        assert!(render(&mut complete, &scene, &camera, &settings));
+        assert_eq!(complete.to_image(), full);
+    }
+
+    #[test]
+    fn hdr_skybox_radiance_is_not_clamped() {
+        let scene = hdr_sky_scene("sky", [3.0, 2.0, 1.5]);
+        let direction = Vec3::new(0.3, 0.2, 1.0).normalize();
+        let radiance = trace_ray(&Vec3::zeros(), &direction, &scene, &small_settings(4, 4), 0, 0.0, None);
+        assert!(radiance.x > 2.9 && radiance.y > 1.9 && radiance.z > 1.0);
+    }
+
+    #[test]
+    fn accumulated_halton_frames_converge_towards_the_supersampled_edge() {
+        let (scene, camera) = cube_scene();
+        let settings = RenderSettings { aa_samples: 2, sample_sequence: SampleSequence::Halton, ..small_settings(12, 9) };
+        let reference = render_to_hdr(&scene, &camera, &RenderSettings { aa_samples: 256, ..settings.clone() }).average();
+
+        let error = |hdr: &HdrImage| {
+            hdr.average().iter().zip(&reference).map(|(a, b)| (a - b).magnitude()).sum::<f32>()
+        };
+        let mut accumulation = HdrImage::new(12, 9);
+        render_accumulate(&mut accumulation, &scene, &camera, &settings);
+        let single_frame_error = error(&accumulation);
+        for frame_index in 1..16 {
+            render_accumulate(&mut accumulation, &scene, &camera, &RenderSettings { frame_index, ..settings.clone() });
+        }
+        assert_eq!(accumulation.frames, 16);
+        assert!(error(&accumulation) < single_frame_error * 0.5);
+    }
+
+    #[test]
+    fn adaptive_pixels_stop_early_on_flat_regions_and_keep_sampling_on_edges() {
+        let (scene, camera) = cube_scene();
+        let settings = RenderSettings { aa_samples: 64, adaptive_threshold: Some(0.01), ..small_settings(16, 12) };
+        let samples_taken = |x: usize, y: usize| render_pixel_samples(x, y, 16.0, 12.0, &scene, &camera, &settings).1.len();
+
+        // Cielo y caras del cubo son planos; solo los dos bordes del cubo en la fila usan todas las muestras
+        let (min, max) = (settings.min_adaptive_samples as usize, settings.aa_samples as usize);
+        let row: Vec<usize> = (0..16).map(|x| samples_taken(x, 6)).collect();
+        assert_eq!(samples_taken(0, 0), min);
+        assert_eq!(samples_taken(8, 6), min);
+        assert_eq!(row.iter().filter(|&&count| count == max).count(), 2, "{:?}", row);
+        assert!(row.iter().all(|&count| count == min || count == max), "{:?}", row);
+    }
+
+    #[test]
+    fn render_measures_pixels_with_the_target_buffer_size() {
+        let mut scene = Scene::new();
+        let shiny = Material::new(Color::new(90, 90, 200), 50.0, [0.5, 0.5, 0.0, 0.0], 1.0, None, 0.0);
+        scene.add_cube(Cube { min: Vec3::new(-1.0, -1.0, -1.0), max: Vec3::new(1.0, 1.0, 1.0), material: shiny, inverted: false }, &[]);
+        // Luz en el reflejo especular de la cámara sobre la cara +Z: el brillo llega hasta los bordes
+        scene.lights = vec![Light::new(Vec3::new(-3.0, -2.5, 5.0), Color::new(255, 255, 255), 1.0)];
+        let camera = Camera::new(Vec3::new(3.0, 2.5, 4.0), Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+        let matching = RenderSettings { specular_aa: true, ..small_settings(16, 12) };
+        let window_sized = RenderSettings { width: 800, height: 600, ..matching.clone() };
+
+        // Un búfer de 16x12 se traza igual aunque los ajustes digan 800x600 (resolución interna)
+        let mut expected = Framebuffer::new(16, 12);
+        render(&mut expected, &scene, &camera, &matching);
+        let mut low_res = Framebuffer::new(16, 12);
+        render(&mut low_res, &scene, &camera, &window_sized);
+        assert_eq!(low_res.buffer, expected.buffer);
+    }
+
+    #[test]
+    fn hdr_render_keeps_radiance_above_one() {
+        let scene = hdr_sky_scene("render_hdr", [3.5, 1.0, 0.5]);
+        let camera = Camera::new(Vec3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+        let hdr = render_to_hdr(&scene, &camera, &small_settings(4, 3));
+
+        assert_eq!((hdr.width, hdr.height, hdr.frames), (4, 3, 1));
+        assert!(hdr.average().iter().all(|pixel| (pixel.x - 3.5).abs() < 0.05));
+    }
+
+    #[test]
+    fn tone_map_setting_is_applied_to_the_rendered_image() {
+        let scene = hdr_sky_scene("tonemap", [0.5, 0.5, 0.5]);
+        let camera = Camera::new(Vec3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+        let red = |tone_map: ToneMap, exposure: f32| {
+            let settings = RenderSettings { tone_map, exposure, ..small_settings(4, 4) };
+            render_to_image(&scene, &camera, &settings).get_pixel(2, 2)[0]
+        };
+
+        // Radiancia 2.0 (0.5 * exposición 4): clamp satura, las curvas comprimen
+        assert_eq!(red(ToneMap::Clamp, 4.0), 255);
+        let aces = red(ToneMap::Aces, 4.0);
+        let reinhard = red(ToneMap::Reinhard, 4.0);
+        assert!(aces < 255 && reinhard < aces);
+        assert_eq!(reinhard, (2.0f32 / 3.0 * 255.0).round() as u8);
+        assert_eq!(red(ToneMap::Clamp, 1.0), 128);
+    }
+
+    fn glass_block_scene(refractive_index: f32, dispersion: f32) -> Scene {
+        let mut scene = Scene::new();
+        let mut glass = Material::new(Color::new(255, 255, 255), 10.0, [0.0, 0.0, 0.0, 1.0], refractive_index, None, 0.0);
+        glass.dispersion = dispersion;
+        scene.add_cube(Cube { min: Vec3::new(-1.0, -1.0, -1.0), max: Vec3::new(1.0, 1.0, 1.0), material: glass, inverted: false }, &[]);
+        scene
+    }
+
+    #[test]
+    fn refraction_bends_the_ray_through_a_glass_block() {
+        // Rayo oblicuo por las caras z = 1 y z = -1: sale paralelo pero desplazado hacia -x
+        let origin = Vec3::new(-1.5, 0.0, 3.0);
+        let direction = Vec3::new(0.5, 0.0, -1.0).normalize();
+        let inside = refract(&direction, &Vec3::new(0.0, 0.0, 1.0), 1.5).unwrap();
+        let exit = Vec3::new(-0.5, 0.0, 1.0) + inside * (2.0 / -inside.z);
+        let outside = refract(&inside, &Vec3::new(0.0, 0.0, -1.0), 1.5).unwrap();
+        assert!((outside - direction).magnitude() < 1e-5);
+
+        // Blanco rojo donde llega el rayo desviado; en línea recta el rayo pasa a su lado
+        let target_center = exit + outside * (3.0 / -outside.z);
+        let target = Material::new(Color::new(255, 0, 0), 10.0, [0.9, 0.0, 0.0, 0.0], 1.0, None, 0.0);
+        let half = Vec3::new(0.15, 0.2, 0.2);
+        let with_target = |refractive_index: f32| {
+            let mut scene = glass_block_scene(refractive_index, 0.0);
+            scene.add_cube(Cube { min: target_center - half, max: target_center + half, material: target.clone(), inverted: false }, &[]);
+            scene.lights = vec![Light::new(target_center + Vec3::new(0.0, 0.0, 2.0), Color::new(255, 255, 255), 1.0)];
+            trace_ray(&origin, &direction, &scene, &RenderSettings::default(), 0, 0.0, None)
+        };
+
+        let refracted = with_target(1.5);
+        assert!(refracted.x > refracted.z * 2.0, "{:?}", refracted);
+        let straight = with_target(1.0);
+        assert!(straight.z > straight.x, "{:?}", straight);
+    }
+
+    #[test]
+    fn dispersion_splits_the_channels_and_zero_dispersion_keeps_them_together() {
+        let incident = Vec3::new(0.6, -0.2, -1.0).normalize();
+        let normal = Vec3::new(0.0, 0.0, 1.0);
+        let mut material = Material::new(Color::new(255, 255, 255), 10.0, [0.0, 0.0, 0.0, 1.0], 1.5, None, 0.0);
+        let angle = |dir: Option<Vec3>| (-dir.unwrap().dot(&normal)).acos();
+
+        let [r, g, b] = refract_dispersed(&incident, &normal, &material).map(angle);
+        assert_eq!((r, g), (g, b));
+
+        material.dispersion = 0.1;
+        let [r, g, b] = refract_dispersed(&incident, &normal, &material).map(angle);
+        // El azul (índice mayor) se acerca más a la normal
+        assert!(r - g > 1e-3 && g - b > 1e-3, "{} {} {}", r, g, b);
+
+        // En el render: tras el bloque cada canal sale desplazado distinto. Un blanco cuyo borde cae
+        // entre la salida del rojo y la del verde solo lo ve el canal rojo
+        let origin = Vec3::new(-1.5, 0.0, 3.0);
+        let direction = Vec3::new(0.5, 0.0, -1.0).normalize();
+        let x_at_target = |refractive_index: f32| {
+            let inside = refract(&direction, &Vec3::new(0.0, 0.0, 1.0), refractive_index).unwrap();
+            let exit = Vec3::new(-0.5, 0.0, 1.0) + inside * (2.0 / -inside.z);
+            exit.x + direction.x * (3.0 / -direction.z)
+        };
+        let (red_x, green_x) = (x_at_target(1.3), x_at_target(1.5));
+        assert!(red_x > green_x + 0.1);
+
+        let trace = |dispersion: f32| {
+            let mut scene = glass_block_scene(1.5, dispersion);
+            let target = Material::new(Color::new(255, 255, 255), 10.0, [0.9, 0.0, 0.0, 0.0], 1.0, None, 0.0);
+            let min = Vec3::new((red_x + green_x) / 2.0, -0.2, -4.04);
+            scene.add_cube(Cube { min, max: min + Vec3::new(0.5, 0.4, 0.04), material: target, inverted: false }, &[]);
+            scene.lights = vec![Light::new(Vec3::new(red_x + 0.25, 0.0, -2.0), Color::new(255, 255, 255), 1.0)];
+            trace_ray(&origin, &direction, &scene, &RenderSettings::default(), 0, 0.0, None)
+        };
+        let (split, together) = (trace(0.4), trace(0.0));
+        let sky = SKYBOX_COLOR.to_radiance();
+        assert!((together - sky).magnitude() < 1e-5, "{:?}", together);
+        assert!((split.x - sky.x).abs() > 0.05, "{:?}", split);
+        assert!((split.yz() - sky.yz()).magnitude() < 1e-5, "{:?}", split);
+    }
+
+    #[test]
+    fn ray_aimed_at_the_sun_sees_the_disc_only_on_the_analytic_day_sky() {
+        let sun = Vec3::new(0.3, 0.8, -0.5).normalize();
+        let mut scene = Scene::new();
+        scene.sun_direction = Some(sun);
+        let settings = RenderSettings::default();
+        let trace = |scene: &Scene, direction: &Vec3| trace_ray(&Vec3::new(0.0, 0.0, 0.0), direction, scene, &settings, 0, 0.0, None);
+
+        let disc = trace(&scene, &sun);
+        assert_eq!(Color::from_radiance(&disc).to_hex(), scene.sun_color.to_hex());
+        let beside = trace(&scene, &(sun + Vec3::new(0.2, 0.0, 0.0)).normalize());
+        assert!(beside.x < disc.x - 0.2, "{:?}", beside);
+
+        // De noche (sol bajo el horizonte) no hay disco
+        let night = Vec3::new(sun.x, -sun.y, sun.z);
+        scene.sun_direction = Some(night);
+        assert!(trace(&scene, &night).magnitude() < 0.2);
+
+        // Sobre una textura de cielo tampoco: se ve la textura
+        let mut textured = hdr_sky_scene("sun_disc", [0.1, 0.2, 0.3]);
+        textured.sun_direction = Some(sun);
+        assert!((trace(&textured, &sun) - Vec3::new(0.1, 0.2, 0.3)).magnitude() < 0.01);
+    }
+
+    #[test]
+    fn sun_glint_replaces_the_phong_highlight_of_the_sun_light() {
+        // Piso pulido visto en el reflejo del sol
+        let mut scene = Scene::new();
+        let floor = Material::new(Color::new(40, 40, 40), 50.0, [0.2, 0.8, 0.0, 0.0], 1.0, None, 0.0);
+        scene.add_cube(Cube { min: Vec3::new(-5.0, -1.0, -5.0), max: Vec3::new(5.0, 0.0, 5.0), material: floor, inverted: false }, &[]);
+        let sun = Vec3::new(0.0, 1.0, -1.0).normalize();
+        let sun_light = Light::new(sun * 50.0, Color::new(255, 255, 255), 1.0);
+        let origin = Vec3::new(0.0, 2.0, 2.0);
+        let direction = Vec3::new(0.0, -1.0, -1.0).normalize();
+        let trace = |scene: &Scene| trace_ray(&origin, &direction, scene, &RenderSettings::default(), 0, 0.0, None);
+
+        scene.lights = vec![Light { specular: false, ..sun_light.clone() }];
+        let no_highlight = trace(&scene);
+        scene.sun_direction = Some(sun);
+        let glint = trace(&scene);
+        scene.lights = vec![sun_light];
+        let doubled = trace(&scene);
+
+        assert!(glint.x > no_highlight.x + 0.5, "{:?} {:?}", glint, no_highlight);
+        assert!(doubled.x > glint.x + 0.1, "{:?} {:?}", doubled, glint);
+    }
+
+    #[test]
+    fn moving_camera_blurs_a_static_edge_across_the_shutter() {
+        let (scene, mut camera) = cube_scene();
+        let settings = RenderSettings { shutter: 1.0, motion_samples: 16, ..small_settings(16, 12) };
+        let sharp = render_to_image(&scene, &camera, &settings);
+
+        camera.eye_velocity = Vec3::new(1.5, 0.0, 0.0);
+        camera.center_velocity = Vec3::new(1.5, 0.0, 0.0);
+        // Extremos del obturador sin motion blur
+        let instant = RenderSettings { shutter: 0.0, ..settings.clone() };
+        let start = render_to_image(&scene, &camera.at_time(0.0), &instant);
+        let end = render_to_image(&scene, &camera.at_time(1.0), &instant);
+        let blurred = render_to_image(&scene, &camera, &settings);
+
+        assert_eq!(start, sharp);
+        assert_ne!(blurred, sharp);
+        // Los píxeles del borde mezclan el cubo y el cielo: no coinciden con ningún extremo del obturador
+        let mixed = blurred
+            .enumerate_pixels()
+            .filter(|(x, y, pixel)| **pixel != *start.get_pixel(*x, *y) && **pixel != *end.get_pixel(*x, *y))
+            .count();
+        assert!(mixed > 0);
+    }
+
+    #[test]
+    fn pixel_trace_records_the_hit_and_every_bounce() {
+        let (mut scene, camera) = cube_scene();
+        scene.lights = vec![Light::new(Vec3::new(0.0, 0.0, 5.0), Color::new(255, 255, 255), 1.0)];
+        let settings = small_settings(8, 8);
+        let trace = trace_pixel(4, 4, &scene, &camera, &settings);
+        let hit = trace.primary_hit().expect("the center pixel sees the cube");
+        assert!((hit.distance - 5.0).abs() < 1e-4);
+        assert!((hit.normal - Vec3::new(0.0, 0.0, 1.0)).magnitude() < 1e-5);
+        assert_eq!(hit.shading.lights.len(), 1);
+        assert_eq!(hit.shading.lights[0].shadow, 0.0);
+        assert_eq!(trace.bounces.len(), 1);
+        let pixel = render_to_image(&scene, &camera, &settings).get_pixel(4, 4).0;
+        assert_eq!([trace.color.r(), trace.color.g(), trace.color.b()], pixel);
+
+        // Vidrio: el rayo de cámara entra, se refracta hasta la cara de salida y sale al cielo
+        let glass = glass_block_scene(1.5, 0.0);
+        let trace = trace_pixel(4, 4, &glass, &camera, &settings);
+        let steps: Vec<(BounceKind, u32, bool)> = trace.bounces.iter().map(|b| (b.kind, b.depth, b.hit.is_some())).collect();
+        assert_eq!(
+            steps,
+            vec![(BounceKind::Camera, 0, true), (BounceKind::Refraction, 1, true), (BounceKind::Refraction, 2, false)]
+        );
+        assert!((trace.bounces[1].hit.as_ref().unwrap().distance - 2.0).abs() < 1e-3);
+        assert!(trace.to_string().contains("Refraction ray (depth 2)"));
+        assert!(trace.bounces.iter().all(|bounce| bounce.depth < MAX_DEPTH));
+    }
+
+    #[test]
+    fn passes_share_the_render_core_with_supersampling_backdrop_and_region() {
+        let (mut scene, camera) = cube_scene();
+        scene.backdrop = Some(std::sync::Arc::new(Texture::missing("backdrop", crate::texture::ColorSpace::Linear)));
+        let settings = RenderSettings { aa_samples: 4, region: Some((2, 1, 14, 10)), ..small_settings(16, 12) };
+        let passes = render_passes(&scene, &camera, &settings);
+
+        let mut framebuffer = Framebuffer::new(16, 12);
+        framebuffer.clear();
+        render(&mut framebuffer, &scene, &camera, &settings);
+        for y in 1..10 {
+            for x in 2..14 {
+                assert_eq!(passes.beauty[y * 16 + x].to_hex(), framebuffer.buffer[y * 16 + x], "({}, {})", x, y);
+            }
+        }
+        // Fuera de la región no se traza nada
+        assert_eq!(passes.beauty[0].to_hex(), 0);
+        assert_eq!(passes.depth[11 * 16 + 15], f32::INFINITY);
+        // Con varias muestras la normal del centro sigue siendo la de la cara frontal
+        assert!((passes.normal[6 * 16 + 8] - Vec3::new(0.0, 0.0, 1.0)).magnitude() < 1e-5);
+    }
+
+    #[test]
+    fn only_frame_index_and_exposure_keep_the_same_traced_image() {
+        let settings = small_settings(8, 8);
+        assert!(settings.traces_same_image(&RenderSettings { frame_index: 7, exposure: 2.5, ..settings.clone() }));
+        assert!(!settings.traces_same_image(&RenderSettings { preview: true, ..settings.clone() }));
+        assert!(!settings.traces_same_image(&RenderSettings { fov: 1.0, ..settings.clone() }));
+        assert!(!settings.traces_same_image(&RenderSettings { tone_map: settings.tone_map.next(), ..settings.clone() }));
+    }
+
+    #[test]
+    fn stacked_blockers_size_the_penumbra_by_the_nearer_one() {
+        let mut light = Light::new(Vec3::new(0.0, 10.0, 0.0), Color::new(255, 255, 255), 1.0);
+        light.radius = 2.0;
+        light.shadow_samples = 64;
+        let floor = Material::black();
+        let plate = Material::new(Color::new(200, 200, 200), 10.0, [0.9, 0.1, 0.0, 0.0], 1.0, None, 0.0);
+        // Placas con el borde en x = 0 a las alturas dadas; la primera queda primera en el orden lineal
+        let scene_with = |heights: &[f32]| {
+            let mut scene = Scene::new();
+            for &height in heights {
+                let min = Vec3::new(-20.0, height, -20.0);
+                scene.add_cube(Cube { min, max: Vec3::new(0.0, height + 0.1, 20.0), material: plate.clone(), inverted: false }, &[]);
+            }
+            scene
+        };
+        let shadow_at = |scene: &Scene, x: f32| {
+            sampling::seed_pixel(148, (x * 100.0) as usize, 1, 0);
+            let intersect = Intersect::new(Vec3::new(x, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0), 1.0, &floor, 0.0, 0.0);
+            cast_soft_shadow(&intersect, scene, &light, 0.0)
+        };
+
+        // Bajo la placa cercana todo rayo que toca la lejana cruza antes la cercana: la penumbra
+        // debe ser la de la cercana sola, aunque la lejana se encuentre primero
+        let near = scene_with(&[3.0]);
+        let stacked = scene_with(&[6.0, 3.0]);
+        let mut penumbra = 0;
+        for i in 1..=20 {
+            let x = -0.1 * i as f32;
+            let expected = shadow_at(&near, x);
+            assert_eq!(shadow_at(&stacked, x), expected, "x = {}", x);
+            penumbra += (expected > 0.05 && expected < 0.95) as usize;
+        }
+        assert!(penumbra > 0);
+    }
+}
@@ -1,11 +1,69 @@
 use nalgebra_glm::Vec3;
+use std::f32::consts::PI;
+use std::sync::Arc;
+use crate::aabb::Aabb;
 use crate::color::Color;
+use crate::sampling;
+use crate::texture::Texture;
+
+// Iluminancia (lux) que equivale a un factor de luz 1.0 en el sombreado
+pub const REFERENCE_ILLUMINANCE: f32 = 100.0;
+
+// Cómo se interpreta `Light::intensity`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LightUnit {
+    Relative, // Factor directo, sin atenuación por distancia
+    Lumens,   // Flujo luminoso de una fuente puntual, cae con 1/d² (1 unidad de escena = 1 metro)
+}
+
+// Plantilla proyectada por una luz (sombra de una ventana, follaje): la textura se estira sobre
+// el cono de `fov` radianes alrededor de `direction` y su luminancia multiplica la intensidad
+#[derive(Clone)]
+pub struct Gobo {
+    pub texture: Arc<Texture>,
+    pub direction: Vec3,
+    pub fov: f32,
+}
+
+impl Gobo {
+    pub fn new(texture: Arc<Texture>, direction: Vec3, fov: f32) -> Self {
+        Gobo { texture, direction, fov }
+    }
+
+    // Factor 0..1 para la dirección luz -> punto; fuera del cono no llega luz
+    pub fn attenuation(&self, to_point: &Vec3) -> f32 {
+        let forward = self.direction.normalize();
+        let depth = to_point.dot(&forward);
+        if depth <= 0.0 {
+            return 0.0;
+        }
+
+        // Proyección perspectiva sobre el plano de la plantilla, como una cámara en la luz
+        let (right, up) = sampling::tangent_basis(&forward);
+        let half_extent = (self.fov / 2.0).tan();
+        let x = to_point.dot(&right) / depth / half_extent;
+        let y = to_point.dot(&up) / depth / half_extent;
+        if x.abs() > 1.0 || y.abs() > 1.0 {
+            return 0.0;
+        }
+
+        let texel = self.texture.get_bilinear_at_uv((x + 1.0) / 2.0, (y + 1.0) / 2.0);
+        Color::from_rgb_f32(texel.x, texel.y, texel.z).luminance()
+    }
+}
 
 #[derive(Clone)]
 pub struct Light {
     pub position: Vec3,
     pub color: Color,
     pub intensity: f32,
+    pub unit: LightUnit,
+    pub casts_shadows: bool,         // false para luces de relleno
+    pub specular: bool,              // false si el brillo lo aporta otro término (el destello del sol)
+    pub shadow_tint: Option<Color>,  // Color de las zonas en sombra (None = oscuridad)
+    pub radius: f32,                 // Radio del disco emisor; 0 = luz puntual con sombras duras
+    pub shadow_samples: u32,         // Rayos de sombra por punto cuando radius > 0
+    pub gobo: Option<Gobo>,          // Plantilla proyectada que modula la intensidad
 }
 
 impl Light {
@@ -14,6 +72,196 @@ impl Light {
             position,
             color,
             intensity,
+            unit: LightUnit::Relative,
+            casts_shadows: true,
+            specular: true,
+            shadow_tint: None,
+            radius: 0.0,
+            shadow_samples: 8,
+            gobo: None,
+        }
+    }
+
+    // Fuente puntual con potencia física, ej. una bombilla de 800 lm
+    pub fn new_lumens(position: Vec3, color: Color, lumens: f32) -> Self {
+        Light {
+            position,
+            color,
+            intensity: lumens,
+            unit: LightUnit::Lumens,
+            casts_shadows: true,
+            specular: true,
+            shadow_tint: None,
+            radius: 0.0,
+            shadow_samples: 8,
+            gobo: None,
+        }
+    }
+
+    // Luz sobre el centro de la escena, `height` unidades por encima de su punto más alto
+    pub fn above_scene(bounds: &Aabb, height: f32, color: Color, intensity: f32) -> Self {
+        let center = bounds.centroid();
+        Light::new(Vec3::new(center.x, bounds.max.y + height, center.z), color, intensity)
+    }
+
+    // Luz desplazada desde el centro de la escena en múltiplos del tamaño de la caja por eje,
+    // así sigue en el mismo lugar relativo si la escena se mueve o se escala
+    pub fn relative_to_scene(bounds: &Aabb, offset: Vec3, color: Color, intensity: f32) -> Self {
+        let position = bounds.centroid() + offset.component_mul(&bounds.size());
+        Light::new(position, color, intensity)
+    }
+
+    // Factor de luz que llega a `point`, con atenuación por distancia y la plantilla si la hay
+    pub fn intensity_towards(&self, point: &Vec3) -> f32 {
+        let to_point = point - self.position;
+        let intensity = self.intensity_at(to_point.magnitude());
+        match &self.gobo {
+            Some(gobo) => intensity * gobo.attenuation(&to_point),
+            None => intensity,
+        }
+    }
+
+    // Factor de luz que llega a un punto a `distance` de la fuente
+    pub fn intensity_at(&self, distance: f32) -> f32 {
+        match self.unit {
+            LightUnit::Relative => self.intensity,
+            LightUnit::Lumens => {
+                // Flujo repartido en la esfera: E = Φ / (4π d²), en lux
+                let distance = distance.max(0.01);
+                let illuminance = self.intensity / (4.0 * PI * distance * distance);
+                illuminance / REFERENCE_ILLUMINANCE
+            }
         }
     }
-}
\ No newline at end of file
+}
+
+// Luces a evaluar en `point`, cada una con el factor que compensa su probabilidad de selección.
+// Con pocas luces se usan todas (factor 1); con más de `max_direct_lights` se elige una sola con
+// probabilidad proporcional a su aporte aproximado (intensidad / distancia²) y se divide por esa pdf
+pub fn select_lights(lights: &[Light], point: &Vec3, max_direct_lights: usize) -> Vec<(usize, f32)> {
+    if lights.len() <= max_direct_lights {
+        return (0..lights.len()).map(|i| (i, 1.0)).collect();
+    }
+
+    let weights: Vec<f32> = lights
+        .iter()
+        .map(|light| {
+            let distance_squared = (light.position - point).magnitude_squared().max(1e-3);
+            let brightness = light.color.luminance().max(1e-3);
+            light.intensity_towards(point) * brightness / distance_squared
+        })
+        .collect();
+    let total: f32 = weights.iter().sum();
+    if total <= 0.0 {
+        return Vec::new();
+    }
+
+    let mut pick = sampling::random_f32() * total;
+    for (index, weight) in weights.iter().enumerate() {
+        if pick < *weight {
+            return vec![(index, total / weight)];
+        }
+        pick -= weight;
+    }
+    // Redondeo: la última luz con peso
+    let index = weights.iter().rposition(|w| *w > 0.0).unwrap_or(0);
+    vec![(index, total / weights[index])]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn doubling_intensity_doubles_the_light_at_a_fixed_point() {
+        let point = Vec3::new(0.0, 0.0, -3.0);
+        for unit in [LightUnit::Relative, LightUnit::Lumens] {
+            let mut light = Light::new(Vec3::new(0.0, 2.0, 0.0), Color::new(255, 255, 255), 800.0);
+            light.unit = unit;
+            let distance = (light.position - point).magnitude();
+            let single = light.intensity_at(distance);
+            light.intensity *= 2.0;
+            assert!((light.intensity_at(distance) - 2.0 * single).abs() < 1e-5, "{:?}", unit);
+        }
+
+        // 4π·100 lm a 1 m dan 100 lux, la iluminancia de referencia
+        let lamp = Light::new_lumens(Vec3::zeros(), Color::new(255, 255, 255), 4.0 * PI * REFERENCE_ILLUMINANCE);
+        assert!((lamp.intensity_at(1.0) - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn relative_light_follows_the_scene_when_its_bounds_move() {
+        let white = Color::new(255, 255, 255);
+        let bounds = Aabb::new(Vec3::new(-2.0, 0.0, -1.0), Vec3::new(2.0, 3.0, 1.0));
+        let shift = Vec3::new(10.0, -4.0, 7.0);
+        let moved = Aabb::new(bounds.min + shift, bounds.max + shift);
+
+        let offset = Vec3::new(0.5, 1.0, 0.0);
+        let light = Light::relative_to_scene(&bounds, offset, white, 1.0);
+        assert_eq!(light.position, Vec3::new(2.0, 4.5, 0.0));
+        assert_eq!(Light::relative_to_scene(&moved, offset, white, 1.0).position, light.position + shift);
+
+        let above = Light::above_scene(&bounds, 2.0, white, 1.0);
+        assert_eq!(above.position, Vec3::new(0.0, 5.0, 0.0));
+        assert_eq!(Light::above_scene(&moved, 2.0, white, 1.0).position, above.position + shift);
+    }
+
+    #[test]
+    fn gobo_lights_the_regions_that_match_its_template() {
+        // Plantilla con la esquina superior izquierda blanca, la superior derecha gris y la mitad inferior negra
+        let path = std::env::temp_dir().join(format!("raytracing_gobo_{}.png", std::process::id()));
+        image::RgbImage::from_fn(8, 8, |x, y| match (x < 4, y < 4) {
+            (true, true) => image::Rgb([255, 255, 255]),
+            (false, true) => image::Rgb([128, 128, 128]),
+            _ => image::Rgb([0, 0, 0]),
+        })
+        .save(&path)
+        .unwrap();
+        let texture = Arc::new(Texture::try_new(path.to_str().unwrap()).unwrap());
+        std::fs::remove_file(&path).ok();
+
+        // Mirando hacia -y, la parte superior de la plantilla cae hacia +x y la izquierda hacia +z
+        let mut light = Light::new(Vec3::new(0.0, 5.0, 0.0), Color::new(255, 255, 255), 0.8);
+        light.gobo = Some(Gobo::new(texture, Vec3::new(0.0, -1.0, 0.0), PI / 2.0));
+        let white = light.intensity_towards(&Vec3::new(3.0, 0.0, 3.0));
+        let grey = light.intensity_towards(&Vec3::new(3.0, 0.0, -3.0));
+
+        assert!((white - 0.8).abs() < 1e-4, "{}", white);
+        assert!(grey > 0.1 && grey < 0.7, "{}", grey);
+        for point in [Vec3::new(-3.0, 0.0, 3.0), Vec3::new(-3.0, 0.0, -3.0)] {
+            let dark = light.intensity_towards(&point);
+            assert!(dark < 1e-4, "{:?}: {}", point, dark);
+        }
+        // Fuera del cono de la plantilla no llega luz
+        assert_eq!(light.intensity_towards(&Vec3::new(20.0, 0.0, 0.0)), 0.0);
+    }
+
+    #[test]
+    fn one_importance_sampled_light_is_unbiased_for_every_light() {
+        let lights: Vec<Light> = (0..12)
+            .map(|i| {
+                let angle = i as f32 * PI / 6.0;
+                let position = Vec3::new(4.0 * angle.cos(), 2.0 + i as f32 * 0.5, 4.0 * angle.sin());
+                Light::new(position, Color::new(255, 200 + i * 4, 150), 0.2 + 0.1 * i as f32)
+            })
+            .collect();
+        let point = Vec3::zeros();
+
+        // Con pocas luces se evalúan todas sin compensación
+        assert_eq!(select_lights(&lights, &point, 12), (0..12).map(|i| (i, 1.0)).collect::<Vec<_>>());
+
+        // Eligiendo una sola, el factor esperado de cada luz es 1 (el mismo peso que en el bucle completo)
+        let samples = 20000;
+        let mut expected_factor = [0.0; 12];
+        for _ in 0..samples {
+            let selected = select_lights(&lights, &point, 1);
+            assert_eq!(selected.len(), 1);
+            for (index, factor) in selected {
+                expected_factor[index] += factor / samples as f32;
+            }
+        }
+        for factor in expected_factor {
+            assert!((factor - 1.0).abs() < 0.2, "{:?}", expected_factor);
+        }
+    }
+}
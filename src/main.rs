@@ -1,220 +1,71 @@
-use core::f32;
-use minifb::{Key, Window, WindowOptions};
+use minifb::{Key, KeyRepeat, MouseButton, MouseMode, Window, WindowOptions};
 use nalgebra_glm::Vec3;
 use std::time::{Duration, Instant};
 use std::f32::consts::PI;
-use std::f32::INFINITY;
-use rand::Rng;
-
-mod framebuffer;
-use framebuffer::Framebuffer;
-
-mod cube;
-use cube::Cube;
-
-mod ray_intersect;
-use ray_intersect::{Intersect, RayIntersect};
-
-mod color;
-use color::Color;
-
-mod camera;
-use camera::Camera;
-
-mod material;
-use material::Material;
-
-mod light;
-use light::Light;
-
-mod texture;
 use std::sync::Arc;
-use texture::Texture;
-
-const BIAS: f32 = 0.001;
-const SKYBOX_COLOR: Color = Color::new(135, 206, 235); // Light sky blue
-
-const AMBIENT_LIGHT_COLOR: Color = Color::new(50, 50, 50);
-const AMBIENT_INTENSITY: f32 = 0.3;
-
-fn offset_point(intersect: &Intersect, direction: &Vec3) -> Vec3 {
-    let offset = intersect.normal * BIAS;
-    intersect.point + offset
-}
-
-fn reflect(incident: &Vec3, normal: &Vec3) -> Vec3 {
-    incident - 2.0 * incident.dot(normal) * normal
-}
-
-fn cast_shadow(
-    intersect: &Intersect,
-    objects: &[Cube],
-    light_dir: &Vec3,
-    light_distance: f32
-) -> f32 {
-    let shadow_ray_origin = offset_origin(intersect, light_dir);
-    let mut shadow_intensity = 0.0;
-
-    for object in objects {
-        let shadow_intersect = object.ray_intersect(&shadow_ray_origin, light_dir);
-        if shadow_intersect.is_intersecting && shadow_intersect.distance < light_distance {
-            // Si el objeto intersectado emite luz, reduce la sombra, pero no la elimina completamente
-            if let Some(_emission) = object.material.emission_color {
-                let distance_ratio = shadow_intersect.distance / light_distance;
-                let emission_intensity = 1.0 / (distance_ratio * distance_ratio);
-                shadow_intensity = emission_intensity; // Ajustar la sombra según la intensidad de la emisión
-                break; // Asumimos que el bloque emisor de luz bloquea cualquier otra sombra
-            } else {
-                // Si no es un emisor de luz, aplica la sombra normalmente
-                shadow_intensity = 1.0;
-                break;
-            }
-        }
-    }
-
-    shadow_intensity
-}
-
-fn get_skybox_color(ray_direction: &Vec3, skybox: &Texture) -> Color {
-    let dir = ray_direction.normalize();
-    let u = 0.5 + (dir.x.atan2(dir.z) / (2.0 * PI));
-    let v = 0.5 - (dir.y.asin() / PI);
-    skybox.get_color_at_uv(u, v)
-}
-
-fn clamp_color(color: Color) -> Color {
-    Color::new(
-        color.r().min(255).max(0),
-        color.g().min(255).max(0),
-        color.b().min(255).max(0),
-    )
-}
-
-fn generate_random_direction() -> Vec3 {
-    let theta = rand::random::<f32>() * 2.0 * std::f32::consts::PI;
-    let z: f32 = rand::random::<f32>() * 2.0 - 1.0;  // Random valor entre -1 y 1
-    let r = (1.0 - z * z).sqrt();
-    let x = r * theta.cos();
-    let y = r * theta.sin();
-    Vec3::new(x, y, z).normalize()
-}
-
-fn offset_origin(intersect: &Intersect, direction: &Vec3) -> Vec3 {
-    let offset = intersect.normal * BIAS;
-    if direction.dot(&intersect.normal) < 0.0 {
-        intersect.point - offset
-    } else {
-        intersect.point + offset
-    }
-}
-
-
-pub fn cast_ray(
-    ray_origin: &Vec3,
-    ray_direction: &Vec3,
-    objects: &[Cube],
-    lights: &[Light],
-    skybox: &Texture,
-    depth: u32,
-) -> Color {
-    if depth >= 3 {
-        return SKYBOX_COLOR;
-    }
-
-    let mut intersect = Intersect::empty();
-    let mut zbuffer = INFINITY;
-
-    // Comprobación de intersección con los objetos
-    for object in objects {
-        let i = object.ray_intersect(ray_origin, ray_direction);
-        if i.is_intersecting && i.distance < zbuffer {
-            zbuffer = i.distance;
-            intersect = i;
-        }
-    }
-
-    if !intersect.is_intersecting {
-        return get_skybox_color(ray_direction, skybox);
-    }
-
-    let ambient_light = AMBIENT_LIGHT_COLOR * AMBIENT_INTENSITY;
-    let mut total_light = ambient_light;
-
-    // Calcular la luz total desde las luces
-    for light in lights {
-        let light_dir = (light.position - intersect.point).normalize();
-        let light_distance = (light.position - intersect.point).magnitude();
-        let view_dir = (ray_origin - intersect.point).normalize();
-        let reflect_dir = reflect(&-light_dir, &intersect.normal).normalize();
 
-        // Calcular la intensidad de sombra para esta luz usando cast_shadow
-        let shadow_intensity = cast_shadow(&intersect, objects, &light_dir, light_distance);
-        let light_intensity = light.intensity * (1.0 - shadow_intensity);
-
-        // Cálculo de la luz difusa
-        let diffuse_intensity = intersect.normal.dot(&light_dir).max(0.0).min(1.0);
-        let diffuse_color = intersect.material.get_diffuse_color(intersect.u, intersect.v);
-        let diffuse = diffuse_color * intersect.material.albedo[0] * diffuse_intensity * light_intensity;
-
-        // Cálculo de la luz especular
-        let specular_intensity = view_dir.dot(&reflect_dir).max(0.0).powf(intersect.material.specular);
-        let specular = light.color * intersect.material.albedo[1] * specular_intensity * light_intensity;
-
-        total_light = total_light + diffuse + specular;
-    }
-
-    // Añadir la luz de emisión
-    let mut emission_contribution = Color::black();
-    for object in objects {
-        if let Some(emission) = object.material.emission_color {
-            let num_rays = 16;  // Número de direcciones para emitir luz
-            let emission_strength = 1.0 / (num_rays as f32);  // Reducir la intensidad de emisión
-
-            for _ in 0..num_rays {
-                let emission_dir = generate_random_direction();
-                let emission_origin = object.position();
-                let emission_distance = (emission_origin - intersect.point).magnitude();
-                let emission_intensity = emission_strength / (1.0 + emission_distance * emission_distance);
-
-                let emission_diffuse_intensity = intersect.normal.dot(&emission_dir).max(0.0);
-                let emission_diffuse = emission * emission_diffuse_intensity * emission_intensity;
-
-                emission_contribution = emission_contribution + emission_diffuse;
-            }
-        }
-    }
-
-    // Sumar la contribución de emisión a la luz total
-    total_light = total_light + emission_contribution;
-
-    // Clampeo del color final
-    total_light = clamp_color(total_light);
-    total_light
-}
-
-
-
-pub fn render(framebuffer: &mut Framebuffer, objects: &[Cube], camera: &Camera, lights: &[Light], current_skybox: &Arc<Texture>) {
-    let width = framebuffer.width as f32;
-    let height = framebuffer.height as f32;
-    let aspect_ratio = width / height;
-    let fov = PI / 3.0;
-    let perspective_scale = (fov / 2.0).tan();
-
-    for y in 0..framebuffer.height {
-        for x in 0..framebuffer.width {
-            let screen_x = (2.0 * x as f32) / width - 1.0;
-            let screen_y = -(2.0 * y as f32) / height + 1.0;
-            let screen_x = screen_x * aspect_ratio * perspective_scale;
-            let screen_y = screen_y * perspective_scale;
-            let ray_direction = Vec3::new(screen_x, screen_y, -1.0).normalize();
-            let rotated_direction = camera.basis_change(&ray_direction);
-            let pixel_color = cast_ray(&camera.eye, &rotated_direction, objects, lights, &current_skybox, 0);
-            framebuffer.set_current_color(pixel_color.to_hex());
-            framebuffer.point(x, y);
-        }
-    }
-}
+use raytracing::framebuffer::{Framebuffer, ScaleFilter};
+use raytracing::cube::Cube;
+use raytracing::instance::InstancedCubes;
+use raytracing::quad::Quad;
+use raytracing::meshing::greedy_mesh;
+use raytracing::scene::Scene;
+use raytracing::color::Color;
+use raytracing::camera::{Camera, CameraState, NavigationSpeeds, Viewpoint, DEFAULT_MIN_ZOOM_DISTANCE};
+use raytracing::denoise::{denoise, DenoiseSettings};
+use raytracing::exposure::AutoExposure;
+use raytracing::trace::window_to_framebuffer;
+use raytracing::camera_path::{CameraPath, Interpolation};
+use raytracing::batch::RenderQueue;
+use raytracing::clock::SimulationClock;
+use raytracing::presets;
+use raytracing::editor::MaterialEditor;
+use raytracing::reprojection::{render_temporal, TemporalCache};
+use raytracing::stereo::{render_stereo, StereoMode};
+use raytracing::hud::hud_lines;
+use raytracing::overlay::Overlay;
+use raytracing::bounds_overlay::{draw_bounds, scene_bounds_hierarchy, MAX_OVERLAY_DEPTH};
+use raytracing::grid_overlay::{draw_grid, DEFAULT_GRID_EXTENT};
+use raytracing::font::{GLYPH_HEIGHT, LINE_SPACING};
+use raytracing::sidecar::RenderMetadata;
+use raytracing::light::Light;
+use raytracing::fog::VolumetricFog;
+use raytracing::texture::{ColorSpace, Texture};
+use raytracing::hdr::HdrImage;
+use raytracing::{render, render_accumulate, render_passes, render_to_hdr, trace_pixel, RenderSettings, SampleSequence};
+
+// El terreno se arma dos veces: como cubos instanciados (lo habitual) y con sus caras unidas en
+// quads grandes (greedy meshing). La tecla U elige cuál de las dos capas se ve
+const TERRAIN_CUBES_LAYER: &str = "terreno_cubos";
+const TERRAIN_MESH_LAYER: &str = "terreno_unido";
+
+// Teclas que cambian luces, cielo, materiales, capas o el sombreado: invalidan el historial de la
+// reproyección temporal (mover la cámara no, de eso se encarga la propia reproyección). Los
+// cambios de render_settings se detectan aparte comparando los ajustes
+const SCENE_KEYS: [Key; 16] = [
+    Key::D, Key::N, Key::K, Key::V, Key::F, Key::H, Key::O, Key::U,
+    Key::F1, Key::F2, Key::F3, Key::F10, Key::Equal, Key::Minus,
+    Key::LeftBracket, Key::RightBracket,
+];
+
+// Separación entre los ojos para el render estéreo, en unidades de la escena
+const INTERPUPILLARY_DISTANCE: f32 = 0.3;
+
+// Densidad de la niebla volumétrica (tecla F): baja para que se vean los haces sin tapar la casa
+const FOG_DENSITY: f32 = 0.08;
+
+// Radio en píxeles de los marcadores de luces (tecla L)
+const LIGHT_MARKER_RADIUS: f32 = 5.0;
+
+// Archivo donde F9 guarda el punto de vista y de donde F10 lo restaura
+const CAMERA_BOOKMARK_PATH: &str = "camera_bookmark.json";
+
+// Guardar cada cuadro del recorrido de cámara como PNG en flythrough/
+const SAVE_FLYTHROUGH_FRAMES: bool = false;
+
+// Tinte de la luz ambiental según la hora: cálido de día, azulado de noche
+const DAY_AMBIENT_TINT: Color = Color::new(60, 52, 40);
+const NIGHT_AMBIENT_TINT: Color = Color::new(25, 35, 70);
 
 fn main() {
     let window_width = 800;
@@ -236,82 +87,53 @@ fn main() {
     .expect("Failed to create window");
 
     // Definiendo texturas y materiales
-    let grass_texture: Arc<Texture> = Arc::new(Texture::new("assets/grass_texture.png"));
-    let wood_texture: Arc<Texture> = Arc::new(Texture::new("assets/wood_texture.png"));
-    let door_texture: Arc<Texture> = Arc::new(Texture::new("assets/dark_door_texture.png"));
-    let glass_texture: Arc<Texture> = Arc::new(Texture::new("assets/glass_texture.png"));
-    let plank_texture: Arc<Texture> = Arc::new(Texture::new("assets/plank.png"));
-    let stone_texture: Arc<Texture> = Arc::new(Texture::new("assets/stone_texture.jpg"));
-    let glowstone_texture: Arc<Texture> = Arc::new(Texture::new("assets/glowstone_texture.jpeg"));
-
-    let grass_material = Material::new_with_texture(
-        0.1,
-        [0.8, 0.1, 0.0, 0.0],
-        1.0,
-        grass_texture.clone(),
-        None,
-        0.0,
-    );
-    let wood_material = Material::new_with_texture(
-        0.2,
-        [0.9, 0.05, 0.0, 0.0],
-        1.0,
-        wood_texture.clone(),
-        None,
-        0.0,
-    );
-    let plank_material = Material::new_with_texture(
-        0.2,
-        [0.9, 0.05, 0.0, 0.0],
-        1.0,
-        plank_texture.clone(),
-        None,
-        0.0,
-    );
-    let stone_material = Material::new_with_texture(
-        0.2,
-        [0.9, 0.05, 0.0, 0.0],
-        1.0,
-        stone_texture.clone(),
-        None,
-        0.0,
-    );
-    let door_material = Material::new_with_texture(
-        0.3,
-        [0.7, 0.1, 0.0, 0.0],
-        1.0,
-        door_texture.clone(),
-        None,
-        0.0,
-    );
-    let glass_material = Material::new_with_texture(
-        0.3,
-        [0.7, 0.1, 0.0, 0.5],  // Puedes ajustar los valores de albedo si es necesario
-        1.5,  // Ajusta el índice de refracción a 1.5 para el vidrio
-        glass_texture.clone(),
-        None,
-        0.0,
-    );
-    let glowstone_texture = Material::new_with_texture(
-        50.0,                        // Specular
-        [0.9, 0.1, 0.0, 0.0],        // Albedo
-        0.0,                   // Refractive index
-        glowstone_texture.clone(),        // Textura para el material
-        Some(Color::new(255, 255, 0)),  // Color de emisión
-        1.0
+    // Texturas de albedo: vienen en sRGB y se pasan a lineal al cargarlas. Si falta alguna, el
+    // pánico nombra el archivo
+    let load_albedo = |path: &str| {
+        Arc::new(Texture::try_new_with_color_space(path, ColorSpace::Srgb).unwrap_or_else(|error| panic!("{}", error)))
+    };
+    let grass_texture: Arc<Texture> = load_albedo("assets/grass_texture.png");
+    let wood_texture: Arc<Texture> = load_albedo("assets/wood_texture.png");
+    let door_texture: Arc<Texture> = load_albedo("assets/dark_door_texture.png");
+    let glass_texture: Arc<Texture> = load_albedo("assets/glass_texture.png");
+    let plank_texture: Arc<Texture> = load_albedo("assets/plank.png");
+    let stone_texture: Arc<Texture> = load_albedo("assets/stone_texture.jpg");
+    let glowstone_texture: Arc<Texture> = load_albedo("assets/glowstone_texture.jpeg");
+
+    let grass_material = presets::grass(grass_texture.clone());
+    let wood_material = presets::wood(wood_texture.clone());
+    let plank_material = presets::wood(plank_texture.clone());
+    let stone_material = presets::stone(stone_texture.clone());
+    let door_material = presets::varnished_wood(door_texture.clone());
+    let glass_material = presets::glass(glass_texture.clone());
+    let glowstone_texture = presets::emissive_textured(
+        glowstone_texture.clone(),      // Textura para el material
+        Color::new(255, 255, 0),        // Color de emisión
+        1.0,                            // Intensidad de emisión
     );
 
-    // Base de césped 9x8
-    let mut objects: Vec<Cube> = Vec::new();
+    let mut scene = Scene::new();
+
+    // Base de césped 9x8: un solo cubo plantilla compartido por todas las instancias
+    let mut instances = InstancedCubes::new();
+    let grass_template = instances.add_template(Cube {
+        min: Vec3::new(0.0, -1.0, 0.0),
+        max: Vec3::new(1.0, 0.0, 1.0),
+        material: grass_material.clone(),
+        inverted: false,
+    });
     for i in 0..9 {
         for j in 0..8 {
-            objects.push(Cube {
-                min: Vec3::new(i as f32, -1.0, j as f32),
-                max: Vec3::new(i as f32 + 1.0, 0.0, j as f32 + 1.0),
-                material: grass_material.clone(),
-            });
+            instances.add_instance(grass_template, Vec3::new(i as f32, 0.0, j as f32));
         }
     }
+    scene.add_instances(instances, &["terreno", TERRAIN_CUBES_LAYER]);
+    // La misma base con solo las caras exteriores, unidas en 6 quads grandes (oculta al inicio)
+    let cells: Vec<[i32; 3]> = (0..9).flat_map(|i| (0..8).map(move |j| [i, -1, j])).collect();
+    for quad in greedy_mesh(&cells, &grass_material) {
+        scene.add_quad(quad, &["terreno", TERRAIN_MESH_LAYER]);
+    }
+    scene.set_layer_visible(TERRAIN_MESH_LAYER, false);
 
     // Base y paredes de la casa con columnas de wood_material, paredes de plank_material y capa superior de stone_material
     for i in 1..8 {  // Base de 7 bloques de ancho
@@ -320,11 +142,11 @@ fn main() {
             let is_column = (i == 1 || i == 7) && (j == 2 || j == 5);
 
             // Evitar la creación de bloques donde va la puerta (posición [4, 3])
-            let is_door_position = (i == 4 && j == 5); // Ajustar la posición a la nueva altura de la puerta
+            let is_door_position = i == 4 && j == 5; // Ajustar la posición a la nueva altura de la puerta
 
             // Primer bloque de altura (base)
             if !is_door_position {
-                objects.push(Cube {
+                scene.add_cube(Cube {
                     min: Vec3::new(i as f32, 0.0, j as f32),
                     max: Vec3::new(i as f32 + 1.0, 1.0, j as f32 + 1.0),
                     material: if is_column {
@@ -332,7 +154,8 @@ fn main() {
                     } else {
                         plank_material.clone() // Usar plank_material para las paredes
                     },
-                });
+                    inverted: false,
+                }, &["casa"]);
             }
 
             // Bloques de altura adicionales (paredes y columnas) hasta una altura de 5
@@ -347,61 +170,150 @@ fn main() {
                 };
 
                 // Evitar poner bloques donde van las ventanas y la puerta
-                if !(i == 3 && j == 5 && k == 1) &&  // Ventana 1
-                !(i == 5 && j == 5 && k == 1) &&  // Ventana 2
-                !(i == 4 && j == 5 && k < 2) {    // Evitar bloques en la puerta (altura hasta 2)
-                    
-                    objects.push(Cube {
+                let is_window = (i == 3 || i == 5) && j == 5 && k == 1; // Ventanas 1 y 2
+                let is_door = i == 4 && j == 5 && k < 2;                 // Puerta (altura hasta 2)
+                if !is_window && !is_door {
+                    scene.add_cube(Cube {
                         min: Vec3::new(i as f32, k as f32, j as f32),
                         max: Vec3::new(i as f32 + 1.0, k as f32 + 1.0, j as f32 + 1.0),
-                        material: material,  // Asignar el material dependiendo de la capa
-                    });
+                        material,  // Asignar el material dependiendo de la capa
+                        inverted: false,
+                    }, &["casa"]);
                 }
             }
         }
     }
 
     // Ventanas en el segundo bloque de altura (k = 1)
-    objects.push(Cube {
-        min: Vec3::new(3.0, 1.0, 5.0),
-        max: Vec3::new(4.0, 2.0, 6.0),
+    // (cada ventana es un quad delgado sobre la cara frontal de la pared)
+    scene.add_quad(Quad {
+        corner: Vec3::new(3.0, 1.0, 6.0),
+        u_edge: Vec3::new(1.0, 0.0, 0.0),
+        v_edge: Vec3::new(0.0, 1.0, 0.0),
         material: glass_material.clone(),
-    });
-    objects.push(Cube {
-        min: Vec3::new(5.0, 1.0, 5.0),
-        max: Vec3::new(6.0, 2.0, 6.0),
+        uv_repeat: (1.0, 1.0),
+    }, &["casa", "ventanas"]);
+    scene.add_quad(Quad {
+        corner: Vec3::new(5.0, 1.0, 6.0),
+        u_edge: Vec3::new(1.0, 0.0, 0.0),
+        v_edge: Vec3::new(0.0, 1.0, 0.0),
         material: glass_material.clone(),
-    });
-    objects.push(Cube {
+        uv_repeat: (1.0, 1.0),
+    }, &["casa", "ventanas"]);
+    scene.add_cube(Cube {
         min: Vec3::new(7.0, 0.0, 6.0),
         max: Vec3::new(8.0, 1.0, 7.0),
         material: glowstone_texture.clone(),
-    });
+        inverted: false,
+    }, &["luces"]);
 
     // Puerta en el centro con altura de 3 bloques
-    objects.push(Cube {
+    scene.add_cube(Cube {
         min: Vec3::new(4.0, 0.0, 5.0),
         max: Vec3::new(5.0, 2.0, 6.0), 
         material: door_material.clone(),
-    });
+        inverted: false,
+    }, &["casa"]);
     
+    for (name, error) in scene.validate_materials() {
+        eprintln!("Material {}: {}", name, error);
+    }
+    let scene_stats = scene.stats();
+    println!("{}", scene_stats);
+
     // Inicializando la cámara
     let mut camera = Camera::new(
         Vec3::new(10.0, 10.0, 20.0),
         Vec3::new(4.0, 0.0, 4.0),
         Vec3::new(0.0, 1.0, 0.0),
     );
+    // Se conserva la dirección de vista inicial pero la distancia se ajusta a la escena completa
+    camera.frame_scene(scene_stats.bounds(), RenderSettings::default().fov);
+    // Zoom y paneo proporcionales al tamaño de la escena; el acercamiento mínimo también
+    let navigation = NavigationSpeeds::for_bounds(&scene_stats.bounds());
+    camera.min_distance = DEFAULT_MIN_ZOOM_DISTANCE * navigation.scale;
+
+    // Recorrido de cámara alrededor de la casa (se activa con la tecla P)
+    let scene_center = Vec3::new(4.5, 1.0, 4.0);
+    let up = Vec3::new(0.0, 1.0, 0.0);
+    let mut camera_path = CameraPath::new(Interpolation::CatmullRom);
+    camera_path.add_keyframe(0.0, Vec3::new(10.0, 10.0, 20.0), scene_center, up);
+    camera_path.add_keyframe(4.0, Vec3::new(20.0, 6.0, 4.0), scene_center, up);
+    camera_path.add_keyframe(8.0, Vec3::new(4.5, 8.0, -12.0), scene_center, up);
+    camera_path.add_keyframe(12.0, Vec3::new(-11.0, 6.0, 4.0), scene_center, up);
+    camera_path.add_keyframe(16.0, Vec3::new(10.0, 10.0, 20.0), scene_center, up);
+    let mut flythrough_active = false;
+    // Reloj de la animación; la barra espaciadora lo pausa (la cámara manual sigue activa)
+    let mut clock = SimulationClock::new();
+    let mut flythrough_frame = 0;
+
+    // Mapa de entorno HDR (el sol y las nubes superan 1.0); si no se puede leer, el JPEG
+    let skybox_texture = Arc::new(
+        Texture::try_new_hdr("assets/sky.hdr")
+            .or_else(|error| {
+                eprintln!("{}; using assets/sky.jpeg", error);
+                Texture::try_new("assets/sky.jpeg")
+            })
+            .unwrap_or_else(|error| panic!("{}", error)),
+    );
+    let skybox_night_texture = Arc::new(
+        Texture::try_new("assets/night_texture.jpg").unwrap_or_else(|error| panic!("{}", error)),
+    );
 
-    let lights = vec![Light::new(Vec3::new(-10.0, 10.0, 10.0), Color::new(255, 255, 255), 1.0)];
-
-    let skybox_texture = Arc::new(Texture::new("assets/sky.jpeg"));
-    let skybox_night_texture = Arc::new(Texture::new("assets/night_texture.jpg"));
-    let mut current_skybox_texture = skybox_texture.clone();
-
-    let daytime_light = Light::new(Vec3::new(-10.0, 10.0, 10.0), Color::new(255, 255, 255), 1.0); // Luz brillante
-    let nighttime_light = Light::new(Vec3::new(-10.0, 10.0, 10.0), Color::new(10, 10, 10), 0.5); // Luz más tenue y azulada
-
-    let mut current_light = daytime_light.clone(); // Inicialmente la luz diurna
+    // Sol arriba a la izquierda y al frente de la escena, relativo a su caja envolvente
+    let sun_offset = Vec3::new(-1.6, 1.7, 0.75);
+    let daytime_light = Light::relative_to_scene(&scene_stats.bounds(), sun_offset, Color::new(255, 255, 255), 1.0); // Luz brillante
+    let nighttime_light = Light::relative_to_scene(&scene_stats.bounds(), sun_offset, Color::new(10, 10, 10), 0.5); // Luz más tenue y azulada
+    // Dirección hacia el sol desde el centro de la escena. De día su brillo especular es el destello
+    // del sol, no el Phong de la luz puntual; de noche queda bajo el horizonte (sin disco ni destello)
+    let daytime_light = Light { specular: false, ..daytime_light };
+    let sun_direction = (daytime_light.position - scene_stats.bounds().centroid()).normalize();
+    let night_sun_direction = Vec3::new(sun_direction.x, -sun_direction.y, sun_direction.z);
+
+    // Inicialmente cielo y luz diurnos
+    let mut current_skybox = skybox_texture.clone();
+    let mut analytic_sky = false; // Tecla K: cielo analítico según la posición del sol
+    scene.lights = vec![daytime_light.clone()];
+    scene.ambient_color = DAY_AMBIENT_TINT;
+
+    let mut render_settings = RenderSettings {
+        width: framebuffer_width,
+        height: framebuffer_height,
+        ..RenderSettings::default()
+    };
+
+    // Exposición automática (tecla E para activar/desactivar)
+    let mut auto_exposure = AutoExposure::new(0.4, 1.5);
+    let mut auto_exposure_enabled = true;
+
+    // Editor de materiales (M): selecciona el cubo al centro de la pantalla
+    let mut material_editor = MaterialEditor::new();
+
+    let mut time_of_day = "day";
+    let mut show_hud = false;
+    let mut internal_scale = 1; // Divisor de la resolución de trazado (tecla R)
+    let mut low_res = Framebuffer::new(framebuffer_width, framebuffer_height);
+    let mut show_light_markers = false;
+    let mut show_grid = false; // G: piso cuadriculado y ejes en el origen
+    let mut greedy_terrain = false; // U: terreno unido en quads en lugar de cubos instanciados
+    let mut overlay = Overlay::new(framebuffer_width, framebuffer_height); // Texto de interfaz, compuesto al presentar
+    let mut bounds_overlay_depth: Option<usize> = None; // F7: cajas envolventes hasta esta profundidad
+    let mut mouse_was_down = false;
+
+    // Reproyección temporal (tecla T): reutiliza el cuadro anterior al orbitar
+    let mut temporal_cache = TemporalCache::new(framebuffer_width, framebuffer_height);
+    let mut temporal_enabled = false;
+
+    // Acumulación progresiva (tecla A): con la cámara quieta cada cuadro suma muestras Halton
+    // nuevas al promedio; mover la cámara o cambiar la escena reinicia la suma
+    let mut accumulation = HdrImage::new(framebuffer_width, framebuffer_height);
+    let mut accumulation_enabled = false;
+    let mut accumulated_view: Option<(Vec3, Vec3, Vec3)> = None;
+    // Ajustes con los que se trazó el historial (temporal y acumulado)
+    let mut history_settings = render_settings.clone();
+
+    // Estéreo (tecla B): apagado -> lado a lado -> anaglifo
+    let mut stereo_mode: Option<StereoMode> = None;
 
     // Ciclo principal
     let mut previous_time = Instant::now();
@@ -412,30 +324,372 @@ fn main() {
 
         framebuffer.clear();
         if window.is_key_down(Key::D) {
-            current_skybox_texture = skybox_texture.clone(); // Cambiar a cielo diurno
-            current_light = daytime_light.clone(); // Cambiar a luz diurna
+            current_skybox = skybox_texture.clone(); // Cambiar a cielo diurno
+            scene.lights = vec![daytime_light.clone()]; // Cambiar a luz diurna
+            scene.ambient_color = DAY_AMBIENT_TINT;
+            time_of_day = "day";
         } else if window.is_key_down(Key::N) {
-            current_skybox_texture = skybox_night_texture.clone(); // Cambiar a cielo nocturno
-            current_light = nighttime_light.clone(); // Usar luz nocturna
+            current_skybox = skybox_night_texture.clone(); // Cambiar a cielo nocturno
+            scene.lights = vec![nighttime_light.clone()]; // Usar luz nocturna
+            scene.ambient_color = NIGHT_AMBIENT_TINT;
+            time_of_day = "night";
+        }
+
+        if window.is_key_pressed(Key::K, KeyRepeat::No) {
+            analytic_sky = !analytic_sky;
+        }
+        // V: vista previa rápida (solo colores de material) mientras se ubica la cámara
+        if window.is_key_pressed(Key::V, KeyRepeat::No) {
+            render_settings.preview = !render_settings.preview;
+        }
+        // F: niebla volumétrica con haces de luz
+        if window.is_key_pressed(Key::F, KeyRepeat::No) {
+            scene.fog = match scene.fog {
+                Some(_) => None,
+                None => Some(VolumetricFog::new(FOG_DENSITY, Color::new(255, 255, 255))),
+            };
+        }
+        // O: curva de tono clamp -> Reinhard -> ACES
+        if window.is_key_pressed(Key::O, KeyRepeat::No) {
+            render_settings.tone_map = render_settings.tone_map.next();
+        }
+        // H: luz ambiental plana o de hemisferio (cielo arriba, suelo abajo)
+        if window.is_key_pressed(Key::H, KeyRepeat::No) {
+            scene.hemisphere_ambient = !scene.hemisphere_ambient;
+        }
+        scene.skybox = if analytic_sky { None } else { Some(current_skybox.clone()) };
+        scene.sun_direction = Some(if time_of_day == "day" { sun_direction } else { night_sun_direction });
+
+        if window.is_key_pressed(Key::Space, KeyRepeat::No) {
+            clock.toggle_pause();
+        }
+        clock.advance(delta_time);
+
+        if window.is_key_pressed(Key::P, KeyRepeat::No) {
+            flythrough_active = !flythrough_active;
+            clock.reset();
+            flythrough_frame = 0;
+            if SAVE_FLYTHROUGH_FRAMES && flythrough_active {
+                if let Err(error) = std::fs::create_dir_all("flythrough") {
+                    eprintln!("Failed to create flythrough directory: {}", error);
+                }
+            }
+        }
+        // I: alterna la interpolación del recorrido entre Catmull-Rom y lineal
+        if window.is_key_pressed(Key::I, KeyRepeat::No) {
+            camera_path.interpolation = match camera_path.interpolation {
+                Interpolation::CatmullRom => Interpolation::Linear,
+                Interpolation::Linear => Interpolation::CatmullRom,
+            };
+        }
+        if flythrough_active && !clock.paused {
+            let (eye, center, up) = camera_path.sample(clock.time % camera_path.duration());
+            camera.look_at(eye, center, up);
+        }
+
+        // Vistas predefinidas: 1 = frente, 2 = arriba, 3 = lado, 4 = isométrica
+        for (key, view) in [
+            (Key::Key1, Viewpoint::Front),
+            (Key::Key2, Viewpoint::Top),
+            (Key::Key3, Viewpoint::Side),
+            (Key::Key4, Viewpoint::Iso),
+        ] {
+            if window.is_key_pressed(key, KeyRepeat::No) {
+                camera.set_viewpoint(view, scene_stats.bounds_min, scene_stats.bounds_max);
+            }
+        }
+
+        // Mostrar/ocultar capas: F1 = terreno, F2 = casa, F3 = luces
+        for (key, layer) in [(Key::F2, "casa"), (Key::F3, "luces")] {
+            if window.is_key_pressed(key, KeyRepeat::No) {
+                let visible = scene.is_layer_visible(layer);
+                scene.set_layer_visible(layer, !visible);
+            }
+        }
+        // El terreno muestra solo una de sus dos versiones; U alterna cubos instanciados / greedy meshing
+        let toggle_terrain = window.is_key_pressed(Key::F1, KeyRepeat::No);
+        let toggle_greedy = window.is_key_pressed(Key::U, KeyRepeat::No);
+        if toggle_greedy {
+            greedy_terrain = !greedy_terrain;
+        }
+        if toggle_terrain || toggle_greedy {
+            let visible = scene.is_layer_visible("terreno") != toggle_terrain;
+            scene.set_layer_visible(TERRAIN_CUBES_LAYER, visible && !greedy_terrain);
+            scene.set_layer_visible(TERRAIN_MESH_LAYER, visible && greedy_terrain);
+        }
+
+        // Editor de materiales: M = seleccionar/soltar, Tab = siguiente campo, +/- = ajustar
+        if window.is_key_pressed(Key::M, KeyRepeat::No) {
+            material_editor.selected = match material_editor.selected {
+                Some(_) => None,
+                None => {
+                    let (ray_origin, ray_direction) = camera.get_ray_with_pixel_aspect(
+                        framebuffer_width as f32 / 2.0,
+                        framebuffer_height as f32 / 2.0,
+                        framebuffer_width as f32,
+                        framebuffer_height as f32,
+                        render_settings.fov,
+                        render_settings.pixel_aspect,
+                    );
+                    scene.pick_object(&ray_origin, &ray_direction)
+                }
+            };
+        }
+        if let Some(index) = material_editor.selected {
+            if window.is_key_pressed(Key::Tab, KeyRepeat::No) {
+                material_editor.next_field();
+            }
+            if window.is_key_pressed(Key::Equal, KeyRepeat::Yes) {
+                material_editor.adjust(scene.material_mut(index), 1);
+            }
+            if window.is_key_pressed(Key::Minus, KeyRepeat::Yes) {
+                material_editor.adjust(scene.material_mut(index), -1);
+            }
+        }
+
+        if window.is_key_pressed(Key::T, KeyRepeat::No) {
+            temporal_enabled = !temporal_enabled;
+        }
+        if window.is_key_pressed(Key::A, KeyRepeat::No) {
+            accumulation_enabled = !accumulation_enabled;
+        }
+        // Las teclas que cambian la escena y cualquier cambio de los ajustes de render vuelven
+        // inválido el historial
+        let scene_changed = SCENE_KEYS.iter().any(|key| window.is_key_pressed(*key, KeyRepeat::Yes));
+        if scene_changed || !render_settings.traces_same_image(&history_settings) {
+            temporal_cache.reset();
+            accumulated_view = None;
+            history_settings = render_settings.clone();
+        }
+        if window.is_key_pressed(Key::B, KeyRepeat::No) {
+            stereo_mode = match stereo_mode {
+                None => Some(StereoMode::SideBySide),
+                Some(StereoMode::SideBySide) => Some(StereoMode::Anaglyph),
+                Some(StereoMode::Anaglyph) => None,
+            };
+        }
+        // R: resolución interna 1/1 -> 1/2 -> 1/4; el cuadro se traza pequeño y se escala a la ventana
+        if window.is_key_pressed(Key::R, KeyRepeat::No) {
+            internal_scale = if internal_scale >= 4 { 1 } else { internal_scale * 2 };
+            low_res = Framebuffer::new(framebuffer_width / internal_scale, framebuffer_height / internal_scale);
+        }
+        // E: exposición automática. Se mide sobre la radiancia lineal de una pasada reducida (1/8,
+        // una muestra por píxel) y el factor entra en render_settings.exposure para este cuadro
+        if window.is_key_pressed(Key::E, KeyRepeat::No) {
+            auto_exposure_enabled = !auto_exposure_enabled;
+            if !auto_exposure_enabled {
+                render_settings.exposure = 1.0;
+            }
+        }
+        if auto_exposure_enabled {
+            let metering_settings = RenderSettings {
+                width: (framebuffer_width / 8).max(1),
+                height: (framebuffer_height / 8).max(1),
+                region: None,
+                aa_samples: 1,
+                time_budget: None,
+                ..render_settings.clone()
+            };
+            let metered = render_to_hdr(&scene, &camera, &metering_settings);
+            render_settings.exposure = auto_exposure.update(&metered, delta_time);
+        }
+        let render_target = if internal_scale > 1 { &mut low_res } else { &mut framebuffer };
+        if let Some(mode) = stereo_mode {
+            render_stereo(render_target, &scene, &camera, &render_settings, INTERPUPILLARY_DISTANCE, mode);
+        } else if temporal_enabled {
+            render_temporal(render_target, &scene, &camera, &render_settings, &mut temporal_cache);
+        } else if accumulation_enabled {
+            let view = (camera.eye, camera.center, camera.up);
+            let size_changed = accumulation.width != render_target.width || accumulation.height != render_target.height;
+            if size_changed || accumulated_view != Some(view) {
+                accumulation = HdrImage::new(render_target.width, render_target.height);
+                accumulated_view = Some(view);
+            }
+            // Al menos dos muestras por cuadro para que el desplazamiento Halton recorra el píxel.
+            // Cada cuadro continúa la secuencia donde terminó el anterior; el índice solo vive en
+            // esta copia de los ajustes, así los otros modos siguen trazando el cuadro 0
+            let settings = RenderSettings {
+                sample_sequence: SampleSequence::Halton,
+                aa_samples: render_settings.aa_samples.max(2),
+                frame_index: accumulation.frames,
+                ..render_settings.clone()
+            };
+            render_accumulate(&mut accumulation, &scene, &camera, &settings);
+            *render_target = accumulation.to_framebuffer(render_settings.tone_map, render_settings.exposure);
+        } else {
+            render(render_target, &scene, &camera, &render_settings);
+        }
+        if internal_scale > 1 {
+            low_res.blit_scaled(&mut framebuffer, ScaleFilter::Bilinear);
         }
 
-        render(&mut framebuffer, &objects, &camera, &[current_light.clone()], &current_skybox_texture);
+        // F5: guardar los pases beauty/normal/albedo/profundidad/ID de material (y el beauty sin ruido) de la vista actual
+        if window.is_key_pressed(Key::F5, KeyRepeat::No) {
+            let passes = render_passes(&scene, &camera, &render_settings);
+            let outputs = [
+                ("pass_beauty.png", passes.beauty.clone()),
+                ("pass_normal.png", passes.normal_colors()),
+                ("pass_albedo.png", passes.albedo.clone()),
+                ("pass_depth.png", passes.depth_colors()),
+                ("pass_material_id.png", passes.material_id_colors()),
+                ("pass_denoised.png", denoise(&passes, &DenoiseSettings::default())),
+            ];
+            for (file_name, colors) in outputs {
+                if let Err(error) = passes.to_framebuffer(&colors).save_png(file_name) {
+                    eprintln!("Failed to save render pass {}: {}", file_name, error);
+                }
+            }
+            // Radiancia lineal en EXR para composición (conserva los brillos mayores a 1.0)
+            if let Err(error) = render_to_hdr(&scene, &camera, &render_settings).save_exr("pass_beauty.exr") {
+                eprintln!("Failed to save EXR: {}", error);
+            }
+            // pass_beauty.json: ajustes y cámara con los que se generaron los pases
+            if let Err(error) = RenderMetadata::new(&render_settings, &camera, time_of_day).write_sidecar("pass_beauty.png") {
+                eprintln!("Failed to save render metadata: {}", error);
+            }
+        }
+
+        // F9 / F10: guardar y restaurar el punto de vista marcado
+        if window.is_key_pressed(Key::F9, KeyRepeat::No) {
+            if let Err(error) = camera.to_state(render_settings.fov).save(CAMERA_BOOKMARK_PATH) {
+                eprintln!("Failed to save camera bookmark: {}", error);
+            }
+        }
+        if window.is_key_pressed(Key::F10, KeyRepeat::No) {
+            match CameraState::load(CAMERA_BOOKMARK_PATH) {
+                Ok(state) => {
+                    camera.apply_state(&state);
+                    render_settings.fov = state.fov;
+                }
+                Err(error) => eprintln!("Failed to load camera bookmark: {}", error),
+            }
+        }
+
+        // Ctrl + clic: imprimir (en stderr) el registro de cada rebote del píxel bajo el cursor
+        let mouse_down = window.get_mouse_down(MouseButton::Left);
+        if mouse_down && !mouse_was_down && window.is_key_down(Key::LeftCtrl) {
+            let pixel = window
+                .get_mouse_pos(MouseMode::Discard)
+                .and_then(|mouse| window_to_framebuffer(mouse, window.get_size(), (render_settings.width, render_settings.height)));
+            if let Some((x, y)) = pixel {
+                eprintln!("{}", trace_pixel(x, y, &scene, &camera, &render_settings));
+            }
+        }
+        mouse_was_down = mouse_down;
+
+        // F8: renderizar 8 vistas del recorrido de cámara a turntable_NNN.png
+        if window.is_key_pressed(Key::F8, KeyRepeat::No) {
+            let queue = RenderQueue::from_path(&camera_path, 8);
+            let rendered = queue.render_all(&scene, &render_settings, "turntable", &mut |index, total, fraction| {
+                if fraction >= 1.0 {
+                    eprintln!("Turntable: {}/{}", index + 1, total);
+                }
+            });
+            if let Err(error) = rendered {
+                eprintln!("Failed to save turntable frame: {}", error);
+            }
+        }
+
+        // F6: exportar la geometría de la escena para abrirla en Blender
+        if window.is_key_pressed(Key::F6, KeyRepeat::No) {
+            if let Err(error) = scene.export_obj("scene.obj") {
+                eprintln!("Failed to export scene: {}", error);
+            }
+        }
+
+        if flythrough_active && SAVE_FLYTHROUGH_FRAMES {
+            if let Err(error) = framebuffer.save_png(&format!("flythrough/frame_{:04}.png", flythrough_frame)) {
+                eprintln!("Failed to save flythrough frame: {}", error);
+            }
+            flythrough_frame += 1;
+        }
+
+        // Contador de FPS en la esquina superior izquierda
+        overlay.clear();
+        if delta_time > 0.0 {
+            overlay.draw_text(10, 10, &format!("FPS: {:.0}", 1.0 / delta_time), Color::from_hex(0xFFFFFF));
+        }
+        // F7: recorre apagado -> nodos del BVH hasta el nivel 0, 1, ... MAX_OVERLAY_DEPTH -> apagado
+        if window.is_key_pressed(Key::F7, KeyRepeat::No) {
+            bounds_overlay_depth = match bounds_overlay_depth {
+                None => Some(0),
+                Some(depth) if depth < MAX_OVERLAY_DEPTH => Some(depth + 1),
+                Some(_) => None,
+            };
+        }
+        if let Some(depth) = bounds_overlay_depth {
+            let boxes = scene_bounds_hierarchy(&scene, depth);
+            draw_bounds(&mut framebuffer, &camera, &render_settings, &boxes);
+        }
+        if window.is_key_pressed(Key::G, KeyRepeat::No) {
+            show_grid = !show_grid;
+        }
+        if show_grid {
+            draw_grid(&mut framebuffer, &camera, &render_settings, DEFAULT_GRID_EXTENT);
+        }
+        // L: marcadores de las luces (disco del color de la luz con borde negro para verlo sobre el cielo)
+        if window.is_key_pressed(Key::L, KeyRepeat::No) {
+            show_light_markers = !show_light_markers;
+        }
+        if show_light_markers {
+            for light in &scene.lights {
+                let projected = camera.project(
+                    &light.position,
+                    framebuffer_width as f32,
+                    framebuffer_height as f32,
+                    render_settings.fov,
+                    render_settings.pixel_aspect,
+                );
+                if let Some((x, y)) = projected {
+                    overlay.fill_circle(x, y, LIGHT_MARKER_RADIUS + 1.5, Color::black(), 1.0);
+                    overlay.fill_circle(x, y, LIGHT_MARKER_RADIUS, light.color, 1.0);
+                }
+            }
+        }
+        // F4: HUD con el estado de la cámara en la esquina inferior izquierda
+        if window.is_key_pressed(Key::F4, KeyRepeat::No) {
+            show_hud = !show_hud;
+        }
+        if show_hud {
+            let lines = hud_lines(&camera, &render_settings, delta_time);
+            let text_height = lines.len() * (GLYPH_HEIGHT + LINE_SPACING);
+            overlay.draw_text_panel(
+                10,
+                framebuffer_height.saturating_sub(text_height + 10),
+                &lines.join("\n"),
+                Color::from_hex(0x00FFFF),
+                Color::black(),
+                0.5,
+            );
+        }
+        if let Some(index) = material_editor.selected {
+            let lines = material_editor.lines(&scene.objects()[index].shape.material);
+            overlay.draw_text_panel(10, 24, &lines.join("\n"), Color::from_hex(0xFFFF00), Color::black(), 0.5);
+        }
         window
-            .update_with_buffer(&framebuffer.buffer, framebuffer_width, framebuffer_height)
+            .update_with_buffer(&overlay.present(&framebuffer), framebuffer_width, framebuffer_height)
             .unwrap();
 
         // Control de la cámara y movimiento
         if window.is_key_down(Key::Left) {
-            camera.orbit(PI / 10.0 * delta_time, 0.0);
+            camera.orbit(navigation.orbit * delta_time, 0.0);
         }
         if window.is_key_down(Key::Right) {
-            camera.orbit(-PI / 10.0 * delta_time, 0.0);
+            camera.orbit(-navigation.orbit * delta_time, 0.0);
         }
         if window.is_key_down(Key::Up) {
-            camera.zoom(0.5 * delta_time);
+            camera.zoom(navigation.zoom * delta_time);
         }
         if window.is_key_down(Key::Down) {
-            camera.zoom(-0.5 * delta_time);
+            camera.zoom(-navigation.zoom * delta_time);
+        }
+
+        // Girar el entorno con [ y ]
+        if window.is_key_down(Key::LeftBracket) {
+            scene.skybox_yaw -= PI / 4.0 * delta_time;
+        }
+        if window.is_key_down(Key::RightBracket) {
+            scene.skybox_yaw += PI / 4.0 * delta_time;
         }
 
         std::thread::sleep(frame_delay);
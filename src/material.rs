@@ -1,9 +1,94 @@
-use once_cell::sync::Lazy;
+use nalgebra_glm::Vec3;
+use std::f32::consts::PI;
+use std::fmt;
 use std::sync::Arc;
 
 use crate::color::Color;
 use crate::texture::Texture;
 
+// Cómo se calculan las coordenadas UV de la textura sobre el objeto
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UvProjection {
+    CubeFace,    // Cada cara del cubo recibe la textura completa
+    Spherical,   // Proyección esférica desde el centro del objeto
+    Cylindrical, // Envoltura alrededor del eje Y (ej. un tronco)
+    Planar,      // Proyección desde arriba sobre el plano XZ
+}
+
+impl UvProjection {
+    // UV de un punto dado el centro y el tamaño del objeto; None para CubeFace (lo resuelve cada primitiva).
+    // Un objeto plano (quad) tiene tamaño 0 en algún eje: se evita dividir por cero
+    pub fn project(&self, point: &Vec3, center: &Vec3, size: &Vec3) -> Option<(f32, f32)> {
+        let local = point - center;
+        let size = size.map(|s| s.max(1e-6));
+        match self {
+            UvProjection::CubeFace => None,
+            UvProjection::Spherical => {
+                let dir = local.normalize();
+                let u = 0.5 + dir.z.atan2(dir.x) / (2.0 * PI);
+                let v = 0.5 + dir.y.clamp(-1.0, 1.0).asin() / PI;
+                Some((u, v))
+            }
+            UvProjection::Cylindrical => {
+                let u = 0.5 + local.z.atan2(local.x) / (2.0 * PI);
+                let v = local.y / size.y + 0.5;
+                Some((u, v))
+            }
+            UvProjection::Planar => {
+                let u = local.x / size.x + 0.5;
+                let v = local.z / size.z + 0.5;
+                Some((u, v))
+            }
+        }
+    }
+}
+
+// Cómo decae con la distancia la luz que emite un material emisivo
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EmissionFalloff {
+    InverseSquare,  // 1 / (1 + d²): el comportamiento original
+    InverseLinear,  // 1 / (1 + d): llega más lejos (iluminar una habitación entera)
+    Constant,       // Igual a cualquier distancia
+    Cutoff(f32),    // Inverso del cuadrado que se apaga suavemente hasta 0 en este radio
+}
+
+impl EmissionFalloff {
+    pub fn attenuation(&self, distance: f32) -> f32 {
+        match *self {
+            EmissionFalloff::InverseSquare => 1.0 / (1.0 + distance * distance),
+            EmissionFalloff::InverseLinear => 1.0 / (1.0 + distance),
+            EmissionFalloff::Constant => 1.0,
+            EmissionFalloff::Cutoff(radius) => {
+                if radius <= 0.0 {
+                    return 0.0;
+                }
+                // Ventana (1 - (d/r)⁴)² para que el borde no se note
+                let ratio = distance / radius;
+                let window = (1.0 - ratio.powi(4)).clamp(0.0, 1.0);
+                window * window / (1.0 + distance * distance)
+            }
+        }
+    }
+}
+
+// Problemas de un material que rompen la conservación de energía
+#[derive(Debug, Clone, PartialEq)]
+pub enum MaterialError {
+    AlbedoOverUnity(f32),   // Suma de difuso + especular + reflexión + refracción
+    NegativeAlbedo(usize),  // Índice del componente negativo
+}
+
+impl fmt::Display for MaterialError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MaterialError::AlbedoOverUnity(sum) => write!(f, "albedo components sum to {:.2} (> 1.0): the surface reflects more light than it receives", sum),
+            MaterialError::NegativeAlbedo(index) => write!(f, "albedo[{}] is negative", index),
+        }
+    }
+}
+
+impl std::error::Error for MaterialError {}
+
 #[derive(Debug, Clone)]
 pub struct Material {
     pub diffuse: Color,
@@ -14,6 +99,15 @@ pub struct Material {
     pub texture: Option<Arc<Texture>>,  
     pub emission_color: Option<Color>, 
     pub emission_intensity: f32,
+    pub emission_falloff: EmissionFalloff,
+    pub translucency: f32, // Luz que atraviesa superficies delgadas (césped, hojas) cuando están a contraluz
+    pub uv_projection: UvProjection,
+    pub checker_color: Option<Color>, // Tablero procedural: alterna diffuse y este color por celdas de 1x1 en XZ
+    pub roughness_u: f32,             // Rugosidad a lo largo de la tangente; con ambas en 0 se usa el brillo Phong
+    pub roughness_v: f32,             // ... y de la bitangente (distintas = brillo estirado, metal cepillado)
+    pub tangent: Option<Vec3>,        // Dirección del cepillado; None = tangente de la superficie
+    pub dispersion: f32,              // Separación del índice de refracción entre rojo y azul (0 = sin dispersión)
+    pub absorption: f32,              // Coeficiente de Beer-Lambert por unidad de espesor de los materiales transparentes
 }
 
 impl Material {
@@ -34,6 +128,15 @@ impl Material {
             texture: None,  // Sin textura
             emission_color,
             emission_intensity,
+            emission_falloff: EmissionFalloff::InverseSquare,
+            translucency: 0.0,
+            uv_projection: UvProjection::CubeFace,
+            checker_color: None,
+            roughness_u: 0.0,
+            roughness_v: 0.0,
+            tangent: None,
+            dispersion: 0.0,
+            absorption: 0.0,
         }
     }
 
@@ -54,18 +157,161 @@ impl Material {
             texture: Some(texture),  
             emission_color,
             emission_intensity,
+            emission_falloff: EmissionFalloff::InverseSquare,
+            translucency: 0.0,
+            uv_projection: UvProjection::CubeFace,
+            checker_color: None,
+            roughness_u: 0.0,
+            roughness_v: 0.0,
+            tangent: None,
+            dispersion: 0.0,
+            absorption: 0.0,
+        }
+    }
+
+    // Material que emite luz propia; el resto de campos toma valores neutros
+    pub const fn new_emissive(color: Color, intensity: f32) -> Self {
+        Material {
+            diffuse: color,
+            specular: 10.0,
+            albedo: [0.9, 0.1, 0.0, 0.0],
+            refractive_index: 1.0,
+            has_texture: false,
+            texture: None,
+            emission_color: Some(color),
+            emission_intensity: intensity,
+            emission_falloff: EmissionFalloff::InverseSquare,
+            translucency: 0.0,
+            uv_projection: UvProjection::CubeFace,
+            checker_color: None,
+            roughness_u: 0.0,
+            roughness_v: 0.0,
+            tangent: None,
+            dispersion: 0.0,
+            absorption: 0.0,
         }
     }
 
-    pub fn get_diffuse_color(&self, u: f32, v: f32) -> Color {
+    pub const fn new_emissive_textured(texture: Arc<Texture>, color: Color, intensity: f32) -> Self {
+        Material {
+            diffuse: Color::new(255, 255, 255),
+            specular: 10.0,
+            albedo: [0.9, 0.1, 0.0, 0.0],
+            refractive_index: 1.0,
+            has_texture: true,
+            texture: Some(texture),
+            emission_color: Some(color),
+            emission_intensity: intensity,
+            emission_falloff: EmissionFalloff::InverseSquare,
+            translucency: 0.0,
+            uv_projection: UvProjection::CubeFace,
+            checker_color: None,
+            roughness_u: 0.0,
+            roughness_v: 0.0,
+            tangent: None,
+            dispersion: 0.0,
+            absorption: 0.0,
+        }
+    }
+
+    // Color difuso en el punto de impacto: el tablero se calcula con la posición en el mundo,
+    // las texturas con (u, v)
+    pub fn get_diffuse_color(&self, u: f32, v: f32, point: &Vec3) -> Color {
+        Color::from_radiance(&self.get_diffuse_radiance(u, v, point))
+    }
+
+    // Lo mismo en punto flotante, sin recortar el texel (mapas HDR, texturas linealizadas)
+    pub fn get_diffuse_radiance(&self, u: f32, v: f32, point: &Vec3) -> Vec3 {
+        if let Some(checker_color) = self.checker_color {
+            let parity = (point.x.floor() as i64 + point.z.floor() as i64).rem_euclid(2);
+            return if parity == 0 { self.diffuse.to_radiance() } else { checker_color.to_radiance() };
+        }
         if self.has_texture {
             if let Some(texture) = &self.texture {
                 let x = (u * (texture.width as f32 - 1.0)) as usize;
                 let y = ((1.0 - v) * (texture.height as f32 - 1.0)) as usize;
-                return texture.get_color(x, y); 
+                return texture.get_radiance(x, y);
+            }
+        }
+        self.diffuse.to_radiance()
+    }
+
+    // Suelo de tablero sin archivo de imagen (celdas de una unidad en el plano XZ)
+    pub fn new_checkerboard(color_a: Color, color_b: Color, specular: f32, albedo: [f32; 4]) -> Self {
+        Material {
+            checker_color: Some(color_b),
+            ..Material::new(color_a, specular, albedo, 1.0, None, 0.0)
+        }
+    }
+
+    // Índices de refracción para R, G y B: el verde usa refractive_index y el rojo / azul se
+    // separan dispersion / 2 hacia abajo / arriba (el azul se desvía más, como en un prisma)
+    pub fn channel_refractive_indices(&self) -> [f32; 3] {
+        let half = self.dispersion / 2.0;
+        [
+            (self.refractive_index - half).max(1.0),
+            self.refractive_index,
+            self.refractive_index + half,
+        ]
+    }
+
+    // Comprueba que el albedo conserve energía: componentes no negativos que suman a lo sumo 1
+    pub fn validate(&self) -> Result<(), MaterialError> {
+        if let Some(index) = self.albedo.iter().position(|a| *a < 0.0) {
+            return Err(MaterialError::NegativeAlbedo(index));
+        }
+        let sum: f32 = self.albedo.iter().sum();
+        if sum > 1.0 + 1e-4 {
+            return Err(MaterialError::AlbedoOverUnity(sum));
+        }
+        Ok(())
+    }
+
+    // Recorta los negativos y escala el albedo para que sume 1 si se pasaba; devuelve true si cambió
+    pub fn normalize_albedo(&mut self) -> bool {
+        let original = self.albedo;
+        for a in self.albedo.iter_mut() {
+            *a = a.max(0.0);
+        }
+        let sum: f32 = self.albedo.iter().sum();
+        if sum > 1.0 {
+            for a in self.albedo.iter_mut() {
+                *a /= sum;
+            }
+        }
+        self.albedo != original
+    }
+
+    // Identificador estable del material para el pase de IDs (FNV-1a sobre su color, textura y
+    // coeficientes). Nunca es 0: ese valor queda reservado para el fondo
+    pub fn id(&self) -> u32 {
+        let mut hash: u32 = 0x811C_9DC5;
+        let mut feed = |bytes: &[u8]| {
+            for &byte in bytes {
+                hash ^= byte as u32;
+                hash = hash.wrapping_mul(0x0100_0193);
             }
+        };
+        feed(&self.diffuse.to_hex().to_le_bytes());
+        feed(&self.specular.to_bits().to_le_bytes());
+        for a in self.albedo {
+            feed(&a.to_bits().to_le_bytes());
+        }
+        feed(&self.refractive_index.to_bits().to_le_bytes());
+        if let Some(texture) = &self.texture {
+            feed(texture.id.as_bytes());
+        }
+        if let Some(emission) = self.emission_color {
+            feed(&emission.to_hex().to_le_bytes());
         }
-        self.diffuse 
+        if let Some(checker) = self.checker_color {
+            feed(&checker.to_hex().to_le_bytes());
+        }
+        hash.max(1)
+    }
+
+    pub fn is_anisotropic(&self) -> bool {
+        self.roughness_u > 0.0 && self.roughness_v > 0.0
     }
 
     pub fn black() -> Self {
@@ -78,6 +324,94 @@ impl Material {
             texture: None,  
             emission_color: None,  
             emission_intensity: 0.0,
+            emission_falloff: EmissionFalloff::InverseSquare,
+            translucency: 0.0,
+            uv_projection: UvProjection::CubeFace,
+            checker_color: None,
+            roughness_u: 0.0,
+            roughness_v: 0.0,
+            tangent: None,
+            dispersion: 0.0,
+            absorption: 0.0,
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+
+    #[test]
+    fn emissive_constructor_sets_the_emission_and_a_sane_albedo() {
+        let lamp = Material::new_emissive(Color::new(255, 200, 120), 3.5);
+        let emission = lamp.emission_color.expect("emissive materials have an emission color");
+        assert_eq!((emission.r(), emission.g(), emission.b()), (255, 200, 120));
+        assert_eq!(lamp.emission_intensity, 3.5);
+        assert_eq!(lamp.albedo, [0.9, 0.1, 0.0, 0.0]);
+        assert_eq!(lamp.refractive_index, 1.0);
+        assert!(lamp.texture.is_none());
+        assert_eq!(lamp.validate(), Ok(()));
+    }
+
+    #[test]
+    fn adjacent_checkerboard_cells_alternate_colors() {
+        let white = Color::new(230, 230, 230);
+        let red = Color::new(180, 30, 30);
+        let floor = Material::new_checkerboard(white, red, 10.0, [0.85, 0.1, 0.0, 0.0]);
+        let color_at = |x: f32, z: f32| floor.get_diffuse_color(0.0, 0.0, &Vec3::new(x, 0.0, z)).to_hex();
+
+        assert_eq!(color_at(0.5, 0.5), white.to_hex());
+        assert_eq!(color_at(1.5, 0.5), red.to_hex());
+        assert_eq!(color_at(0.5, 1.5), red.to_hex());
+        assert_eq!(color_at(1.5, 1.5), white.to_hex());
+        // También del lado negativo, sin una fila doble en el origen
+        assert_eq!(color_at(-0.5, 0.5), red.to_hex());
+        assert_eq!(color_at(-0.5, -0.5), white.to_hex());
+    }
+
+    #[test]
+    fn constant_emission_ignores_distance_and_inverse_square_decays() {
+        for distance in [0.0, 1.0, 8.0, 100.0] {
+            assert_eq!(EmissionFalloff::Constant.attenuation(distance), 1.0);
+        }
+        assert!((EmissionFalloff::InverseSquare.attenuation(1.0) - 0.5).abs() < 1e-6);
+        assert!((EmissionFalloff::InverseSquare.attenuation(8.0) - 1.0 / 65.0).abs() < 1e-6);
+        assert!((EmissionFalloff::InverseLinear.attenuation(8.0) - 1.0 / 9.0).abs() < 1e-6);
+        // El corte llega suave a 0 en su radio y no pasa del inverso del cuadrado
+        let cutoff = EmissionFalloff::Cutoff(4.0);
+        assert_eq!(cutoff.attenuation(4.0), 0.0);
+        assert_eq!(cutoff.attenuation(6.0), 0.0);
+        assert!(cutoff.attenuation(1.0) > 0.0 && cutoff.attenuation(1.0) < EmissionFalloff::InverseSquare.attenuation(1.0));
+    }
+
+    #[test]
+    fn over_unity_albedo_is_flagged_and_normalized() {
+        let mut material = Material::new(Color::new(200, 200, 200), 10.0, [0.8, 0.4, 0.2, 0.2], 1.0, None, 0.0);
+        assert!(matches!(material.validate(), Err(MaterialError::AlbedoOverUnity(sum)) if (sum - 1.6).abs() < 1e-5));
+
+        assert!(material.normalize_albedo());
+        assert_eq!(material.validate(), Ok(()));
+        assert!((material.albedo.iter().sum::<f32>() - 1.0).abs() < 1e-5);
+        assert!((material.albedo[0] / material.albedo[1] - 2.0).abs() < 1e-5);
+        assert!(!material.normalize_albedo());
+
+        let negative = Material::new(Color::new(200, 200, 200), 10.0, [0.5, -0.1, 0.0, 0.0], 1.0, None, 0.0);
+        assert_eq!(negative.validate(), Err(MaterialError::NegativeAlbedo(1)));
+    }
+
+    #[test]
+    fn spherical_projection_maps_the_pole_and_the_equator() {
+        let center = Vec3::new(1.0, 2.0, 3.0);
+        let size = Vec3::new(2.0, 2.0, 2.0);
+        let (_, top) = UvProjection::Spherical.project(&(center + Vec3::new(0.0, 1.0, 0.0)), &center, &size).unwrap();
+        let (_, bottom) = UvProjection::Spherical.project(&(center - Vec3::new(0.0, 1.0, 0.0)), &center, &size).unwrap();
+        assert!((top - 1.0).abs() < 1e-6);
+        assert!(bottom.abs() < 1e-6);
+        for direction in [Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 0.0, -1.0), Vec3::new(-0.6, 0.0, 0.8)] {
+            let (_, v) = UvProjection::Spherical.project(&(center + direction), &center, &size).unwrap();
+            assert!((v - 0.5).abs() < 1e-6);
+        }
+        assert_eq!(UvProjection::CubeFace.project(&center, &center, &size), None);
+    }
+}
@@ -0,0 +1,153 @@
+use nalgebra_glm::Vec3;
+use std::collections::HashSet;
+
+use crate::material::Material;
+use crate::quad::Quad;
+
+// Ejes (u, v) de las caras perpendiculares a cada eje; coinciden con Cube::get_uv
+fn face_axes(axis: usize) -> (usize, usize) {
+    match axis {
+        0 => (2, 1),
+        1 => (0, 2),
+        _ => (0, 1),
+    }
+}
+
+// Convierte una rejilla de cubos unitarios (cada celda es su esquina mínima) en quads:
+// descarta las caras interiores entre celdas vecinas y une las caras coplanares contiguas
+// en rectángulos lo más grandes posible (greedy meshing, como en los motores de vóxeles)
+pub fn greedy_mesh(cells: &[[i32; 3]], material: &Material) -> Vec<Quad> {
+    let mut quads = Vec::new();
+    if cells.is_empty() {
+        return quads;
+    }
+
+    let occupied: HashSet<[i32; 3]> = cells.iter().copied().collect();
+    let mut min = [i32::MAX; 3];
+    let mut max = [i32::MIN; 3];
+    for cell in cells {
+        for axis in 0..3 {
+            min[axis] = min[axis].min(cell[axis]);
+            max[axis] = max[axis].max(cell[axis]);
+        }
+    }
+
+    for axis in 0..3 {
+        let (u_axis, v_axis) = face_axes(axis);
+        let width = (max[u_axis] - min[u_axis] + 1) as usize;
+        let height = (max[v_axis] - min[v_axis] + 1) as usize;
+
+        for side in [-1, 1] {
+            for slice in min[axis]..=max[axis] {
+                // Máscara de caras expuestas en este plano: celda ocupada con la vecina vacía
+                let mut mask = vec![false; width * height];
+                for b in 0..height {
+                    for a in 0..width {
+                        let mut cell = [0; 3];
+                        cell[axis] = slice;
+                        cell[u_axis] = min[u_axis] + a as i32;
+                        cell[v_axis] = min[v_axis] + b as i32;
+                        let mut neighbor = cell;
+                        neighbor[axis] += side;
+                        mask[b * width + a] = occupied.contains(&cell) && !occupied.contains(&neighbor);
+                    }
+                }
+
+                for b in 0..height {
+                    let mut a = 0;
+                    while a < width {
+                        if !mask[b * width + a] {
+                            a += 1;
+                            continue;
+                        }
+
+                        // Extender a lo ancho y luego hacia abajo mientras la fila completa esté expuesta
+                        let mut run_width = 1;
+                        while a + run_width < width && mask[b * width + a + run_width] {
+                            run_width += 1;
+                        }
+                        let mut run_height = 1;
+                        while b + run_height < height
+                            && (a..a + run_width).all(|x| mask[(b + run_height) * width + x])
+                        {
+                            run_height += 1;
+                        }
+                        for y in b..b + run_height {
+                            for x in a..a + run_width {
+                                mask[y * width + x] = false;
+                            }
+                        }
+
+                        let mut corner = Vec3::new(0.0, 0.0, 0.0);
+                        corner[axis] = (slice + if side > 0 { 1 } else { 0 }) as f32;
+                        corner[u_axis] = (min[u_axis] + a as i32) as f32;
+                        corner[v_axis] = (min[v_axis] + b as i32) as f32;
+                        let mut u_edge = Vec3::new(0.0, 0.0, 0.0);
+                        u_edge[u_axis] = run_width as f32;
+                        let mut v_edge = Vec3::new(0.0, 0.0, 0.0);
+                        v_edge[v_axis] = run_height as f32;
+
+                        quads.push(Quad {
+                            corner,
+                            u_edge,
+                            v_edge,
+                            material: material.clone(),
+                            uv_repeat: (run_width as f32, run_height as f32),
+                        });
+                        a += run_width;
+                    }
+                }
+            }
+        }
+    }
+
+    quads
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+    use crate::cube::Cube;
+    use crate::ray_intersect::RayIntersect;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    fn material() -> Material {
+        Material::new(Color::new(200, 200, 200), 10.0, [0.9, 0.1, 0.0, 0.0], 1.0, None, 0.0)
+    }
+
+    #[test]
+    fn pair_of_cubes_meshes_into_fewer_than_twelve_faces() {
+        let quads = greedy_mesh(&[[0, 0, 0], [1, 0, 0]], &material());
+        assert_eq!(quads.len(), 6);
+        let area: f32 = quads.iter().map(|q| q.u_edge.cross(&q.v_edge).magnitude()).sum();
+        assert!((area - 10.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn meshed_grid_is_hit_where_the_cubes_are() {
+        let cells: Vec<[i32; 3]> = (0..4).flat_map(|i| (0..3).map(move |j| [i, -1, j])).collect();
+        let quads = greedy_mesh(&cells, &material());
+        let cubes: Vec<Cube> = cells
+            .iter()
+            .map(|c| {
+                let min = Vec3::new(c[0] as f32, c[1] as f32, c[2] as f32);
+                Cube { min, max: min + Vec3::new(1.0, 1.0, 1.0), material: material(), inverted: false }
+            })
+            .collect();
+        let nearest = |hits: Vec<f32>| hits.into_iter().fold(f32::INFINITY, f32::min);
+
+        let mut rng = StdRng::seed_from_u64(136);
+        for _ in 0..500 {
+            // Orígenes por encima o por debajo de la losa, nunca dentro
+            let y = if rng.gen() { rng.gen_range(0.5..3.0) } else { rng.gen_range(-4.0..-1.5) };
+            let origin = Vec3::new(rng.gen_range(-3.0..7.0), y, rng.gen_range(-3.0..6.0));
+            let target = Vec3::new(rng.gen_range(-0.5..4.5), rng.gen_range(-1.0..0.0), rng.gen_range(-0.5..3.5));
+            let dir = (target - origin).normalize();
+            let cube_hit = nearest(cubes.iter().map(|c| c.ray_intersect(&origin, &dir)).filter(|i| i.is_intersecting).map(|i| i.distance).collect());
+            let quad_hit = nearest(quads.iter().map(|q| q.ray_intersect(&origin, &dir)).filter(|i| i.is_intersecting).map(|i| i.distance).collect());
+            assert!(cube_hit == quad_hit || (cube_hit - quad_hit).abs() < 1e-3, "{:?} {:?}: {} {}", origin, dir, cube_hit, quad_hit);
+        }
+    }
+}
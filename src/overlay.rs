@@ -0,0 +1,129 @@
+use crate::color::Color;
+use crate::font::{for_each_text_pixel, text_size};
+use crate::framebuffer::Framebuffer;
+
+const PANEL_PADDING: usize = 4;
+
+// Capa de interfaz (HUD, paneles) con alfa por píxel, separada del render: se compone encima
+// al presentar, así el cuadro renderizado no se sobrescribe y los paneles pueden ser translúcidos
+pub struct Overlay {
+    pub width: usize,
+    pub height: usize,
+    pub color: Vec<Color>,
+    pub alpha: Vec<f32>, // 0 = transparente, 1 = opaco
+}
+
+impl Overlay {
+    pub fn new(width: usize, height: usize) -> Self {
+        Overlay {
+            width,
+            height,
+            color: vec![Color::black(); width * height],
+            alpha: vec![0.0; width * height],
+        }
+    }
+
+    // Deja la capa completamente transparente (al inicio de cada cuadro)
+    pub fn clear(&mut self) {
+        self.alpha.iter_mut().for_each(|a| *a = 0.0);
+    }
+
+    // Pinta un píxel "encima" de lo que ya tenía la capa (operador over)
+    fn blend_pixel(&mut self, x: usize, y: usize, color: Color, alpha: f32) {
+        if x >= self.width || y >= self.height {
+            return;
+        }
+        let index = y * self.width + x;
+        let old_alpha = self.alpha[index];
+        let new_alpha = alpha + old_alpha * (1.0 - alpha);
+        if new_alpha > 0.0 {
+            self.color[index] = self.color[index].lerp(color, alpha / new_alpha);
+        }
+        self.alpha[index] = new_alpha;
+    }
+
+    pub fn fill_rect(&mut self, x: usize, y: usize, width: usize, height: usize, color: Color, alpha: f32) {
+        let alpha = alpha.clamp(0.0, 1.0);
+        for py in y..(y + height).min(self.height) {
+            for px in x..(x + width).min(self.width) {
+                self.blend_pixel(px, py, color, alpha);
+            }
+        }
+    }
+
+    // Disco relleno centrado en (center_x, center_y); admite centros fuera de pantalla
+    pub fn fill_circle(&mut self, center_x: f32, center_y: f32, radius: f32, color: Color, alpha: f32) {
+        let alpha = alpha.clamp(0.0, 1.0);
+        let x0 = (center_x - radius).floor().max(0.0) as usize;
+        let y0 = (center_y - radius).floor().max(0.0) as usize;
+        let x1 = ((center_x + radius).ceil().max(0.0) as usize).min(self.width);
+        let y1 = ((center_y + radius).ceil().max(0.0) as usize).min(self.height);
+        for y in y0..y1 {
+            for x in x0..x1 {
+                let dx = x as f32 + 0.5 - center_x;
+                let dy = y as f32 + 0.5 - center_y;
+                if dx * dx + dy * dy <= radius * radius {
+                    self.blend_pixel(x, y, color, alpha);
+                }
+            }
+        }
+    }
+
+    pub fn draw_text(&mut self, x: usize, y: usize, text: &str, color: Color) {
+        for_each_text_pixel(x, y, text, |px, py| self.blend_pixel(px, py, color, 1.0));
+    }
+
+    // Texto sobre un panel translúcido con PANEL_PADDING píxeles de margen alrededor
+    pub fn draw_text_panel(&mut self, x: usize, y: usize, text: &str, color: Color, panel: Color, panel_alpha: f32) {
+        let (width, height) = text_size(text);
+        self.fill_rect(
+            x.saturating_sub(PANEL_PADDING),
+            y.saturating_sub(PANEL_PADDING),
+            width + 2 * PANEL_PADDING,
+            height + 2 * PANEL_PADDING,
+            panel,
+            panel_alpha,
+        );
+        self.draw_text(x, y, text, color);
+    }
+
+    // Buffer final a mostrar: el render con la capa compuesta encima (el framebuffer no se modifica)
+    pub fn present(&self, framebuffer: &Framebuffer) -> Vec<u32> {
+        framebuffer
+            .buffer
+            .iter()
+            .enumerate()
+            .map(|(index, &pixel)| {
+                let alpha = if index < self.alpha.len() { self.alpha[index] } else { 0.0 };
+                if alpha <= 0.0 {
+                    pixel
+                } else {
+                    Color::from_hex(pixel).lerp(self.color[index], alpha).to_hex()
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn half_transparent_white_panel_over_black_is_mid_gray() {
+        let mut framebuffer = Framebuffer::new(6, 4);
+        framebuffer.clear();
+        let mut overlay = Overlay::new(6, 4);
+        overlay.fill_rect(1, 1, 3, 2, Color::new(255, 255, 255), 0.5);
+
+        let presented = overlay.present(&framebuffer);
+        let gray = Color::from_hex(presented[6 + 2]);
+        assert!((127..=128).contains(&gray.r()) && gray.r() == gray.g() && gray.g() == gray.b(), "{:06x}", presented[8]);
+        // Fuera del panel queda el render y el framebuffer no se toca
+        assert_eq!(presented[0], 0x000000);
+        assert!(framebuffer.buffer.iter().all(|&pixel| pixel == 0x000000));
+
+        overlay.clear();
+        assert_eq!(overlay.present(&framebuffer), framebuffer.buffer);
+    }
+}
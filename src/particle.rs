@@ -0,0 +1,89 @@
+use nalgebra_glm::Vec3;
+use crate::aabb::Aabb;
+use crate::color::Color;
+use crate::material::Material;
+use crate::ray_intersect::{RayIntersect, Intersect};
+use crate::sampling;
+
+// Partícula puntual (chispa, polvo, estrella) dibujada como un disco que siempre mira al rayo.
+// No proyecta sombra ni recibe luz: se ve con su propio color
+pub struct Particle {
+    pub position: Vec3,
+    pub size: f32,      // Diámetro del disco en unidades de mundo
+    pub additive: bool, // Suma su color a lo que hay detrás en vez de taparlo
+    color: Color,
+    material: Material, // Hecho a partir de color: cambiarlo solo con set_color
+}
+
+impl Particle {
+    pub fn new(position: Vec3, size: f32, color: Color) -> Self {
+        Particle {
+            position,
+            size,
+            color,
+            additive: false,
+            material: Material::new_emissive(color, 1.0),
+        }
+    }
+
+    pub fn new_additive(position: Vec3, size: f32, color: Color) -> Self {
+        Particle { additive: true, ..Particle::new(position, size, color) }
+    }
+
+    pub fn color(&self) -> Color {
+        self.color
+    }
+
+    pub fn set_color(&mut self, color: Color) {
+        self.color = color;
+        self.material = Material::new_emissive(color, 1.0);
+    }
+
+    pub fn bounds(&self) -> Aabb {
+        let half = Vec3::new(self.size, self.size, self.size) * 0.5;
+        Aabb::new(self.position - half, self.position + half)
+    }
+}
+
+impl RayIntersect for Particle {
+    fn ray_intersect(&self, ray_origin: &Vec3, ray_dir: &Vec3) -> Intersect<'_> {
+        // El disco está en el plano perpendicular al rayo que pasa por la partícula
+        let length_squared = ray_dir.dot(ray_dir);
+        if length_squared < 1e-12 {
+            return Intersect::empty();
+        }
+        let t = (self.position - ray_origin).dot(ray_dir) / length_squared;
+        if t < 0.0 {
+            return Intersect::empty();
+        }
+
+        let point = ray_origin + ray_dir * t;
+        let offset = point - self.position;
+        let radius = self.size * 0.5;
+        if offset.magnitude() > radius {
+            return Intersect::empty();
+        }
+
+        // UV en el disco: (0.5, 0.5) en el centro
+        let normal = -ray_dir.normalize();
+        let (tangent, bitangent) = sampling::tangent_basis(&normal);
+        let u = 0.5 + offset.dot(&tangent) / self.size;
+        let v = 0.5 + offset.dot(&bitangent) / self.size;
+        Intersect::new(point, normal, t, &self.material, u, v)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn changing_the_color_updates_the_material_the_ray_sees() {
+        let mut particle = Particle::new(Vec3::zeros(), 1.0, Color::new(255, 0, 0));
+        particle.set_color(Color::new(0, 0, 255));
+
+        let hit = particle.ray_intersect(&Vec3::new(0.0, 0.0, 5.0), &Vec3::new(0.0, 0.0, -1.0));
+        let emission = hit.material.emission_color.unwrap();
+        assert_eq!((particle.color().b(), emission.r(), emission.b()), (255, 0, 255));
+    }
+}
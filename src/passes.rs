@@ -0,0 +1,114 @@
+use nalgebra_glm::Vec3;
+use crate::color::Color;
+use crate::framebuffer::Framebuffer;
+
+// Espacio en el que se guardan las normales del pase de normales
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NormalSpace {
+    World, // Coordenadas de la escena (para reiluminar)
+    View,  // Relativas a la cámara: +X derecha, +Y arriba, +Z hacia la cámara (la vista mira a -Z)
+}
+
+impl NormalSpace {
+    pub fn name(&self) -> &'static str {
+        match self {
+            NormalSpace::World => "world",
+            NormalSpace::View => "view",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<NormalSpace> {
+        match name {
+            "world" => Some(NormalSpace::World),
+            "view" => Some(NormalSpace::View),
+            _ => None,
+        }
+    }
+}
+
+// Buffers auxiliares producidos junto al beauty para composición y denoising.
+// Los píxeles sin impacto quedan con normal cero, albedo negro, profundidad infinita e ID 0.
+pub struct RenderPasses {
+    pub width: usize,
+    pub height: usize,
+    pub beauty: Vec<Color>,
+    pub normal: Vec<Vec3>,
+    pub albedo: Vec<Color>,
+    pub depth: Vec<f32>,
+    pub material_id: Vec<u32>, // Material::id del impacto, para armar máscaras al componer
+}
+
+// ID del pase de materiales para los píxeles de fondo
+pub const BACKGROUND_ID: u32 = 0;
+
+impl RenderPasses {
+    pub fn new(width: usize, height: usize) -> Self {
+        let size = width * height;
+        RenderPasses {
+            width,
+            height,
+            beauty: vec![Color::black(); size],
+            normal: vec![Vec3::new(0.0, 0.0, 0.0); size],
+            albedo: vec![Color::black(); size],
+            depth: vec![f32::INFINITY; size],
+            material_id: vec![BACKGROUND_ID; size],
+        }
+    }
+
+    // Copia un buffer de colores a un framebuffer para mostrarlo o guardarlo
+    pub fn to_framebuffer(&self, colors: &[Color]) -> Framebuffer {
+        let mut framebuffer = Framebuffer::new(self.width, self.height);
+        for (pixel, color) in framebuffer.buffer.iter_mut().zip(colors) {
+            *pixel = color.to_hex();
+        }
+        framebuffer
+    }
+
+    // Normales codificadas como color: cada componente de -1..1 pasa a 0..255
+    pub fn normal_colors(&self) -> Vec<Color> {
+        self.normal
+            .iter()
+            .map(|n| Color::from_rgb_f32(n.x * 0.5 + 0.5, n.y * 0.5 + 0.5, n.z * 0.5 + 0.5))
+            .collect()
+    }
+
+    // Un color distinto por ID (mezcla de bits del ID), fondo en negro
+    pub fn material_id_colors(&self) -> Vec<Color> {
+        self.material_id.iter().map(|&id| id_color(id)).collect()
+    }
+
+    // Profundidad normalizada a escala de grises (cercano = claro), fondo en negro
+    pub fn depth_colors(&self) -> Vec<Color> {
+        let max_depth = self
+            .depth
+            .iter()
+            .copied()
+            .filter(|d| d.is_finite())
+            .fold(0.0, f32::max);
+        self.depth
+            .iter()
+            .map(|&d| {
+                if d.is_finite() && max_depth > 0.0 {
+                    let value = 1.0 - d / max_depth;
+                    Color::from_rgb_f32(value, value, value)
+                } else {
+                    Color::black()
+                }
+            })
+            .collect()
+    }
+}
+
+pub fn id_color(id: u32) -> Color {
+    if id == BACKGROUND_ID {
+        return Color::black();
+    }
+    // Mezcla tipo murmur para que IDs parecidos den colores muy distintos; 0x40 evita el negro
+    let mut h = id;
+    h ^= h >> 16;
+    h = h.wrapping_mul(0x85EB_CA6B);
+    h ^= h >> 13;
+    h = h.wrapping_mul(0xC2B2_AE35);
+    h ^= h >> 16;
+    Color::new((h >> 16) as u8 | 0x40, (h >> 8) as u8 | 0x40, h as u8 | 0x40)
+}
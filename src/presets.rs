@@ -0,0 +1,92 @@
+use nalgebra_glm::Vec3;
+use std::sync::Arc;
+
+use crate::color::Color;
+use crate::material::Material;
+use crate::texture::Texture;
+
+// Catálogo de materiales ya ajustados para la escena
+// albedo: [difuso, especular, reflexión, refracción]
+
+// Césped: mate y con algo de translucidez a contraluz
+pub fn grass(texture: Arc<Texture>) -> Material {
+    let mut material = Material::new_with_texture(0.1, [0.8, 0.1, 0.0, 0.0], 1.0, texture, None, 0.0);
+    material.translucency = 0.25;
+    material
+}
+
+// Suelo de tablero procedural (no necesita textura)
+pub fn checkerboard(color_a: Color, color_b: Color) -> Material {
+    Material::new_checkerboard(color_a, color_b, 0.1, [0.85, 0.1, 0.0, 0.0])
+}
+
+// Madera sin tratar (troncos, tablones)
+pub fn wood(texture: Arc<Texture>) -> Material {
+    Material::new_with_texture(0.2, [0.9, 0.05, 0.0, 0.0], 1.0, texture, None, 0.0)
+}
+
+// Madera barnizada: un poco más de brillo (puertas, muebles)
+pub fn varnished_wood(texture: Arc<Texture>) -> Material {
+    Material::new_with_texture(0.3, [0.7, 0.1, 0.0, 0.0], 1.0, texture, None, 0.0)
+}
+
+pub fn stone(texture: Arc<Texture>) -> Material {
+    Material::new_with_texture(0.2, [0.9, 0.05, 0.0, 0.0], 1.0, texture, None, 0.0)
+}
+
+// Vidrio: deja pasar el 70% de la luz (índice 1.5) y el vidrio grueso se oscurece un poco
+pub fn glass(texture: Arc<Texture>) -> Material {
+    let mut material = Material::new_with_texture(0.3, [0.2, 0.1, 0.0, 0.7], 1.5, texture, None, 0.0);
+    material.absorption = 0.3;
+    material
+}
+
+// Metal cepillado: el brillo se estira a lo largo de `tangent` (la dirección del cepillado)
+pub fn brushed_metal(color: Color, tangent: Vec3) -> Material {
+    let mut material = Material::new(color, 125.0, [0.3, 0.6, 0.05, 0.0], 1.0, None, 0.0);
+    material.roughness_u = 0.35;
+    material.roughness_v = 0.05;
+    material.tangent = Some(tangent);
+    material
+}
+
+// Metal pulido: brillo especular alto y reflexión fuerte
+pub fn polished_metal(color: Color) -> Material {
+    Material::new(color, 125.0, [0.3, 0.6, 0.1, 0.0], 1.0, None, 0.0)
+}
+
+pub fn emissive(color: Color, intensity: f32) -> Material {
+    Material::new_emissive(color, intensity)
+}
+
+pub fn emissive_textured(texture: Arc<Texture>, color: Color, intensity: f32) -> Material {
+    Material::new_emissive_textured(texture, color, intensity)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::texture::ColorSpace;
+
+    #[test]
+    fn presets_conserve_energy_and_glass_stays_mostly_transparent() {
+        let texture = Arc::new(Texture::missing("test", ColorSpace::Srgb));
+        let glass = glass(texture.clone());
+        assert_eq!(glass.albedo[3], 0.7);
+        assert!((glass.refractive_index - 1.5).abs() < 0.05, "{}", glass.refractive_index);
+
+        let presets = [
+            grass(texture.clone()),
+            checkerboard(Color::new(255, 255, 255), Color::new(0, 0, 0)),
+            wood(texture.clone()),
+            varnished_wood(texture.clone()),
+            stone(texture.clone()),
+            glass,
+            brushed_metal(Color::new(200, 200, 200), Vec3::new(1.0, 0.0, 0.0)),
+            polished_metal(Color::new(200, 200, 200)),
+        ];
+        for material in presets {
+            assert_eq!(material.validate(), Ok(()), "{:?}", material.albedo);
+        }
+    }
+}
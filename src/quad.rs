@@ -0,0 +1,127 @@
+use nalgebra_glm::Vec3;
+use crate::aabb::Aabb;
+use crate::material::Material;
+use crate::ray_intersect::{RayIntersect, Intersect};
+
+// Rectángulo (paralelogramo) definido por una esquina y dos aristas; útil para ventanas y letreros
+pub struct Quad {
+    pub corner: Vec3,
+    pub u_edge: Vec3,
+    pub v_edge: Vec3,
+    pub material: Material,
+    pub uv_repeat: (f32, f32), // Veces que se repite la textura a lo largo de cada arista
+}
+
+impl Quad {
+    // Caja que contiene las cuatro esquinas (plana en el eje de la normal si el quad está alineado)
+    pub fn bounds(&self) -> Aabb {
+        [self.u_edge, self.v_edge, self.u_edge + self.v_edge]
+            .iter()
+            .fold(Aabb::new(self.corner, self.corner), |bounds, edge| bounds.include_point(&(self.corner + edge)))
+    }
+}
+
+impl RayIntersect for Quad {
+    fn ray_intersect(&self, ray_origin: &Vec3, ray_dir: &Vec3) -> Intersect<'_> {
+        self.ray_intersect_from(ray_origin, ray_dir, 0.0)
+    }
+
+    fn ray_intersect_from(&self, ray_origin: &Vec3, ray_dir: &Vec3, t_min: f32) -> Intersect<'_> {
+        let plane_normal = self.u_edge.cross(&self.v_edge);
+        let normal = plane_normal.normalize();
+
+        // Rayo paralelo al plano
+        let denom = normal.dot(ray_dir);
+        if denom.abs() < 1e-6 {
+            return Intersect::empty();
+        }
+
+        let t = (self.corner - ray_origin).dot(&normal) / denom;
+        if t < t_min.max(0.0) {
+            return Intersect::empty();
+        }
+
+        // Coordenadas del punto a lo largo de cada arista (válidas también si no son ortogonales)
+        let point = ray_origin + ray_dir * t;
+        let local = point - self.corner;
+        let w = plane_normal / plane_normal.dot(&plane_normal);
+        let u = w.dot(&local.cross(&self.v_edge));
+        let v = w.dot(&self.u_edge.cross(&local));
+
+        if !(0.0..=1.0).contains(&u) || !(0.0..=1.0).contains(&v) {
+            return Intersect::empty();
+        }
+
+        // El quad es de dos caras: la normal siempre mira hacia el rayo
+        let facing_normal = if denom > 0.0 { -normal } else { normal };
+
+        let bounds = self.bounds();
+        let (tex_u, tex_v) = match self.material.uv_projection.project(&point, &bounds.centroid(), &bounds.size()) {
+            Some(uv) => uv,
+            None => {
+                let (u_repeat, v_repeat) = self.uv_repeat;
+                let tex_u = if u_repeat == 1.0 { u } else { (u * u_repeat).fract() };
+                let tex_v = if v_repeat == 1.0 { v } else { (v * v_repeat).fract() };
+                (tex_u, tex_v)
+            }
+        };
+
+        Intersect::new(point, facing_normal, t, &self.material, tex_u, tex_v)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+    use crate::material::UvProjection;
+
+    #[test]
+    fn quad_is_hit_inside_its_edges_and_missed_just_outside() {
+        let material = Material::new(Color::new(200, 200, 200), 10.0, [1.0, 0.0, 0.0, 0.0], 1.0, None, 0.0);
+        let window = Quad {
+            corner: Vec3::new(0.0, 0.0, 0.0),
+            u_edge: Vec3::new(2.0, 0.0, 0.0),
+            v_edge: Vec3::new(0.0, 1.0, 0.0),
+            material,
+            uv_repeat: (1.0, 1.0),
+        };
+        let toward = Vec3::new(0.0, 0.0, -1.0);
+
+        let hit = window.ray_intersect(&Vec3::new(1.99, 0.5, 3.0), &toward);
+        assert!(hit.is_intersecting);
+        assert!((hit.distance - 3.0).abs() < 1e-5);
+        assert_eq!(hit.normal, Vec3::new(0.0, 0.0, 1.0));
+        // Por detrás la normal mira hacia el rayo (dos caras)
+        assert_eq!(window.ray_intersect(&Vec3::new(1.0, 0.5, -3.0), &-toward).normal, Vec3::new(0.0, 0.0, -1.0));
+
+        for outside in [Vec3::new(2.01, 0.5, 3.0), Vec3::new(-0.01, 0.5, 3.0), Vec3::new(1.0, 1.01, 3.0), Vec3::new(1.0, -0.01, 3.0)] {
+            assert!(!window.ray_intersect(&outside, &toward).is_intersecting);
+        }
+    }
+
+    #[test]
+    fn quad_uses_the_material_projection_instead_of_its_edges() {
+        let mut material = Material::new(Color::new(200, 200, 200), 10.0, [1.0, 0.0, 0.0, 0.0], 1.0, None, 0.0);
+        let quad = |material: Material| Quad {
+            corner: Vec3::new(-1.0, 0.0, -1.0),
+            u_edge: Vec3::new(0.0, 0.0, 2.0),
+            v_edge: Vec3::new(2.0, 0.0, 0.0),
+            material,
+            uv_repeat: (1.0, 1.0),
+        };
+        let origin = Vec3::new(0.5, 3.0, -0.5);
+        let down = Vec3::new(0.0, -1.0, 0.0);
+
+        // Por aristas: u va a lo largo de z y v a lo largo de x
+        let edges = quad(material.clone());
+        let hit = edges.ray_intersect(&origin, &down);
+        assert!((hit.u - 0.25).abs() < 1e-5 && (hit.v - 0.75).abs() < 1e-5);
+
+        // Planar: u a lo largo de x y v a lo largo de z, aunque el quad no tenga altura
+        material.uv_projection = UvProjection::Planar;
+        let planar = quad(material);
+        let hit = planar.ray_intersect(&origin, &down);
+        assert!((hit.u - 0.75).abs() < 1e-5 && (hit.v - 0.25).abs() < 1e-5);
+    }
+}
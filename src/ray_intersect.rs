@@ -1,23 +1,26 @@
 use crate::material::Material;
+use once_cell::sync::Lazy;
 use nalgebra_glm::Vec3;
+use crate::sampling;
 
+// Impacto de un rayo. El material se toma prestado del objeto (no se clona en cada impacto)
 #[derive(Debug, Clone)]
-pub struct Intersect {
+pub struct Intersect<'a> {
     pub point: Vec3,
     pub normal: Vec3,
     pub distance: f32,
     pub is_intersecting: bool,
-    pub material: Material,
+    pub material: &'a Material,
     pub u: f32,
     pub v: f32,
 }
 
-impl Intersect {
+impl<'a> Intersect<'a> {
     pub fn new(
         point: Vec3,
         normal: Vec3,
         distance: f32,
-        material: Material,
+        material: &'a Material,
         u: f32,
         v: f32,
     ) -> Self {
@@ -38,13 +41,33 @@ impl Intersect {
             normal: Vec3::new(0.0, 0.0, 0.0),
             distance: 0.0,
             is_intersecting: false,
-            material: Material::black(),
+            material: &EMPTY_MATERIAL,
             u: 0.0,
             v: 0.0,
         }
     }
+
+    // Tangente y bitangente de la superficie en el punto de impacto
+    pub fn tangent_basis(&self) -> (Vec3, Vec3) {
+        sampling::tangent_basis(&self.normal)
+    }
 }
 
+// Material de los impactos vacíos (nunca se sombrea, pero Intersect siempre lleva uno)
+static EMPTY_MATERIAL: Lazy<Material> = Lazy::new(Material::black);
+
 pub trait RayIntersect {
-    fn ray_intersect(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Intersect;
+    fn ray_intersect(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Intersect<'_>;
+
+    // Primer impacto con distancia >= t_min (plano cercano): si el rayo arranca fuera de la forma
+    // pero t_min cae dentro, se ve la cara de salida en lugar de perder la forma entera. Por
+    // defecto el origen avanza hasta t_min y la distancia se devuelve desde el origen original
+    fn ray_intersect_from(&self, ray_origin: &Vec3, ray_direction: &Vec3, t_min: f32) -> Intersect<'_> {
+        if t_min <= 0.0 {
+            return self.ray_intersect(ray_origin, ray_direction);
+        }
+        let mut intersect = self.ray_intersect(&(ray_origin + ray_direction * t_min), ray_direction);
+        intersect.distance += t_min;
+        intersect
+    }
 }
\ No newline at end of file
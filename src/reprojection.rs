@@ -0,0 +1,200 @@
+use nalgebra_glm::Vec3;
+use crate::camera::Camera;
+use crate::framebuffer::Framebuffer;
+use crate::scene::Scene;
+use crate::{clipped_region, display_color, heaviest_surface, render_pixel_samples, trace_rows, RenderSettings};
+
+// Historial del cuadro anterior para reutilizar muestras mientras la cámara se mueve poco
+pub struct TemporalCache {
+    width: usize,
+    height: usize,
+    radiance: Vec<Vec3>,
+    depth: Vec<f32>, // Distancia del ojo al impacto (INFINITY = fondo, sin historial)
+    normal: Vec<Vec3>,
+    camera: Option<Camera>,
+    frame: u32,
+    pub blend: f32,            // Peso de la muestra nueva al mezclar con el historial
+    pub refresh_interval: u32, // Un píxel reutilizado se vuelve a trazar cada tantos cuadros
+    pub depth_tolerance: f32,  // Diferencia relativa de profundidad aceptada al reproyectar
+    pub normal_tolerance: f32, // Coseno mínimo entre la normal actual y la del historial
+}
+
+impl TemporalCache {
+    pub fn new(width: usize, height: usize) -> Self {
+        TemporalCache {
+            width,
+            height,
+            radiance: vec![Vec3::new(0.0, 0.0, 0.0); width * height],
+            depth: vec![f32::INFINITY; width * height],
+            normal: vec![Vec3::new(0.0, 0.0, 0.0); width * height],
+            camera: None,
+            frame: 0,
+            blend: 0.2,
+            refresh_interval: 4,
+            depth_tolerance: 0.02,
+            normal_tolerance: 0.9,
+        }
+    }
+
+    // Descarta el historial (cambió la escena, la luz o el tamaño)
+    pub fn reset(&mut self) {
+        self.camera = None;
+        self.depth.iter_mut().for_each(|d| *d = f32::INFINITY);
+    }
+
+    // Radiancia del cuadro anterior en el punto, si ese píxel veía la misma superficie
+    fn history(&self, point: &Vec3, normal: &Vec3, settings: &RenderSettings) -> Option<Vec3> {
+        let previous = self.camera.as_ref()?;
+        let (px, py) = previous.project(point, self.width as f32, self.height as f32, settings.fov, settings.pixel_aspect)?;
+        let (px, py) = (px.round(), py.round());
+        if px < 0.0 || py < 0.0 || px >= self.width as f32 || py >= self.height as f32 {
+            return None;
+        }
+        let index = py as usize * self.width + px as usize;
+
+        // Desoclusión: el píxel anterior veía otra superficie más cerca o más lejos
+        let expected_depth = (point - previous.eye).magnitude();
+        let previous_depth = self.depth[index];
+        if !previous_depth.is_finite() || (previous_depth - expected_depth).abs() > self.depth_tolerance * expected_depth {
+            return None;
+        }
+        // A la misma profundidad pero otra cara (una arista del cubo, una pared contigua)
+        if self.normal[index].dot(normal) < self.normal_tolerance {
+            return None;
+        }
+        Some(self.radiance[index])
+    }
+}
+
+// Render con reproyección temporal: cada píxel busca su superficie en el cuadro anterior y, si la
+// encuentra, reutiliza esa radiancia y solo una fracción de los píxeles se vuelve a sombrear por
+// cuadro. Los píxeles sin historial (desoclusiones, fondo) se trazan completos con el núcleo de
+// render, así que respeta el anti-aliasing, el motion blur y la región de los ajustes
+pub fn render_temporal(
+    framebuffer: &mut Framebuffer,
+    scene: &Scene,
+    camera: &Camera,
+    settings: &RenderSettings,
+    cache: &mut TemporalCache,
+) {
+    let width = framebuffer.width;
+    let height = framebuffer.height;
+    if cache.width != width || cache.height != height {
+        *cache = TemporalCache::new(width, height);
+    }
+
+    let settings = &RenderSettings { width, height, ..settings.clone() };
+    let (x0, y0, x1, y1) = clipped_region(settings, width, height);
+    let refresh_interval = cache.refresh_interval.max(1) as usize;
+    let rows: Vec<usize> = (y0..y1).collect();
+
+    let history: &TemporalCache = cache;
+    let traced = trace_rows(&rows, (x0, x1), |x, y| {
+        // Píxel trazado completo: la profundidad y la normal del historial salen del mismo trazado
+        let fresh = || {
+            let (radiance, surfaces) = render_pixel_samples(x, y, width as f32, height as f32, scene, camera, settings);
+            let hit = heaviest_surface(&surfaces).map(|surface| (surface.point, surface.depth, surface.normal));
+            (radiance, hit)
+        };
+        // Patrón rotativo: cada cuadro se refresca 1 de cada refresh_interval píxeles
+        let (radiance, hit) = if (x + y + history.frame as usize).is_multiple_of(refresh_interval) {
+            let (radiance, hit) = fresh();
+            let previous = hit.and_then(|(point, _, normal)| history.history(&point, &normal, settings));
+            (previous.map_or(radiance, |previous| previous.lerp(&radiance, history.blend)), hit)
+        } else {
+            let (ray_origin, ray_direction) = camera.get_ray_with_pixel_aspect(x as f32, y as f32, width as f32, height as f32, settings.fov, settings.pixel_aspect);
+            let intersect = scene.intersect_clipped(&ray_origin, &ray_direction, 0.0, settings.near_clip, settings.far_clip);
+            let previous = intersect.is_intersecting.then(|| history.history(&intersect.point, &intersect.normal, settings)).flatten();
+            match previous {
+                Some(previous) => (previous, Some((intersect.point, intersect.distance, intersect.normal))),
+                None => fresh(),
+            }
+        };
+        let (depth, normal) = hit.map_or((f32::INFINITY, Vec3::new(0.0, 0.0, 0.0)), |(_, depth, normal)| (depth, normal));
+        (radiance, depth, normal)
+    });
+
+    // Fuera de la región no queda historial: esos píxeles no se trazaron en este cuadro
+    cache.depth.iter_mut().for_each(|d| *d = f32::INFINITY);
+    for (y, row) in rows.iter().zip(traced) {
+        for (x, (radiance, depth, normal)) in (x0..x1).zip(row) {
+            let index = y * width + x;
+            framebuffer.buffer[index] = display_color(&radiance, settings).to_hex();
+            cache.radiance[index] = radiance;
+            cache.depth[index] = depth;
+            cache.normal[index] = normal;
+        }
+    }
+    cache.camera = Some(camera.clone());
+    cache.frame = cache.frame.wrapping_add(1);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cube::Cube;
+    use crate::material::Material;
+    use crate::color::Color;
+
+    fn scene_with_cube() -> Scene {
+        let mut scene = Scene::new();
+        let material = Material::new(Color::new(200, 120, 60), 10.0, [0.9, 0.1, 0.0, 0.0], 1.0, None, 0.0);
+        scene.add_cube(Cube { min: Vec3::new(-1.0, -1.0, -1.0), max: Vec3::new(1.0, 1.0, 1.0), material, inverted: false }, &[]);
+        scene
+    }
+
+    #[test]
+    fn static_camera_reprojection_is_identity_and_keeps_blending() {
+        let scene = scene_with_cube();
+        let camera = Camera::new(Vec3::new(0.0, 0.0, 6.0), Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+        let settings = RenderSettings { width: 16, height: 12, ..RenderSettings::default() };
+        let mut cache = TemporalCache::new(16, 12);
+
+        let mut first = Framebuffer::new(16, 12);
+        render_temporal(&mut first, &scene, &camera, &settings, &mut cache);
+        let mut reference = Framebuffer::new(16, 12);
+        crate::render(&mut reference, &scene, &camera, &settings);
+        assert_eq!(first.buffer, reference.buffer);
+
+        // Cámara quieta: cada píxel encuentra su propio historial y la mezcla con la muestra nueva
+        // (igual a la anterior en una escena sin ruido) deja la imagen igual
+        for _ in 0..3 {
+            let mut next = Framebuffer::new(16, 12);
+            render_temporal(&mut next, &scene, &camera, &settings, &mut cache);
+            assert_eq!(next.buffer, first.buffer);
+        }
+        let center = 6 * 16 + 8;
+        assert!(cache.depth[center].is_finite());
+        let point = camera.eye + (Vec3::new(0.0, 0.0, 1.0) - camera.eye).normalize() * cache.depth[center];
+        assert!(cache.history(&point, &Vec3::new(0.0, 0.0, 1.0), &settings).is_some());
+    }
+
+    #[test]
+    fn history_is_rejected_when_the_normal_changes() {
+        let scene = scene_with_cube();
+        let camera = Camera::new(Vec3::new(0.0, 0.0, 6.0), Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+        let settings = RenderSettings { width: 16, height: 12, ..RenderSettings::default() };
+        let mut cache = TemporalCache::new(16, 12);
+        render_temporal(&mut Framebuffer::new(16, 12), &scene, &camera, &settings, &mut cache);
+
+        let point = Vec3::new(0.0, 0.0, 1.0);
+        assert!(cache.history(&point, &Vec3::new(0.0, 0.0, 1.0), &settings).is_some());
+        assert!(cache.history(&point, &Vec3::new(1.0, 0.0, 0.0), &settings).is_none());
+    }
+
+    #[test]
+    fn traced_pixels_keep_the_hit_of_their_own_samples() {
+        let scene = scene_with_cube();
+        let camera = Camera::new(Vec3::new(0.0, 0.0, 6.0), Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+        let settings = RenderSettings { width: 16, height: 12, aa_samples: 4, ..RenderSettings::default() };
+        let mut cache = TemporalCache::new(16, 12);
+        render_temporal(&mut Framebuffer::new(16, 12), &scene, &camera, &settings, &mut cache);
+
+        // Sin historial todos los píxeles se trazan: el centro ve la cara frontal del cubo a 5
+        // unidades y la esquina ve el cielo
+        let center = 6 * 16 + 8;
+        assert!((cache.depth[center] - 5.0).abs() < 0.05, "{}", cache.depth[center]);
+        assert!((cache.normal[center] - Vec3::new(0.0, 0.0, 1.0)).magnitude() < 1e-4);
+        assert!(cache.depth[0].is_infinite());
+    }
+}
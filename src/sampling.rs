@@ -0,0 +1,211 @@
+use nalgebra_glm::Vec3;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::cell::RefCell;
+use std::f32::consts::PI;
+
+thread_local! {
+    // Generador del píxel en curso; render_pixel lo reinicia con (semilla, x, y, cuadro), así el
+    // resultado no depende de qué hilo traza cada fila
+    static PIXEL_RNG: RefCell<StdRng> = RefCell::new(StdRng::seed_from_u64(0));
+}
+
+// Reinicia el generador del hilo para el píxel (x, y) del cuadro `frame_index`
+pub fn seed_pixel(seed: u64, x: usize, y: usize, frame_index: u32) {
+    let mut hash = seed ^ 0x9e37_79b9_7f4a_7c15;
+    for value in [x as u64, y as u64, frame_index as u64] {
+        hash = (hash ^ value).wrapping_mul(0x1000_0000_01b3);
+        hash ^= hash >> 29;
+    }
+    PIXEL_RNG.with(|rng| *rng.borrow_mut() = StdRng::seed_from_u64(hash));
+}
+
+// Número uniforme en [0, 1) del generador del píxel en curso
+pub fn random_f32() -> f32 {
+    PIXEL_RNG.with(|rng| rng.borrow_mut().gen())
+}
+
+// Base ortonormal (tangente, bitangente) perpendicular a la normal
+pub fn tangent_basis(normal: &Vec3) -> (Vec3, Vec3) {
+    // Eje auxiliar que no sea casi paralelo a la normal
+    let helper = if normal.x.abs() > 0.9 {
+        Vec3::new(0.0, 1.0, 0.0)
+    } else {
+        Vec3::new(1.0, 0.0, 0.0)
+    };
+    let tangent = helper.cross(normal).normalize();
+    let bitangent = normal.cross(&tangent);
+    (tangent, bitangent)
+}
+
+// Dirección aleatoria en el hemisferio de la normal con densidad proporcional a cos(θ)
+pub fn sample_cosine_hemisphere<R: Rng>(normal: &Vec3, rng: &mut R) -> Vec3 {
+    // Muestreo uniforme del disco unitario proyectado al hemisferio (método de Malley)
+    let r1: f32 = rng.gen();
+    let r2: f32 = rng.gen();
+    let radius = r1.sqrt();
+    let phi = 2.0 * PI * r2;
+    let x = radius * phi.cos();
+    let y = radius * phi.sin();
+    let z = (1.0 - r1).max(0.0).sqrt();
+
+    let (tangent, bitangent) = tangent_basis(normal);
+    (tangent * x + bitangent * y + normal * z).normalize()
+}
+
+// Densidad de probabilidad de `direction` para sample_cosine_hemisphere: cos(θ) / π
+pub fn cosine_hemisphere_pdf(normal: &Vec3, direction: &Vec3) -> f32 {
+    normal.dot(direction).max(0.0) / PI
+}
+
+// Promedio de muestras de radiancia en el dominio x/(1+x), invertido al final: una muestra muy
+// brillante pesa a lo sumo 1 y no domina la media (supresión de fireflies)
+pub fn average_tonemapped(samples: &[Vec3]) -> Vec3 {
+    if samples.is_empty() {
+        return Vec3::new(0.0, 0.0, 0.0);
+    }
+    let tonemap = |x: f32| x.max(0.0) / (1.0 + x.max(0.0));
+    let inverse = |y: f32| y / (1.0 - y).max(1e-6);
+
+    let mut sum = Vec3::new(0.0, 0.0, 0.0);
+    for sample in samples {
+        sum += Vec3::new(tonemap(sample.x), tonemap(sample.y), tonemap(sample.z));
+    }
+    let mean = sum / samples.len() as f32;
+    Vec3::new(inverse(mean.x), inverse(mean.y), inverse(mean.z))
+}
+
+// Escala una muestra de radiancia para que su luminancia (Rec. 709) no supere `max`,
+// conservando el tono; las muestras bajo el límite no cambian
+pub fn clamp_sample_luminance(sample: Vec3, max: f32) -> Vec3 {
+    let luminance = 0.2126 * sample.x + 0.7152 * sample.y + 0.0722 * sample.z;
+    if luminance > max && luminance > 0.0 {
+        sample * (max / luminance)
+    } else {
+        sample
+    }
+}
+
+// Inverso radical de `index` en `base`: refleja los dígitos alrededor del punto decimal
+// (base 2: 1 -> 0.5, 2 -> 0.25, 3 -> 0.75, ...)
+pub fn radical_inverse(mut index: u32, base: u32) -> f32 {
+    let inverse_base = 1.0 / base as f64;
+    let mut factor = inverse_base;
+    let mut result = 0.0;
+    while index > 0 {
+        result += (index % base) as f64 * factor;
+        index /= base;
+        factor *= inverse_base;
+    }
+    result as f32
+}
+
+// Punto `index` de la secuencia de Halton 2D (bases 2 y 3) en [0, 1)²; el índice 0 es (0, 0),
+// por eso los usuarios suelen empezar en 1
+pub fn halton_2d(index: u32) -> (f32, f32) {
+    (radical_inverse(index, 2), radical_inverse(index, 3))
+}
+
+// Desplazamiento fijo por píxel (rotación de Cranley-Patterson) para que píxeles vecinos no
+// repitan el mismo patrón de Halton; depende solo de (x, y), así los cuadros siguen sumando
+pub fn pixel_rotation(x: usize, y: usize) -> (f32, f32) {
+    let mut hash = (x as u32).wrapping_mul(0x8da6_b343) ^ (y as u32).wrapping_mul(0xd816_3841);
+    hash ^= hash >> 13;
+    hash = hash.wrapping_mul(0x5bd1_e995);
+    hash ^= hash >> 15;
+    ((hash & 0xFFFF) as f32 / 65536.0, (hash >> 16) as f32 / 65536.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cosine_samples_stay_in_the_hemisphere_and_cluster_around_the_normal() {
+        let mut rng = StdRng::seed_from_u64(133);
+        let normal = Vec3::new(1.0, 2.0, -0.5).normalize();
+        let count = 20000;
+        let mut mean_cosine = 0.0;
+        let mut mean_direction = Vec3::zeros();
+        for _ in 0..count {
+            let direction = sample_cosine_hemisphere(&normal, &mut rng);
+            assert!((direction.magnitude() - 1.0).abs() < 1e-4);
+            let cosine = direction.dot(&normal);
+            assert!(cosine >= -1e-5, "{:?}", direction);
+            assert!((cosine_hemisphere_pdf(&normal, &direction) - cosine.max(0.0) / PI).abs() < 1e-6);
+            mean_cosine += cosine / count as f32;
+            mean_direction += direction / count as f32;
+        }
+        // Con densidad cos(θ)/π el coseno medio es 2/3 (1/2 si fuera uniforme)
+        assert!((mean_cosine - 2.0 / 3.0).abs() < 0.01, "{}", mean_cosine);
+        assert!(mean_direction.normalize().dot(&normal) > 0.999);
+    }
+
+    #[test]
+    fn outlier_sample_is_clamped_while_normal_samples_keep_their_mean() {
+        let normal = [Vec3::new(0.2, 0.2, 0.2), Vec3::new(0.3, 0.3, 0.3), Vec3::new(0.25, 0.25, 0.25)];
+        let outlier = Vec3::new(50.0, 40.0, 30.0);
+        let max = 1.0;
+
+        let clamped: Vec<Vec3> = normal.iter().map(|s| clamp_sample_luminance(*s, max)).collect();
+        let mean = clamped.iter().fold(Vec3::zeros(), |sum, s| sum + s) / clamped.len() as f32;
+        assert!((mean - Vec3::new(0.25, 0.25, 0.25)).magnitude() < 1e-6);
+
+        let limited = clamp_sample_luminance(outlier, max);
+        let luminance = 0.2126 * limited.x + 0.7152 * limited.y + 0.0722 * limited.z;
+        assert!((luminance - max).abs() < 1e-5);
+        // Conserva el tono: las proporciones entre canales no cambian
+        assert!((limited.x / limited.z - outlier.x / outlier.z).abs() < 1e-4);
+    }
+
+    #[test]
+    fn halton_matches_reference_values_and_covers_the_square() {
+        let base2 = [0.5, 0.25, 0.75, 0.125, 0.625, 0.375, 0.875, 0.0625];
+        let base3 = [1.0 / 3.0, 2.0 / 3.0, 1.0 / 9.0, 4.0 / 9.0, 7.0 / 9.0, 2.0 / 9.0, 5.0 / 9.0, 8.0 / 9.0];
+        for index in 1..=8u32 {
+            let (u, v) = halton_2d(index);
+            assert!((u - base2[index as usize - 1]).abs() < 1e-6);
+            assert!((v - base3[index as usize - 1]).abs() < 1e-6);
+        }
+
+        // 64 puntos en una grilla de 4x4: cada celda recibe cerca de 4 (al azar habría celdas vacías o con 8+)
+        let mut cells = [0; 16];
+        for index in 1..=64 {
+            let (u, v) = halton_2d(index);
+            cells[(u * 4.0) as usize * 4 + (v * 4.0) as usize] += 1;
+        }
+        assert!(cells.iter().all(|count| (3..=5).contains(count)), "{:?}", cells);
+    }
+
+    #[test]
+    fn tonemapped_average_has_lower_variance_than_naive_average_on_spiky_samples() {
+        let mut rng = StdRng::seed_from_u64(145);
+        let variance = |values: &[f32]| {
+            let mean = values.iter().sum::<f32>() / values.len() as f32;
+            values.iter().map(|v| (v - mean) * (v - mean)).sum::<f32>() / values.len() as f32
+        };
+
+        let mut naive = Vec::new();
+        let mut tonemapped = Vec::new();
+        for _ in 0..200 {
+            // Muestras de 0.5 con fireflies ocasionales 200 veces más brillantes
+            let samples: Vec<Vec3> = (0..16)
+                .map(|_| if rng.gen::<f32>() < 0.05 { Vec3::repeat(100.0) } else { Vec3::repeat(0.5) })
+                .collect();
+            naive.push((samples.iter().fold(Vec3::zeros(), |sum, s| sum + s) / samples.len() as f32).x);
+            tonemapped.push(average_tonemapped(&samples).x);
+        }
+        assert!(variance(&tonemapped) < variance(&naive) * 0.1);
+    }
+
+    #[test]
+    fn seeded_pixels_repeat_their_sequence_and_differ_between_pixels() {
+        let draw = |seed: u64, x: usize, y: usize| {
+            seed_pixel(seed, x, y, 0);
+            (0..4).map(|_| random_f32()).collect::<Vec<f32>>()
+        };
+        assert_eq!(draw(7, 3, 5), draw(7, 3, 5));
+        assert_ne!(draw(7, 3, 5), draw(7, 5, 3));
+        assert_ne!(draw(7, 3, 5), draw(8, 3, 5));
+    }
+}
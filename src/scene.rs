@@ -0,0 +1,1091 @@
+use nalgebra_glm::Vec3;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::{Arc, OnceLock};
+use crate::aabb::Aabb;
+use crate::bvh::Bvh;
+use crate::color::Color;
+use crate::cube::Cube;
+use crate::fog::VolumetricFog;
+use crate::import::ImportTransform;
+use crate::instance::InstancedCubes;
+use crate::light::Light;
+use crate::material::{Material, MaterialError};
+use crate::particle::Particle;
+use crate::quad::Quad;
+use crate::sdf::SignedDistance;
+use crate::ray_intersect::{RayIntersect, Intersect};
+use crate::sky::SUN_DISC_COLOR;
+use crate::texture::Texture;
+
+pub const AMBIENT_LIGHT_COLOR: Color = Color::new(50, 50, 50);
+pub const AMBIENT_INTENSITY: f32 = 0.3;
+// Impactos a menos de esto se consideran en la misma profundidad (caras compartidas entre cubos)
+pub const DEPTH_TIE_EPSILON: f32 = 1e-4;
+
+pub const AMBIENT_GROUND_COLOR: Color = Color::new(40, 32, 24); // Rebote cálido y oscuro desde el suelo
+
+// Envoltura de un objeto de la escena con sus etiquetas de capa y visibilidad
+pub struct SceneObject<T> {
+    pub shape: T,
+    pub tags: Vec<String>,
+    pub visible: bool,
+    pub velocity: Vec3, // Desplazamiento por segundo, usado para el motion blur
+}
+
+impl<T> SceneObject<T> {
+    pub fn new(shape: T, tags: &[&str]) -> Self {
+        SceneObject {
+            shape,
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            visible: true,
+            velocity: Vec3::new(0.0, 0.0, 0.0),
+        }
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t == tag)
+    }
+}
+
+impl<T: RayIntersect> SceneObject<T> {
+    // Intersección con el objeto desplazado a su posición en el instante `time`
+    pub fn intersect_at(&self, ray_origin: &Vec3, ray_direction: &Vec3, time: f32) -> Intersect<'_> {
+        self.intersect_from(ray_origin, ray_direction, time, 0.0)
+    }
+
+    // Igual, aceptando solo impactos con distancia >= t_min
+    pub fn intersect_from(&self, ray_origin: &Vec3, ray_direction: &Vec3, time: f32, t_min: f32) -> Intersect<'_> {
+        let offset = self.velocity * time;
+        let local_origin = ray_origin - offset;
+        let mut intersect = self.shape.ray_intersect_from(&local_origin, ray_direction, t_min);
+        if intersect.is_intersecting {
+            intersect.point += offset;
+        }
+        intersect
+    }
+}
+
+// Primitiva que indexa el BVH: un cubo, una instancia de un grupo, un quad o un SDF
+#[derive(Debug, Clone, Copy)]
+enum Primitive {
+    Cube(usize),
+    Instance(usize, usize), // (grupo, instancia)
+    Quad(usize),
+    Sdf(usize),
+}
+
+// Lista de primitivas en el orden del recorrido lineal (el orden desempata caras coincidentes) y su
+// BVH. Si algo se mueve (motion blur) las cajas fijas no sirven: sin BVH se recorre la lista entera
+struct Acceleration {
+    primitives: Vec<Primitive>,
+    bvh: Option<Bvh>,
+}
+
+// La geometría es privada: todo cambio pasa por add_* / *_mut, que invalidan la caja de sombras y el BVH
+pub struct Scene {
+    objects: Vec<SceneObject<Cube>>,
+    instances: Vec<SceneObject<InstancedCubes>>,
+    quads: Vec<SceneObject<Quad>>,
+    sdfs: Vec<SceneObject<SignedDistance>>, // Formas trazadas por sphere tracing
+    pub particles: Vec<SceneObject<Particle>>,  // Solo se ven en los rayos, no proyectan sombra
+    pub lights: Vec<Light>,
+    pub skybox: Option<Arc<Texture>>, // Sin skybox se usa el cielo analítico o un color fijo
+    pub backdrop: Option<Arc<Texture>>, // Imagen plana detrás de la escena, por posición en pantalla (no por dirección)
+    pub skybox_yaw: f32,              // Giro del entorno alrededor del eje Y, en radianes
+    pub sun_direction: Option<Vec3>,  // Dirección hacia el sol: cielo analítico, disco solar y destello especular
+    pub sun_color: Color,
+    pub fog: Option<VolumetricFog>,   // Niebla volumétrica en los rayos de cámara
+    shadow_bounds: OnceLock<Option<Aabb>>, // Caja de toda la escena para descartar rayos de sombra (ver ray_escapes_bounds)
+    acceleration: OnceLock<Acceleration>,  // BVH de la geometría, construido en el primer rayo después de cada cambio
+    pub ambient_color: Color,         // Tinte de la luz ambiental (cálido de día, frío de noche)
+    pub ambient_intensity: f32,
+    pub hemisphere_ambient: bool,     // Mezcla cielo (ambient_color) y suelo según la normal
+    pub ambient_ground_color: Color,
+}
+
+// Resumen de la geometría de la escena
+#[derive(Debug, Clone, Copy)]
+pub struct SceneStats {
+    pub object_count: usize,
+    pub triangle_count: usize, // Equivalente en triángulos: 12 por cubo, 2 por quad
+    pub total_surface_area: f32,
+    pub bounds_min: Vec3,
+    pub bounds_max: Vec3,
+}
+
+impl SceneStats {
+    pub fn bounds(&self) -> Aabb {
+        Aabb::new(self.bounds_min, self.bounds_max)
+    }
+}
+
+impl fmt::Display for SceneStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Objetos: {}, triángulos: {}, área total: {:.2}, límites: ({:.2}, {:.2}, {:.2}) - ({:.2}, {:.2}, {:.2})",
+            self.object_count,
+            self.triangle_count,
+            self.total_surface_area,
+            self.bounds_min.x, self.bounds_min.y, self.bounds_min.z,
+            self.bounds_max.x, self.bounds_max.y, self.bounds_max.z,
+        )
+    }
+}
+
+impl Default for Scene {
+    fn default() -> Self {
+        Scene::new()
+    }
+}
+
+impl Scene {
+    pub fn new() -> Self {
+        Scene {
+            objects: Vec::new(),
+            instances: Vec::new(),
+            quads: Vec::new(),
+            sdfs: Vec::new(),
+            particles: Vec::new(),
+            lights: Vec::new(),
+            skybox: None,
+            backdrop: None,
+            skybox_yaw: 0.0,
+            sun_direction: None,
+            sun_color: SUN_DISC_COLOR,
+            fog: None,
+            shadow_bounds: OnceLock::new(),
+            acceleration: OnceLock::new(),
+            ambient_color: AMBIENT_LIGHT_COLOR,
+            ambient_intensity: AMBIENT_INTENSITY,
+            hemisphere_ambient: false,
+            ambient_ground_color: AMBIENT_GROUND_COLOR,
+        }
+    }
+
+    // Usa el color promedio del skybox como luz ambiental
+    pub fn set_ambient_from_skybox(&mut self) {
+        if let Some(skybox) = &self.skybox {
+            self.ambient_color = skybox.average_color();
+        }
+    }
+
+    // Luz ambiental que recibe una superficie con esta normal
+    pub fn ambient_light(&self, normal: &Vec3) -> Color {
+        if !self.hemisphere_ambient {
+            return self.ambient_color * self.ambient_intensity;
+        }
+        // normal.y = 1 recibe solo cielo, normal.y = -1 solo suelo
+        let sky_weight = (normal.y * 0.5 + 0.5).clamp(0.0, 1.0);
+        let blended = self.ambient_color * sky_weight + self.ambient_ground_color * (1.0 - sky_weight);
+        blended * self.ambient_intensity
+    }
+
+    pub fn add_cube(&mut self, cube: Cube, tags: &[&str]) {
+        self.invalidate_bounds();
+        self.objects.push(SceneObject::new(cube, tags));
+    }
+
+    pub fn add_instances(&mut self, instances: InstancedCubes, tags: &[&str]) {
+        self.invalidate_bounds();
+        self.instances.push(SceneObject::new(instances, tags));
+    }
+
+    pub fn add_quad(&mut self, quad: Quad, tags: &[&str]) {
+        self.invalidate_bounds();
+        self.quads.push(SceneObject::new(quad, tags));
+    }
+
+    fn has_moving_geometry(&self) -> bool {
+        self.objects.iter().any(|o| o.velocity != Vec3::zeros())
+            || self.instances.iter().any(|o| o.velocity != Vec3::zeros())
+            || self.quads.iter().any(|o| o.velocity != Vec3::zeros())
+            || self.sdfs.iter().any(|o| o.velocity != Vec3::zeros())
+    }
+
+    fn compute_acceleration(&self) -> Acceleration {
+        let mut primitives = Vec::new();
+        let mut bounds = Vec::new();
+        for (index, object) in self.objects.iter().enumerate() {
+            primitives.push(Primitive::Cube(index));
+            bounds.push(object.shape.bounds());
+        }
+        for (group_index, group) in self.instances.iter().enumerate() {
+            for index in 0..group.shape.instances.len() {
+                primitives.push(Primitive::Instance(group_index, index));
+                bounds.push(group.shape.instance_bounds(index));
+            }
+        }
+        for (index, quad) in self.quads.iter().enumerate() {
+            primitives.push(Primitive::Quad(index));
+            bounds.push(quad.shape.bounds());
+        }
+        for (index, sdf) in self.sdfs.iter().enumerate() {
+            primitives.push(Primitive::Sdf(index));
+            bounds.push(sdf.shape.bounds());
+        }
+        let bvh = (!self.has_moving_geometry()).then(|| Bvh::build_parallel(&bounds));
+        Acceleration { primitives, bvh }
+    }
+
+    fn acceleration(&self) -> &Acceleration {
+        self.acceleration.get_or_init(|| self.compute_acceleration())
+    }
+
+    // BVH de la geometría (cubos, instancias, quads y SDF), o None si algo se mueve
+    pub fn bvh(&self) -> Option<&Bvh> {
+        self.acceleration().bvh.as_ref()
+    }
+
+    pub fn add_sdf(&mut self, sdf: SignedDistance, tags: &[&str]) {
+        self.invalidate_bounds();
+        self.sdfs.push(SceneObject::new(sdf, tags));
+    }
+
+    pub fn add_particle(&mut self, particle: Particle, tags: &[&str]) {
+        self.particles.push(SceneObject::new(particle, tags));
+    }
+
+    // Lleva geometría y luces armadas en otro sistema de coordenadas (p. ej. Z arriba) al de la
+    // escena. Las velocidades se convierten como direcciones. Las formas SDF solo trasladan su
+    // centro: un toro conserva su plano XZ
+    pub fn apply_import_transform(&mut self, transform: &ImportTransform) {
+        self.invalidate_bounds();
+        for object in self.objects.iter_mut() {
+            let bounds = transform.apply_aabb(&object.shape.bounds());
+            object.shape.min = bounds.min;
+            object.shape.max = bounds.max;
+            object.velocity = transform.apply(&object.velocity);
+        }
+        for object in self.instances.iter_mut() {
+            object.velocity = transform.apply(&object.velocity);
+            let group = &mut object.shape;
+            for template in group.templates.iter_mut() {
+                let bounds = transform.apply_aabb(&template.bounds());
+                template.min = bounds.min;
+                template.max = bounds.max;
+            }
+            for instance in group.instances.iter_mut() {
+                instance.transform = transform.apply(&instance.transform);
+            }
+        }
+        for object in self.quads.iter_mut() {
+            let quad = &mut object.shape;
+            quad.corner = transform.apply(&quad.corner);
+            quad.u_edge = transform.apply(&quad.u_edge);
+            quad.v_edge = transform.apply(&quad.v_edge);
+            // Con un reflejo la normal (u x v) quedaría hacia adentro: se intercambian las aristas
+            if transform.flips_winding() {
+                std::mem::swap(&mut quad.u_edge, &mut quad.v_edge);
+                quad.uv_repeat = (quad.uv_repeat.1, quad.uv_repeat.0);
+            }
+            object.velocity = transform.apply(&object.velocity);
+        }
+        for object in self.sdfs.iter_mut() {
+            object.shape.center = transform.apply(&object.shape.center);
+            object.velocity = transform.apply(&object.velocity);
+        }
+        for object in self.particles.iter_mut() {
+            object.shape.position = transform.apply(&object.shape.position);
+            object.velocity = transform.apply(&object.velocity);
+        }
+        for light in self.lights.iter_mut() {
+            light.position = transform.apply(&light.position);
+        }
+    }
+
+    pub fn objects(&self) -> &[SceneObject<Cube>] {
+        &self.objects
+    }
+
+    pub fn instances(&self) -> &[SceneObject<InstancedCubes>] {
+        &self.instances
+    }
+
+    pub fn quads(&self) -> &[SceneObject<Quad>] {
+        &self.quads
+    }
+
+    pub fn sdfs(&self) -> &[SceneObject<SignedDistance>] {
+        &self.sdfs
+    }
+
+    // Acceso mutable a la geometría: la caja de sombras se recalcula en el próximo rayo
+    pub fn objects_mut(&mut self) -> &mut [SceneObject<Cube>] {
+        self.invalidate_bounds();
+        &mut self.objects
+    }
+
+    // Material del cubo `index`: el BVH y la caja de sombras solo dependen de la geometría, así
+    // que editarlo no descarta los cachés
+    pub fn material_mut(&mut self, index: usize) -> &mut Material {
+        &mut self.objects[index].shape.material
+    }
+
+    pub fn instances_mut(&mut self) -> &mut [SceneObject<InstancedCubes>] {
+        self.invalidate_bounds();
+        &mut self.instances
+    }
+
+    pub fn quads_mut(&mut self) -> &mut [SceneObject<Quad>] {
+        self.invalidate_bounds();
+        &mut self.quads
+    }
+
+    pub fn sdfs_mut(&mut self) -> &mut [SceneObject<SignedDistance>] {
+        self.invalidate_bounds();
+        &mut self.sdfs
+    }
+
+    fn invalidate_bounds(&mut self) {
+        self.shadow_bounds = OnceLock::new();
+        self.acceleration = OnceLock::new();
+    }
+
+    // Caja de toda la escena para que shadow_blocker descarte sin recorrer los objetos los rayos
+    // que no la tocan (p. ej. del techo hacia el sol). Si algo se mueve (motion blur) la caja fija
+    // no sirve y el atajo queda apagado
+    fn compute_shadow_bounds(&self) -> Option<Aabb> {
+        if self.has_moving_geometry() {
+            None
+        } else {
+            Some(self.stats().bounds())
+        }
+    }
+
+    // true si el rayo no toca la caja de la escena (calculada en el primer rayo después de cada
+    // cambio de geometría): ningún objeto puede bloquearlo
+    pub fn ray_escapes_bounds(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> bool {
+        match self.shadow_bounds.get_or_init(|| self.compute_shadow_bounds()) {
+            Some(bounds) => bounds.intersect_ray(ray_origin, ray_direction).is_none(),
+            None => false,
+        }
+    }
+
+    // Muestra u oculta todos los objetos que tengan la etiqueta
+    pub fn set_layer_visible(&mut self, tag: &str, visible: bool) {
+        for object in self.objects.iter_mut().filter(|o| o.has_tag(tag)) {
+            object.visible = visible;
+        }
+        for group in self.instances.iter_mut().filter(|o| o.has_tag(tag)) {
+            group.visible = visible;
+        }
+        for quad in self.quads.iter_mut().filter(|o| o.has_tag(tag)) {
+            quad.visible = visible;
+        }
+        for sdf in self.sdfs.iter_mut().filter(|o| o.has_tag(tag)) {
+            sdf.visible = visible;
+        }
+        for particle in self.particles.iter_mut().filter(|o| o.has_tag(tag)) {
+            particle.visible = visible;
+        }
+    }
+
+    pub fn is_layer_visible(&self, tag: &str) -> bool {
+        self.objects.iter().filter(|o| o.has_tag(tag)).any(|o| o.visible)
+            || self.instances.iter().filter(|o| o.has_tag(tag)).any(|o| o.visible)
+            || self.quads.iter().filter(|o| o.has_tag(tag)).any(|o| o.visible)
+            || self.sdfs.iter().filter(|o| o.has_tag(tag)).any(|o| o.visible)
+            || self.particles.iter().filter(|o| o.has_tag(tag)).any(|o| o.visible)
+    }
+
+    // Cubos visibles (los que participan en el render)
+    pub fn cubes(&self) -> impl Iterator<Item = &Cube> {
+        self.objects.iter().filter(|o| o.visible).map(|o| &o.shape)
+    }
+
+    // Centro y material de cada objeto visible que emite luz en el instante `time` (cada objeto se
+    // desplaza por su velocidad, como en intersect_at): cubos, instancias, quads y SDF. Los grupos
+    // instanciados sin plantillas emisivas (la base de césped) se saltan sin recorrerlos
+    pub fn emitters(&self, time: f32) -> impl Iterator<Item = (Vec3, &Material)> {
+        let cubes = self.objects.iter().filter(|o| o.visible).map(move |o| (o.shape.position() + o.velocity * time, &o.shape.material));
+        let has_emissive_template = |group: &InstancedCubes| group.templates.iter().any(|t| t.material.emission_color.is_some());
+        let instances = self.instances.iter().filter(move |o| o.visible && has_emissive_template(&o.shape)).flat_map(move |o| {
+            let InstancedCubes { templates, instances } = &o.shape;
+            let offset = o.velocity * time;
+            instances.iter().map(move |instance| {
+                let template = &templates[instance.template_index];
+                (template.position() + instance.transform + offset, &template.material)
+            })
+        });
+        let quads = self.quads.iter().filter(|o| o.visible).map(move |o| (o.shape.bounds().centroid() + o.velocity * time, &o.shape.material));
+        let sdfs = self.sdfs.iter().filter(|o| o.visible).map(move |o| (o.shape.center + o.velocity * time, &o.shape.material));
+        cubes.chain(instances).chain(quads).chain(sdfs).filter(|(_, material)| material.emission_color.is_some())
+    }
+
+    // Índice del cubo visible más cercano que toca el rayo (para seleccionarlo en pantalla)
+    pub fn pick_object(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Option<usize> {
+        let mut picked = None;
+        let mut zbuffer = f32::INFINITY;
+        for (index, object) in self.objects.iter().enumerate().filter(|(_, o)| o.visible) {
+            let i = object.intersect_at(ray_origin, ray_direction, 0.0);
+            if i.is_intersecting && i.distance < zbuffer {
+                zbuffer = i.distance;
+                picked = Some(index);
+            }
+        }
+        picked
+    }
+
+    // Materiales de la escena que no conservan energía, con su nombre (el mismo del .mtl)
+    pub fn validate_materials(&self) -> Vec<(String, MaterialError)> {
+        let mut materials: Vec<&Material> = self.objects.iter().map(|o| &o.shape.material).collect();
+        for group in self.instances.iter().map(|o| &o.shape) {
+            materials.extend(group.templates.iter().map(|t| &t.material));
+        }
+        materials.extend(self.quads.iter().map(|o| &o.shape.material));
+        materials.extend(self.sdfs.iter().map(|o| &o.shape.material));
+
+        let mut errors: Vec<(String, MaterialError)> = Vec::new();
+        for material in materials {
+            if let Err(error) = material.validate() {
+                let name = obj_material_name(material);
+                if !errors.iter().any(|(n, e)| *n == name && *e == error) {
+                    errors.push((name, error));
+                }
+            }
+        }
+        errors
+    }
+
+    pub fn stats(&self) -> SceneStats {
+        let mut object_count = 0;
+        let mut triangle_count = 0;
+        let mut total_surface_area = 0.0;
+        let mut bounds = Aabb::empty();
+
+        for cube in self.objects.iter().map(|o| &o.shape) {
+            object_count += 1;
+            triangle_count += 12;
+            total_surface_area += cube.surface_area();
+            bounds = bounds.union(&cube.bounds());
+        }
+        for group in self.instances.iter().map(|o| &o.shape) {
+            for instance in &group.instances {
+                let template = &group.templates[instance.template_index];
+                object_count += 1;
+                triangle_count += 12;
+                total_surface_area += template.surface_area();
+                bounds = bounds.union(&Aabb::new(template.min + instance.transform, template.max + instance.transform));
+            }
+        }
+        for quad in self.quads.iter().map(|o| &o.shape) {
+            object_count += 1;
+            triangle_count += 2;
+            total_surface_area += quad.u_edge.cross(&quad.v_edge).magnitude();
+            bounds = bounds.union(&quad.bounds());
+        }
+        for sdf in self.sdfs.iter().map(|o| &o.shape) {
+            // Sin malla: cuenta como objeto pero no aporta triángulos; el área es la de su caja
+            object_count += 1;
+            total_surface_area += sdf.bounds().surface_area();
+            bounds = bounds.union(&sdf.bounds());
+        }
+
+        SceneStats {
+            object_count,
+            triangle_count,
+            total_surface_area,
+            bounds_min: bounds.min,
+            bounds_max: bounds.max,
+        }
+    }
+
+    // Exporta la geometría a OBJ (cada cubo como 8 vértices y 12 triángulos, cada quad como 2)
+    // con un .mtl al lado que describe los materiales
+    pub fn export_obj(&self, path: &str) -> io::Result<()> {
+        let obj_path = Path::new(path);
+        let mtl_path = obj_path.with_extension("mtl");
+        let mtl_file_name = mtl_path.file_name().and_then(|n| n.to_str()).unwrap_or("scene.mtl");
+
+        let mut obj = format!("mtllib {}\n", mtl_file_name);
+        let mut mtl = String::new();
+        let mut material_names: Vec<String> = Vec::new();
+        let mut vertex_count = 0;
+
+        // Registra el material la primera vez que aparece y devuelve su nombre
+        let mut use_material = |material: &Material, mtl: &mut String| -> String {
+            let name = obj_material_name(material);
+            if !material_names.contains(&name) {
+                let diffuse = material.diffuse;
+                mtl.push_str(&format!(
+                    "newmtl {}\nKd {:.4} {:.4} {:.4}\n",
+                    name,
+                    diffuse.r() as f32 / 255.0,
+                    diffuse.g() as f32 / 255.0,
+                    diffuse.b() as f32 / 255.0
+                ));
+                if let Some(texture) = &material.texture {
+                    mtl.push_str(&format!("map_Kd {}\n", texture.id));
+                }
+                mtl.push('\n');
+                material_names.push(name.clone());
+            }
+            name
+        };
+
+        let mut boxes: Vec<(Vec3, Vec3, &Material)> = self
+            .objects
+            .iter()
+            .map(|o| (o.shape.min, o.shape.max, &o.shape.material))
+            .collect();
+        for group in self.instances.iter().map(|o| &o.shape) {
+            for instance in &group.instances {
+                let template = &group.templates[instance.template_index];
+                boxes.push((template.min + instance.transform, template.max + instance.transform, &template.material));
+            }
+        }
+
+        for (index, (min, max, material)) in boxes.into_iter().enumerate() {
+            let name = use_material(material, &mut mtl);
+            obj.push_str(&format!("g cube_{}\nusemtl {}\n", index, name));
+            // Vértice i: bit 0 = x, bit 1 = y, bit 2 = z (0 = min, 1 = max)
+            for i in 0..8 {
+                let x = if i & 1 == 0 { min.x } else { max.x };
+                let y = if i & 2 == 0 { min.y } else { max.y };
+                let z = if i & 4 == 0 { min.z } else { max.z };
+                obj.push_str(&format!("v {} {} {}\n", x, y, z));
+            }
+            // Dos triángulos por cara, en sentido antihorario visto desde afuera
+            const CUBE_FACES: [[usize; 4]; 6] = [
+                [0, 4, 6, 2], // -X
+                [1, 3, 7, 5], // +X
+                [0, 1, 5, 4], // -Y
+                [2, 6, 7, 3], // +Y
+                [0, 2, 3, 1], // -Z
+                [4, 5, 7, 6], // +Z
+            ];
+            for [a, b, c, d] in CUBE_FACES {
+                let (a, b, c, d) = (a + vertex_count + 1, b + vertex_count + 1, c + vertex_count + 1, d + vertex_count + 1);
+                obj.push_str(&format!("f {} {} {}\nf {} {} {}\n", a, b, c, a, c, d));
+            }
+            vertex_count += 8;
+        }
+
+        for (index, quad) in self.quads.iter().map(|o| &o.shape).enumerate() {
+            let name = use_material(&quad.material, &mut mtl);
+            obj.push_str(&format!("g quad_{}\nusemtl {}\n", index, name));
+            for corner in [
+                quad.corner,
+                quad.corner + quad.u_edge,
+                quad.corner + quad.u_edge + quad.v_edge,
+                quad.corner + quad.v_edge,
+            ] {
+                obj.push_str(&format!("v {} {} {}\n", corner.x, corner.y, corner.z));
+            }
+            let base = vertex_count + 1;
+            obj.push_str(&format!("f {} {} {}\nf {} {} {}\n", base, base + 1, base + 2, base, base + 2, base + 3));
+            vertex_count += 4;
+        }
+
+        fs::write(obj_path, obj)?;
+        fs::write(mtl_path, mtl)
+    }
+
+    fn intersect_primitive(&self, primitive: Primitive, ray_origin: &Vec3, ray_direction: &Vec3, time: f32, t_min: f32) -> Intersect<'_> {
+        match primitive {
+            Primitive::Cube(index) if self.objects[index].visible => {
+                self.objects[index].intersect_from(ray_origin, ray_direction, time, t_min)
+            }
+            Primitive::Instance(group_index, index) if self.instances[group_index].visible => {
+                let group = &self.instances[group_index];
+                let offset = group.velocity * time;
+                let local_origin = ray_origin - offset;
+                let instance = &group.shape.instances[index];
+                let mut i = instance.ray_intersect_from(&group.shape.templates, &local_origin, ray_direction, t_min);
+                if i.is_intersecting {
+                    i.point += offset;
+                }
+                i
+            }
+            Primitive::Quad(index) if self.quads[index].visible => {
+                self.quads[index].intersect_from(ray_origin, ray_direction, time, t_min)
+            }
+            Primitive::Sdf(index) if self.sdfs[index].visible => {
+                self.sdfs[index].intersect_from(ray_origin, ray_direction, time, t_min)
+            }
+            _ => Intersect::empty(),
+        }
+    }
+
+    // Recorre los impactos con los objetos visibles (cada instancia por separado) que el BVH no
+    // descarta, con el índice de la primitiva en el orden lineal. `visit` puede acortar el límite
+    // (al principio t_max) para que el BVH salte los nodos más lejanos; devuelve false para detenerse
+    fn visit_hits<'a, F>(&'a self, ray_origin: &Vec3, ray_direction: &Vec3, time: f32, t_min: f32, t_max: f32, mut visit: F)
+    where
+        F: FnMut(usize, Intersect<'a>, &mut f32) -> bool,
+    {
+        let acceleration = self.acceleration();
+        let mut visit_primitive = |index: usize, limit: &mut f32| {
+            let i = self.intersect_primitive(acceleration.primitives[index], ray_origin, ray_direction, time, t_min);
+            !i.is_intersecting || visit(index, i, limit)
+        };
+        match &acceleration.bvh {
+            Some(bvh) => bvh.visit(ray_origin, ray_direction, t_min, t_max, visit_primitive),
+            None => {
+                let mut limit = t_max;
+                for index in 0..acceleration.primitives.len() {
+                    if !visit_primitive(index, &mut limit) {
+                        return;
+                    }
+                }
+            }
+        }
+    }
+
+    // Primer objeto visible que bloquea el rayo antes de max_distance
+    pub fn shadow_blocker(&self, ray_origin: &Vec3, ray_direction: &Vec3, max_distance: f32, time: f32) -> Option<Intersect<'_>> {
+        if self.ray_escapes_bounds(ray_origin, ray_direction) {
+            return None;
+        }
+        let mut blocker = None;
+        self.visit_hits(ray_origin, ray_direction, time, 0.0, max_distance, |_, i, _| {
+            if i.distance < max_distance {
+                blocker = Some(i);
+                return false;
+            }
+            true
+        });
+        blocker
+    }
+
+    // Bloqueador más cercano al origen antes de max_distance (shadow_blocker devuelve el primero
+    // que encuentra el BVH, que puede ser uno más lejano)
+    pub fn nearest_shadow_blocker(&self, ray_origin: &Vec3, ray_direction: &Vec3, max_distance: f32, time: f32) -> Option<Intersect<'_>> {
+        if self.ray_escapes_bounds(ray_origin, ray_direction) {
+            return None;
+        }
+        let intersect = self.intersect_clipped(ray_origin, ray_direction, time, 0.0, max_distance);
+        intersect.is_intersecting.then_some(intersect)
+    }
+
+    // Todos los impactos a lo largo del rayo ordenados de cerca a lejos (un impacto por objeto:
+    // la cara por la que entra), para componer superficies transparentes superpuestas
+    pub fn intersect_all(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Vec<Intersect<'_>> {
+        self.intersect_all_at(ray_origin, ray_direction, 0.0)
+    }
+
+    pub fn intersect_all_at(&self, ray_origin: &Vec3, ray_direction: &Vec3, time: f32) -> Vec<Intersect<'_>> {
+        let mut hits = Vec::new();
+        self.visit_hits(ray_origin, ray_direction, time, 0.0, f32::INFINITY, |index, i, _| {
+            hits.push((index, i));
+            true
+        });
+        // A igual distancia, el orden lineal (el BVH visita en otro orden)
+        hits.sort_by(|(a_index, a), (b_index, b)| a.distance.total_cmp(&b.distance).then(a_index.cmp(b_index)));
+        hits.into_iter().map(|(_, i)| i).collect()
+    }
+
+    // Pone las partículas visibles sobre el color de la superficie que está a surface_distance:
+    // la partícula opaca más cercana la tapa y las aditivas delante de lo visible suman su color
+    pub fn composite_particles(
+        &self,
+        ray_origin: &Vec3,
+        ray_direction: &Vec3,
+        time: f32,
+        surface_distance: f32,
+        surface_radiance: Vec3,
+    ) -> Vec3 {
+        let mut color = surface_radiance;
+        let mut limit = surface_distance;
+        for particle in self.particles.iter().filter(|o| o.visible && !o.shape.additive) {
+            let i = particle.intersect_at(ray_origin, ray_direction, time);
+            if i.is_intersecting && i.distance < limit {
+                limit = i.distance;
+                color = particle.shape.color().to_radiance();
+            }
+        }
+        for particle in self.particles.iter().filter(|o| o.visible && o.shape.additive) {
+            let i = particle.intersect_at(ray_origin, ray_direction, time);
+            if i.is_intersecting && i.distance < limit {
+                color += particle.shape.color().to_radiance();
+            }
+        }
+        color
+    }
+
+    // Intersección más cercana entre todos los objetos visibles en el instante `time`
+    pub fn intersect_at(&self, ray_origin: &Vec3, ray_direction: &Vec3, time: f32) -> Intersect<'_> {
+        self.intersect_clipped(ray_origin, ray_direction, time, 0.0, f32::INFINITY)
+    }
+
+    // Igual que intersect_at, pero solo acepta impactos con distancia dentro de [near, far). near
+    // llega a cada objeto como inicio del rayo: un cubo cortado por el plano cercano muestra su cara
+    // de salida. A igual profundidad y orientación gana la primitiva que va antes en el orden lineal
+    pub fn intersect_clipped(&self, ray_origin: &Vec3, ray_direction: &Vec3, time: f32, near: f32, far: f32) -> Intersect<'_> {
+        let mut intersect = Intersect::empty();
+        let mut intersect_index = usize::MAX;
+
+        self.visit_hits(ray_origin, ray_direction, time, near, far, |index, i, limit| {
+            if i.distance >= far {
+                return true;
+            }
+            let nearer = is_nearer_hit(&i, &intersect, ray_direction)
+                || (!is_nearer_hit(&intersect, &i, ray_direction) && index < intersect_index);
+            if nearer {
+                intersect = i;
+                intersect_index = index;
+                // Un nodo que empieza dentro del margen de empate todavía puede desempatar
+                *limit = limit.min(intersect.distance + DEPTH_TIE_EPSILON);
+            }
+            true
+        });
+
+        intersect
+    }
+}
+
+// Prueba de profundidad con desempate estable: si dos caras coinciden (dentro de DEPTH_TIE_EPSILON)
+// gana la que mira más de frente al rayo; con la misma orientación ninguna es más cercana.
+// Así el resultado no depende del redondeo de cada cuadro y no aparecen costuras que parpadean
+fn is_nearer_hit(candidate: &Intersect, current: &Intersect, ray_direction: &Vec3) -> bool {
+    if !current.is_intersecting {
+        return true;
+    }
+    if (candidate.distance - current.distance).abs() > DEPTH_TIE_EPSILON {
+        return candidate.distance < current.distance;
+    }
+    candidate.normal.dot(ray_direction) < current.normal.dot(ray_direction)
+}
+
+// Nombre del material en el .mtl: el archivo de su textura o su color difuso
+fn obj_material_name(material: &Material) -> String {
+    let base = match &material.texture {
+        Some(texture) => Path::new(&texture.id)
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or("textura")
+            .to_string(),
+        None => format!("color_{:06x}", material.diffuse.to_hex()),
+    };
+    if material.emission_color.is_some() {
+        format!("{}_emisivo", base)
+    } else {
+        base
+    }
+}
+
+impl RayIntersect for Scene {
+    fn ray_intersect(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Intersect<'_> {
+        self.intersect_at(ray_origin, ray_direction, 0.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+    use crate::material::Material;
+
+    fn block(min: Vec3, max: Vec3) -> Cube {
+        let material = Material::new(Color::new(200, 200, 200), 10.0, [0.9, 0.1, 0.0, 0.0], 1.0, None, 0.0);
+        Cube { min, max, material, inverted: false }
+    }
+
+    #[test]
+    fn invisible_cube_is_not_hit_while_a_visible_one_is() {
+        let mut scene = Scene::new();
+        scene.add_cube(block(Vec3::new(-1.0, -1.0, -1.0), Vec3::new(1.0, 1.0, 1.0)), &["paredes"]);
+        scene.add_cube(block(Vec3::new(4.0, -1.0, -1.0), Vec3::new(6.0, 1.0, 1.0)), &["muebles"]);
+        let down = Vec3::new(0.0, 0.0, -1.0);
+        let wall_ray = Vec3::new(0.0, 0.0, 5.0);
+        let furniture_ray = Vec3::new(5.0, 0.0, 5.0);
+
+        scene.set_layer_visible("paredes", false);
+        assert!(!scene.is_layer_visible("paredes"));
+        assert!(!scene.intersect_at(&wall_ray, &down, 0.0).is_intersecting);
+        assert!(scene.shadow_blocker(&wall_ray, &down, f32::INFINITY, 0.0).is_none());
+        assert!(scene.intersect_at(&furniture_ray, &down, 0.0).is_intersecting);
+
+        scene.set_layer_visible("paredes", true);
+        assert!(scene.intersect_at(&wall_ray, &down, 0.0).is_intersecting);
+    }
+
+    #[test]
+    fn hemisphere_ambient_uses_the_sky_above_and_the_ground_below() {
+        let mut scene = Scene::new();
+        scene.ambient_intensity = 1.0;
+        scene.ambient_color = Color::new(100, 150, 250);
+        scene.ambient_ground_color = Color::new(80, 60, 40);
+        let up = Vec3::new(0.0, 1.0, 0.0);
+        let down = Vec3::new(0.0, -1.0, 0.0);
+
+        // Ambiente plano: igual en todas las direcciones
+        assert_eq!(scene.ambient_light(&up).to_hex(), scene.ambient_light(&down).to_hex());
+
+        scene.hemisphere_ambient = true;
+        assert_eq!(scene.ambient_light(&up).to_hex(), scene.ambient_color.to_hex());
+        assert_eq!(scene.ambient_light(&down).to_hex(), scene.ambient_ground_color.to_hex());
+        let side = scene.ambient_light(&Vec3::new(1.0, 0.0, 0.0));
+        assert_eq!((side.r(), side.g(), side.b()), (90, 105, 145));
+    }
+
+    #[test]
+    fn intersect_all_returns_stacked_cubes_near_to_far() {
+        let mut scene = Scene::new();
+        // El lejano se agrega primero para que el orden no salga de la lista
+        scene.add_cube(block(Vec3::new(-1.0, -1.0, -6.0), Vec3::new(1.0, 1.0, -4.0)), &[]);
+        scene.add_cube(block(Vec3::new(-1.0, -1.0, -1.0), Vec3::new(1.0, 1.0, 1.0)), &[]);
+
+        let hits = scene.intersect_all(&Vec3::new(0.0, 0.0, 5.0), &Vec3::new(0.0, 0.0, -1.0));
+        let distances: Vec<f32> = hits.iter().map(|hit| hit.distance).collect();
+        assert_eq!(distances, vec![4.0, 9.0]);
+        assert!(scene.intersect_all(&Vec3::new(0.0, 5.0, 5.0), &Vec3::new(0.0, 0.0, -1.0)).is_empty());
+    }
+
+    #[test]
+    fn bvh_queries_match_a_scan_of_every_object() {
+        use rand::rngs::StdRng;
+        use rand::{Rng, SeedableRng};
+
+        let mut rng = StdRng::seed_from_u64(164);
+        let mut scene = Scene::new();
+        let mut group = InstancedCubes::new();
+        let template = group.add_template(block(Vec3::zeros(), Vec3::repeat(1.0)));
+        for _ in 0..60 {
+            let min = Vec3::new(rng.gen_range(-10.0..10.0), rng.gen_range(-10.0..10.0), rng.gen_range(-10.0..10.0));
+            scene.add_cube(block(min, min + Vec3::new(rng.gen_range(0.2..2.0), rng.gen_range(0.2..2.0), rng.gen_range(0.2..2.0))), &[]);
+            group.add_instance(template, Vec3::new(rng.gen_range(-10.0..10.0), rng.gen_range(-10.0..10.0), rng.gen_range(-10.0..10.0)));
+        }
+        scene.add_instances(group, &[]);
+        scene.add_quad(
+            Quad {
+                corner: Vec3::new(-10.0, -11.0, -10.0),
+                u_edge: Vec3::new(0.0, 0.0, 20.0),
+                v_edge: Vec3::new(20.0, 0.0, 0.0),
+                material: block(Vec3::zeros(), Vec3::zeros()).material,
+                uv_repeat: (1.0, 1.0),
+            },
+            &[],
+        );
+        assert!(scene.bvh().is_some());
+
+        for _ in 0..500 {
+            let origin = Vec3::new(rng.gen_range(-15.0..15.0), rng.gen_range(-15.0..15.0), rng.gen_range(-15.0..15.0));
+            let direction = Vec3::new(rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0));
+            let mut nearest = f32::INFINITY;
+            for object in &scene.objects {
+                let i = object.intersect_at(&origin, &direction, 0.0);
+                if i.is_intersecting {
+                    nearest = nearest.min(i.distance);
+                }
+            }
+            for group in &scene.instances {
+                for instance in &group.shape.instances {
+                    let i = instance.ray_intersect(&group.shape.templates, &origin, &direction);
+                    if i.is_intersecting {
+                        nearest = nearest.min(i.distance);
+                    }
+                }
+            }
+            let i = scene.quads[0].intersect_at(&origin, &direction, 0.0);
+            if i.is_intersecting {
+                nearest = nearest.min(i.distance);
+            }
+
+            let hit = scene.intersect_at(&origin, &direction, 0.0);
+            if nearest.is_finite() {
+                assert!(hit.is_intersecting && (hit.distance - nearest).abs() < 1e-4);
+            } else {
+                assert!(!hit.is_intersecting);
+            }
+        }
+
+        // Un objeto en movimiento apaga el BVH; el recorrido lineal da los mismos impactos
+        scene.objects_mut()[0].velocity = Vec3::new(0.0, 1.0, 0.0);
+        assert!(scene.bvh().is_none());
+    }
+
+    #[test]
+    fn intersections_borrow_the_scene_material_instead_of_cloning_it() {
+        use crate::texture::{ColorSpace, Texture};
+
+        let texture = Arc::new(Texture::missing("shared", ColorSpace::Srgb));
+        let material = Material::new_with_texture(0.2, [0.9, 0.05, 0.0, 0.0], 1.0, texture.clone(), None, 0.0);
+        let mut scene = Scene::new();
+        scene.add_cube(Cube { min: Vec3::new(-1.0, -1.0, -1.0), max: Vec3::new(1.0, 1.0, 1.0), material, inverted: false }, &[]);
+        let references_before = Arc::strong_count(&texture);
+
+        // Muchos impactos vivos a la vez: un clon del material sumaría una referencia a la textura por cada uno
+        let hits: Vec<Intersect> = (0..10_000)
+            .map(|i| {
+                let x = (i % 100) as f32 / 100.0 - 0.5;
+                scene.intersect_at(&Vec3::new(x, 0.0, 5.0), &Vec3::new(0.0, 0.0, -1.0), 0.0)
+            })
+            .collect();
+        assert!(hits.iter().all(|hit| hit.is_intersecting && std::ptr::eq(hit.material, &scene.objects[0].shape.material)));
+        assert_eq!(Arc::strong_count(&texture), references_before);
+    }
+
+    #[test]
+    fn coincident_faces_resolve_to_the_same_cube_for_every_ray() {
+        let big = || block(Vec3::new(-1.0, -1.0, -1.0), Vec3::new(1.0, 1.0, 1.0));
+        let small = || block(Vec3::new(-0.5, -0.5, -2.0), Vec3::new(0.5, 0.5, 1.0));
+        // Las caras frontales de ambos cubos están en z = 1
+        for big_first in [true, false] {
+            let mut scene = Scene::new();
+            if big_first {
+                scene.add_cube(big(), &[]);
+                scene.add_cube(small(), &[]);
+            } else {
+                scene.add_cube(small(), &[]);
+                scene.add_cube(big(), &[]);
+            }
+            let first = &scene.objects[0].shape.material;
+            for i in 0..200 {
+                let origin = Vec3::new((i % 20) as f32 * 0.04 - 0.4, (i / 20) as f32 * 0.08 - 0.4, 5.0);
+                let hit = scene.intersect_at(&origin, &Vec3::new(0.0, 0.0, -1.0), 0.0);
+                assert!(hit.is_intersecting);
+                assert!(std::ptr::eq(hit.material, first), "ray {} picked the later cube", i);
+            }
+        }
+    }
+
+    #[test]
+    fn top_surface_point_escapes_the_scene_bounds_toward_an_overhead_sun() {
+        let mut scene = Scene::new();
+        for i in 0..10 {
+            let x = i as f32 * 2.0;
+            scene.add_cube(block(Vec3::new(x, 0.0, 0.0), Vec3::new(x + 1.0, 1.0, 1.0)), &[]);
+        }
+        let up = Vec3::new(0.0, 1.0, 0.0);
+        assert!(scene.ray_escapes_bounds(&Vec3::new(4.5, 1.001, 0.5), &up));
+        assert!(scene.shadow_blocker(&Vec3::new(4.5, 1.001, 0.5), &up, f32::INFINITY, 0.0).is_none());
+        // Un rayo que cruza la escena sí recorre los objetos
+        assert!(!scene.ray_escapes_bounds(&Vec3::new(-1.0, 0.5, 0.5), &Vec3::new(1.0, 0.0, 0.0)));
+    }
+
+    #[test]
+    fn mutating_geometry_refreshes_the_shadow_bounds() {
+        let mut scene = Scene::new();
+        scene.add_cube(block(Vec3::new(0.0, 0.0, 0.0), Vec3::new(1.0, 1.0, 1.0)), &[]);
+        let point = Vec3::new(0.5, 1.001, 0.5);
+        let up = Vec3::new(0.0, 1.0, 0.0);
+        assert!(scene.shadow_blocker(&point, &up, f32::INFINITY, 0.0).is_none());
+
+        // Un techo agregado después de calcular la caja
+        scene.objects_mut()[0].shape = block(Vec3::new(0.0, 3.0, 0.0), Vec3::new(1.0, 4.0, 1.0));
+        assert!(scene.shadow_blocker(&point, &up, f32::INFINITY, 0.0).is_some());
+
+        // Con movimiento la caja fija no vale: el atajo se apaga
+        scene.objects_mut()[0].velocity = Vec3::new(0.0, 1.0, 0.0);
+        assert!(!scene.ray_escapes_bounds(&Vec3::new(0.5, 10.0, 0.5), &up));
+    }
+
+    #[test]
+    fn import_transform_keeps_quad_facing_and_converts_velocity_as_a_direction() {
+        use crate::import::{Handedness, UpAxis};
+
+        let mut scene = Scene::new();
+        // Piso en un archivo Z arriba, mano izquierda: u x v apunta hacia arriba (+Z)
+        let material = Material::new(Color::new(200, 200, 200), 10.0, [0.9, 0.1, 0.0, 0.0], 1.0, None, 0.0);
+        scene.add_quad(
+            Quad { corner: Vec3::new(0.0, 0.0, 0.0), u_edge: Vec3::new(2.0, 0.0, 0.0), v_edge: Vec3::new(0.0, 1.0, 0.0), material, uv_repeat: (4.0, 1.0) },
+            &[],
+        );
+        scene.add_cube(block(Vec3::new(0.0, 0.0, 0.0), Vec3::new(1.0, 1.0, 1.0)), &[]);
+        scene.objects_mut()[0].velocity = Vec3::new(0.0, 0.0, 2.0);
+        scene.quads_mut()[0].velocity = Vec3::new(0.0, 0.0, 2.0);
+        scene.apply_import_transform(&ImportTransform::new(UpAxis::Z, Handedness::Left));
+
+        let quad = &scene.quads()[0];
+        let normal = quad.shape.u_edge.cross(&quad.shape.v_edge).normalize();
+        assert!((normal - Vec3::new(0.0, 1.0, 0.0)).magnitude() < 1e-6, "{:?}", normal);
+        assert_eq!(quad.shape.uv_repeat, (1.0, 4.0));
+        assert_eq!(quad.velocity, Vec3::new(0.0, 2.0, 0.0));
+        assert_eq!(scene.objects()[0].velocity, Vec3::new(0.0, 2.0, 0.0));
+    }
+
+    #[test]
+    fn near_plane_inside_a_cube_shows_its_far_face_instead_of_what_is_behind() {
+        let mut scene = Scene::new();
+        scene.add_cube(block(Vec3::new(-1.0, -1.0, -1.0), Vec3::new(1.0, 1.0, 1.0)), &[]);
+        scene.add_cube(block(Vec3::new(-1.0, -1.0, -6.0), Vec3::new(1.0, 1.0, -5.0)), &[]);
+        let origin = Vec3::new(0.0, 0.0, 5.0);
+        let dir = Vec3::new(0.0, 0.0, -1.0);
+
+        assert_eq!(scene.intersect_clipped(&origin, &dir, 0.0, 0.0, f32::INFINITY).distance, 4.0);
+        assert_eq!(scene.intersect_clipped(&origin, &dir, 0.0, 5.0, f32::INFINITY).distance, 6.0);
+        assert_eq!(scene.intersect_clipped(&origin, &dir, 0.0, 7.0, f32::INFINITY).distance, 10.0);
+        assert!(!scene.intersect_clipped(&origin, &dir, 0.0, 5.0, 5.5).is_intersecting);
+    }
+
+    #[test]
+    fn exporting_a_single_cube_writes_eight_vertices_and_twelve_faces() {
+        let mut scene = Scene::new();
+        scene.add_cube(block(Vec3::new(-1.0, -1.0, -1.0), Vec3::new(1.0, 1.0, 1.0)), &[]);
+        let path = std::env::temp_dir().join(format!("raytracing_export_{}.obj", std::process::id()));
+        scene.export_obj(path.to_str().unwrap()).unwrap();
+
+        let obj = std::fs::read_to_string(&path).unwrap();
+        let mtl = std::fs::read_to_string(path.with_extension("mtl")).unwrap();
+        std::fs::remove_file(&path).ok();
+        std::fs::remove_file(path.with_extension("mtl")).ok();
+
+        assert_eq!(obj.lines().filter(|l| l.starts_with("v ")).count(), 8);
+        assert_eq!(obj.lines().filter(|l| l.starts_with("f ")).count(), 12);
+        assert_eq!(obj.lines().filter(|l| l.starts_with("g ")).count(), 1);
+        let material = obj.lines().find_map(|l| l.strip_prefix("usemtl ")).expect("the cube references its material");
+        assert!(mtl.contains(&format!("newmtl {}", material)));
+    }
+
+    #[test]
+    fn editing_a_material_keeps_the_built_bvh() {
+        let mut scene = Scene::new();
+        for i in 0..4 {
+            let x = i as f32 * 2.0;
+            scene.add_cube(block(Vec3::new(x, 0.0, 0.0), Vec3::new(x + 1.0, 1.0, 1.0)), &[]);
+        }
+        let before = scene.bvh().map(|bvh| bvh as *const Bvh);
+        assert!(before.is_some());
+        scene.material_mut(2).diffuse = Color::new(10, 20, 30);
+        assert_eq!(scene.bvh().map(|bvh| bvh as *const Bvh), before);
+        assert_eq!(scene.objects()[2].shape.material.diffuse.to_hex(), 0x0A141E);
+    }
+
+    #[test]
+    fn emitters_cover_every_visible_emissive_object() {
+        let lamp = Material::new_emissive(Color::new(255, 255, 255), 2.0);
+        let mut scene = Scene::new();
+        scene.add_cube(block(Vec3::zeros(), Vec3::repeat(1.0)), &[]);
+        scene.add_cube(Cube { material: lamp.clone(), ..block(Vec3::repeat(2.0), Vec3::repeat(4.0)) }, &[]);
+        let mut grass = InstancedCubes::new();
+        let plain = grass.add_template(block(Vec3::zeros(), Vec3::repeat(1.0)));
+        grass.add_instance(plain, Vec3::new(10.0, 0.0, 0.0));
+        scene.add_instances(grass, &[]);
+        let mut lanterns = InstancedCubes::new();
+        let lantern = lanterns.add_template(Cube { material: lamp.clone(), ..block(Vec3::zeros(), Vec3::repeat(2.0)) });
+        lanterns.add_instance(lantern, Vec3::new(0.0, 5.0, 0.0));
+        lanterns.add_instance(lantern, Vec3::new(0.0, 9.0, 0.0));
+        scene.add_instances(lanterns, &[]);
+        scene.add_quad(
+            Quad { corner: Vec3::new(-4.0, 0.0, 0.0), u_edge: Vec3::new(2.0, 0.0, 0.0), v_edge: Vec3::new(0.0, 0.0, 2.0), material: lamp.clone(), uv_repeat: (1.0, 1.0) },
+            &[],
+        );
+        scene.add_sdf(SignedDistance::new(Vec3::new(0.0, 0.0, -6.0), crate::sdf::SdfShape::Sphere { radius: 1.0 }, lamp.clone()), &["hidden"]);
+        scene.add_sdf(SignedDistance::new(Vec3::new(0.0, 0.0, 6.0), crate::sdf::SdfShape::Sphere { radius: 1.0 }, lamp), &[]);
+        scene.set_layer_visible("hidden", false);
+
+        let centers = |scene: &Scene, time: f32| scene.emitters(time).map(|(center, _)| center).collect::<Vec<Vec3>>();
+        let expected = vec![
+            Vec3::repeat(3.0),
+            Vec3::new(1.0, 6.0, 1.0),
+            Vec3::new(1.0, 10.0, 1.0),
+            Vec3::new(-3.0, 0.0, 1.0),
+            Vec3::new(0.0, 0.0, 6.0),
+        ];
+        assert_eq!(centers(&scene, 0.0), expected);
+
+        // Con motion blur cada emisor sale de donde está su objeto en ese instante
+        scene.objects_mut()[1].velocity = Vec3::new(2.0, 0.0, 0.0);
+        scene.instances_mut()[1].velocity = Vec3::new(0.0, 0.0, 2.0);
+        scene.quads_mut()[0].velocity = Vec3::new(0.0, 2.0, 0.0);
+        scene.sdfs_mut()[1].velocity = Vec3::new(0.0, -2.0, 0.0);
+        let shifts = [Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 1.0), Vec3::new(0.0, 0.0, 1.0), Vec3::new(0.0, 1.0, 0.0), Vec3::new(0.0, -1.0, 0.0)];
+        let moved: Vec<Vec3> = expected.iter().zip(shifts).map(|(center, shift)| center + shift).collect();
+        assert_eq!(centers(&scene, 0.5), moved);
+    }
+}
@@ -0,0 +1,172 @@
+use nalgebra_glm::Vec3;
+use std::f32::consts::PI;
+use crate::aabb::Aabb;
+use crate::material::Material;
+use crate::ray_intersect::{RayIntersect, Intersect};
+
+const MAX_STEPS: usize = 128;        // Pasos de sphere tracing antes de rendirse
+const HIT_EPSILON: f32 = 1e-4;       // Distancia a la superficie que ya cuenta como impacto
+const START_DISTANCE: f32 = 1e-3;    // El rayo arranca aquí para no volver a tocar la superficie de la que sale
+const NORMAL_EPSILON: f32 = 1e-3;    // Paso de las diferencias centrales para el gradiente
+const MAX_TRACE_DISTANCE: f32 = 1000.0;
+
+// Formas definidas por función de distancia, centradas en el origen local
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SdfShape {
+    Sphere { radius: f32 },
+    RoundedBox { half_extents: Vec3, radius: f32 }, // Caja de semiejes half_extents con esquinas redondeadas
+    Torus { major_radius: f32, minor_radius: f32 }, // Anillo en el plano XZ
+}
+
+impl SdfShape {
+    pub fn distance(&self, p: &Vec3) -> f32 {
+        match *self {
+            SdfShape::Sphere { radius } => p.magnitude() - radius,
+            SdfShape::RoundedBox { half_extents, radius } => {
+                let q = Vec3::new(p.x.abs(), p.y.abs(), p.z.abs()) - half_extents + Vec3::new(radius, radius, radius);
+                let outside = Vec3::new(q.x.max(0.0), q.y.max(0.0), q.z.max(0.0)).magnitude();
+                let inside = q.x.max(q.y).max(q.z).min(0.0);
+                outside + inside - radius
+            }
+            SdfShape::Torus { major_radius, minor_radius } => {
+                let ring = (p.x * p.x + p.z * p.z).sqrt() - major_radius;
+                (ring * ring + p.y * p.y).sqrt() - minor_radius
+            }
+        }
+    }
+
+    // Semiejes de una caja que envuelve la forma
+    fn half_size(&self) -> Vec3 {
+        match *self {
+            SdfShape::Sphere { radius } => Vec3::new(radius, radius, radius),
+            SdfShape::RoundedBox { half_extents, .. } => half_extents,
+            SdfShape::Torus { major_radius, minor_radius } => {
+                let r = major_radius + minor_radius;
+                Vec3::new(r, minor_radius, r)
+            }
+        }
+    }
+}
+
+// Objeto trazado por sphere tracing: convive con cubos y quads en la escena
+pub struct SignedDistance {
+    pub center: Vec3,
+    pub shape: SdfShape,
+    pub material: Material,
+}
+
+impl SignedDistance {
+    pub fn new(center: Vec3, shape: SdfShape, material: Material) -> Self {
+        SignedDistance { center, shape, material }
+    }
+
+    pub fn distance(&self, point: &Vec3) -> f32 {
+        self.shape.distance(&(point - self.center))
+    }
+
+    // Normal como gradiente de la distancia (diferencias centrales)
+    pub fn normal(&self, point: &Vec3) -> Vec3 {
+        let dx = Vec3::new(NORMAL_EPSILON, 0.0, 0.0);
+        let dy = Vec3::new(0.0, NORMAL_EPSILON, 0.0);
+        let dz = Vec3::new(0.0, 0.0, NORMAL_EPSILON);
+        Vec3::new(
+            self.distance(&(point + dx)) - self.distance(&(point - dx)),
+            self.distance(&(point + dy)) - self.distance(&(point - dy)),
+            self.distance(&(point + dz)) - self.distance(&(point - dz)),
+        )
+        .normalize()
+    }
+
+    pub fn bounds(&self) -> Aabb {
+        let half = self.shape.half_size();
+        Aabb::new(self.center - half, self.center + half)
+    }
+}
+
+impl RayIntersect for SignedDistance {
+    fn ray_intersect(&self, ray_origin: &Vec3, ray_dir: &Vec3) -> Intersect<'_> {
+        // Descarta rápido los rayos que ni tocan la caja envolvente
+        if self.bounds().intersect_ray(ray_origin, ray_dir).is_none() {
+            return Intersect::empty();
+        }
+
+        let direction = ray_dir.normalize();
+        let scale = ray_dir.magnitude(); // Las distancias del Intersect van en unidades de ray_dir
+        let mut t = START_DISTANCE;
+        // Con el origen dentro de la forma la distancia es negativa: se avanza |distancia| (lo que
+        // falta hasta la superficie) hasta que cambia de signo, en la cara de salida
+        let inside = self.distance(&(ray_origin + direction * t)) < 0.0;
+        for _ in 0..MAX_STEPS {
+            let point = ray_origin + direction * t;
+            let distance = self.distance(&point);
+            let reached = if inside { distance >= 0.0 } else { distance < HIT_EPSILON };
+            if reached {
+                let normal = self.normal(&point);
+                // UV de la proyección del material; por defecto, esférica alrededor del centro
+                let bounds = self.bounds();
+                let (u, v) = self.material.uv_projection.project(&point, &self.center, &bounds.size()).unwrap_or_else(|| {
+                    let local = (point - self.center).normalize();
+                    (0.5 + local.z.atan2(local.x) / (2.0 * PI), 0.5 - local.y.clamp(-1.0, 1.0).asin() / PI)
+                });
+                return Intersect::new(point, normal, t / scale, &self.material, u, v);
+            }
+            t += distance.abs().max(HIT_EPSILON);
+            if t > MAX_TRACE_DISTANCE {
+                break;
+            }
+        }
+        Intersect::empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+    use crate::material::UvProjection;
+
+    #[test]
+    fn sphere_tracing_a_unit_sphere_hits_at_the_expected_distance() {
+        let sphere = SignedDistance::new(Vec3::new(0.0, 0.0, -3.0), SdfShape::Sphere { radius: 1.0 }, Material::black());
+
+        let hit = sphere.ray_intersect(&Vec3::new(0.0, 0.0, 2.0), &Vec3::new(0.0, 0.0, -1.0));
+        assert!(hit.is_intersecting);
+        assert!((hit.distance - 4.0).abs() < 1e-3, "{}", hit.distance);
+        assert!((hit.normal - Vec3::new(0.0, 0.0, 1.0)).magnitude() < 1e-2);
+
+        // Sobre el eje Y, desde abajo
+        let hit = sphere.ray_intersect(&Vec3::new(0.0, -10.0, -3.0), &Vec3::new(0.0, 1.0, 0.0));
+        assert!((hit.distance - 9.0).abs() < 1e-3);
+        assert!(!sphere.ray_intersect(&Vec3::new(2.0, 0.0, 2.0), &Vec3::new(0.0, 0.0, -1.0)).is_intersecting);
+    }
+
+    #[test]
+    fn sdf_uses_the_material_projection() {
+        let mut material = Material::new(Color::new(200, 200, 200), 10.0, [1.0, 0.0, 0.0, 0.0], 1.0, None, 0.0);
+        material.uv_projection = UvProjection::Cylindrical;
+        let sphere = SignedDistance::new(Vec3::zeros(), SdfShape::Sphere { radius: 1.0 }, material);
+
+        // Envoltura cilíndrica: v sigue la altura dentro de la caja (de -1 a 1)
+        let origin = Vec3::new(5.0, 0.5, 0.0);
+        let hit = sphere.ray_intersect(&origin, &Vec3::new(-1.0, 0.0, 0.0));
+        assert!(hit.is_intersecting);
+        assert!((hit.v - 0.75).abs() < 1e-3);
+        assert!((hit.u - 0.5).abs() < 1e-3);
+    }
+
+    #[test]
+    fn ray_from_inside_the_sphere_hits_the_far_side() {
+        let sphere = SignedDistance::new(Vec3::zeros(), SdfShape::Sphere { radius: 1.0 }, Material::black());
+        let direction = Vec3::new(0.0, 0.0, -1.0);
+
+        let hit = sphere.ray_intersect(&Vec3::new(0.0, 0.0, 0.5), &direction);
+        assert!(hit.is_intersecting);
+        assert!((hit.distance - 1.5).abs() < 1e-3, "{}", hit.distance);
+        assert!((hit.point.z + 1.0).abs() < 1e-3);
+        assert!((hit.normal - Vec3::new(0.0, 0.0, -1.0)).magnitude() < 1e-2);
+
+        // Recién entrado por la cara +Z (como un rayo refractado): sale por la cara opuesta
+        let hit = sphere.ray_intersect(&Vec3::new(0.0, 0.0, 0.9999), &direction);
+        assert!((hit.distance - 1.9999).abs() < 1e-3, "{}", hit.distance);
+    }
+}
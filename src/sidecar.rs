@@ -0,0 +1,261 @@
+use nalgebra_glm::Vec3;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::camera::{Camera, CameraState};
+use crate::filter::PixelFilter;
+use crate::json::{json_f32, json_vec3, parse_array, parse_f32, parse_optional, parse_vec3, split_top_level};
+use crate::passes::NormalSpace;
+use crate::tonemap::ToneMap;
+use crate::{RenderSettings, SampleSequence};
+
+// Datos necesarios para reproducir un render: ajustes (con la semilla), cámara y hora del día.
+// Se guardan como un .json junto a la imagen (imagen.png -> imagen.json).
+#[derive(Debug, Clone)]
+pub struct RenderMetadata {
+    pub settings: RenderSettings,
+    pub eye: Vec3,
+    pub center: Vec3,
+    pub up: Vec3,
+    pub time_of_day: String,
+}
+
+impl RenderMetadata {
+    pub fn new(settings: &RenderSettings, camera: &Camera, time_of_day: &str) -> Self {
+        RenderMetadata {
+            settings: settings.clone(),
+            eye: camera.eye,
+            center: camera.center,
+            up: camera.up,
+            time_of_day: time_of_day.to_string(),
+        }
+    }
+
+    pub fn camera(&self) -> Camera {
+        Camera::from_state(&self.camera_state())
+    }
+
+    pub fn camera_state(&self) -> CameraState {
+        CameraState { eye: self.eye, center: self.center, up: self.up, fov: self.settings.fov }
+    }
+
+    pub fn to_json(&self) -> String {
+        let s = &self.settings;
+        let region = match s.region {
+            Some((x0, y0, x1, y1)) => format!("[{}, {}, {}, {}]", x0, y0, x1, y1),
+            None => "null".to_string(),
+        };
+        let fields = [
+            ("width", s.width.to_string()),
+            ("height", s.height.to_string()),
+            ("fov", json_f32(s.fov)),
+            ("region", region),
+            ("shutter", json_f32(s.shutter)),
+            ("motion_samples", s.motion_samples.to_string()),
+            ("max_sample_luminance", s.max_sample_luminance.map_or("null".to_string(), json_f32)),
+            ("near_clip", json_f32(s.near_clip)),
+            ("far_clip", json_f32(s.far_clip)),
+            ("preview", s.preview.to_string()),
+            ("tonemapped_emission_average", s.tonemapped_emission_average.to_string()),
+            ("pixel_aspect", json_f32(s.pixel_aspect)),
+            ("max_direct_lights", s.max_direct_lights.to_string()),
+            ("min_emission_luminance", json_f32(s.min_emission_luminance)),
+            ("aa_samples", s.aa_samples.to_string()),
+            ("filter", format!("\"{}\"", s.filter.name())),
+            ("normal_space", format!("\"{}\"", s.normal_space.name())),
+            ("min_light_intensity", json_f32(s.min_light_intensity)),
+            ("sample_sequence", format!("\"{}\"", s.sample_sequence.name())),
+            ("adaptive_threshold", s.adaptive_threshold.map_or("null".to_string(), json_f32)),
+            ("min_adaptive_samples", s.min_adaptive_samples.to_string()),
+            ("specular_aa", s.specular_aa.to_string()),
+            ("frame_index", s.frame_index.to_string()),
+            ("tone_map", format!("\"{}\"", s.tone_map.name())),
+            ("exposure", json_f32(s.exposure)),
+            ("seed", s.seed.to_string()),
+            ("time_budget_ms", s.time_budget.map_or("null".to_string(), |budget| budget.as_millis().to_string())),
+            ("camera_eye", json_vec3(&self.eye)),
+            ("camera_center", json_vec3(&self.center)),
+            ("camera_up", json_vec3(&self.up)),
+            ("time_of_day", format!("\"{}\"", self.time_of_day.replace('\\', "\\\\").replace('"', "\\\""))),
+        ];
+        let body: Vec<String> = fields
+            .iter()
+            .map(|(key, value)| format!("  \"{}\": {}", key, value))
+            .collect();
+        format!("{{\n{}\n}}\n", body.join(",\n"))
+    }
+
+    // Lee el formato que escribe to_json (objeto plano con números, arreglos, cadenas y null).
+    // Un valor mal formado es un error, no el valor por defecto: el render no sería el mismo
+    pub fn from_json(json: &str) -> Option<Self> {
+        let body = json.trim().strip_prefix('{')?.strip_suffix('}')?;
+        let mut settings = RenderSettings::default();
+        let mut metadata = RenderMetadata {
+            settings: RenderSettings::default(),
+            eye: Vec3::new(0.0, 0.0, 0.0),
+            center: Vec3::new(0.0, 0.0, 0.0),
+            up: Vec3::new(0.0, 1.0, 0.0),
+            time_of_day: String::new(),
+        };
+
+        for field in split_top_level(body) {
+            let (key, value) = field.split_once(':')?;
+            let key = key.trim().trim_matches('"');
+            let value = value.trim();
+            match key {
+                "width" => settings.width = value.parse().ok()?,
+                "height" => settings.height = value.parse().ok()?,
+                "fov" => settings.fov = parse_f32(value)?,
+                "region" => {
+                    settings.region = if value == "null" {
+                        None
+                    } else {
+                        match parse_array::<usize>(value)?.as_slice() {
+                            [x0, y0, x1, y1] => Some((*x0, *y0, *x1, *y1)),
+                            _ => return None,
+                        }
+                    }
+                }
+                "shutter" => settings.shutter = parse_f32(value)?,
+                "motion_samples" => settings.motion_samples = value.parse().ok()?,
+                "max_sample_luminance" => settings.max_sample_luminance = parse_optional(value)?,
+                "near_clip" => settings.near_clip = parse_f32(value)?,
+                "far_clip" => settings.far_clip = parse_optional(value)?.unwrap_or(f32::INFINITY),
+                "preview" => settings.preview = value.parse().ok()?,
+                "tonemapped_emission_average" => settings.tonemapped_emission_average = value.parse().ok()?,
+                "pixel_aspect" => settings.pixel_aspect = parse_f32(value)?,
+                "max_direct_lights" => settings.max_direct_lights = value.parse().ok()?,
+                "min_emission_luminance" => settings.min_emission_luminance = parse_f32(value)?,
+                "aa_samples" => settings.aa_samples = value.parse().ok()?,
+                "filter" => settings.filter = PixelFilter::from_name(value.trim_matches('"'))?,
+                "normal_space" => settings.normal_space = NormalSpace::from_name(value.trim_matches('"'))?,
+                "min_light_intensity" => settings.min_light_intensity = parse_f32(value)?,
+                "sample_sequence" => settings.sample_sequence = SampleSequence::from_name(value.trim_matches('"'))?,
+                "adaptive_threshold" => settings.adaptive_threshold = parse_optional(value)?,
+                "min_adaptive_samples" => settings.min_adaptive_samples = value.parse().ok()?,
+                "specular_aa" => settings.specular_aa = value.parse().ok()?,
+                "frame_index" => settings.frame_index = value.parse().ok()?,
+                "tone_map" => settings.tone_map = ToneMap::from_name(value.trim_matches('"'))?,
+                "exposure" => settings.exposure = parse_f32(value)?,
+                "seed" => settings.seed = value.parse().ok()?,
+                "time_budget_ms" => settings.time_budget = parse_optional(value)?.map(Duration::from_millis),
+                "camera_eye" => metadata.eye = parse_vec3(value)?,
+                "camera_center" => metadata.center = parse_vec3(value)?,
+                "camera_up" => metadata.up = parse_vec3(value)?,
+                "time_of_day" => {
+                    let text = value.strip_prefix('"')?.strip_suffix('"')?;
+                    metadata.time_of_day = text.replace("\\\"", "\"").replace("\\\\", "\\");
+                }
+                _ => {} // Campos desconocidos se ignoran
+            }
+        }
+
+        metadata.settings = settings;
+        Some(metadata)
+    }
+
+    // Escribe el .json al lado de la imagen ya guardada
+    pub fn write_sidecar(&self, image_path: &str) -> io::Result<()> {
+        fs::write(sidecar_path(image_path), self.to_json())
+    }
+
+    pub fn read_sidecar(image_path: &str) -> io::Result<Self> {
+        let json = fs::read_to_string(sidecar_path(image_path))?;
+        RenderMetadata::from_json(&json)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "Invalid render metadata"))
+    }
+}
+
+pub fn sidecar_path(image_path: &str) -> PathBuf {
+    Path::new(image_path).with_extension("json")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+    use crate::cube::Cube;
+    use crate::light::Light;
+    use crate::material::Material;
+    use crate::render_to_image;
+    use crate::scene::Scene;
+
+    fn custom_settings() -> RenderSettings {
+        RenderSettings {
+            width: 24,
+            height: 18,
+            region: Some((2, 3, 20, 15)),
+            max_sample_luminance: Some(4.0),
+            near_clip: 0.5,
+            aa_samples: 4,
+            filter: PixelFilter::Gaussian,
+            adaptive_threshold: Some(0.02),
+            tone_map: ToneMap::Aces,
+            exposure: 1.5,
+            time_budget: Some(Duration::from_millis(250)),
+            seed: 1234,
+            ..RenderSettings::default()
+        }
+    }
+
+    #[test]
+    fn settings_camera_and_seed_round_trip() {
+        let camera = Camera::new(Vec3::new(1.0, 2.0, 5.0), Vec3::new(0.0, 0.5, 0.0), Vec3::new(0.0, 1.0, 0.0));
+        let metadata = RenderMetadata::new(&custom_settings(), &camera, "dusk");
+        let read = RenderMetadata::from_json(&metadata.to_json()).expect("sidecar should parse");
+
+        assert_eq!(read.to_json(), metadata.to_json());
+        assert_eq!(read.settings.seed, 1234);
+        assert_eq!(read.settings.region, Some((2, 3, 20, 15)));
+        assert_eq!(read.settings.far_clip, f32::INFINITY);
+        assert_eq!(read.camera_state(), metadata.camera_state());
+        assert_eq!(read.time_of_day, "dusk");
+    }
+
+    #[test]
+    fn malformed_values_are_rejected_instead_of_defaulted() {
+        let camera = Camera::new(Vec3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+        let json = RenderMetadata::new(&custom_settings(), &camera, "noon").to_json();
+        for (good, bad) in [
+            ("\"max_sample_luminance\": 4", "\"max_sample_luminance\": bright"),
+            ("\"adaptive_threshold\": 0.02", "\"adaptive_threshold\": 0.0.2"),
+            ("\"time_budget_ms\": 250", "\"time_budget_ms\": -1"),
+            ("\"region\": [2, 3, 20, 15]", "\"region\": [2, 3]"),
+            ("\"region\": [2, 3, 20, 15]", "\"region\": [2, 3.5, 20, 15]"),
+            ("\"region\": [2, 3, 20, 15]", "\"region\": [2, -3, 20, 15]"),
+            ("\"fov\": ", "\"fov\": null, \"x\": "),
+            ("\"seed\": 1234", "\"seed\": 12.5"),
+        ] {
+            assert!(json.contains(good), "{}", good);
+            assert!(RenderMetadata::from_json(&json.replace(good, bad)).is_none(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn rerendering_from_the_sidecar_reproduces_the_image() {
+        let mut scene = Scene::new();
+        let material = Material::new(Color::new(200, 120, 60), 10.0, [0.9, 0.1, 0.0, 0.0], 1.0, None, 0.0);
+        scene.add_cube(Cube { min: Vec3::new(-1.0, -1.0, -1.0), max: Vec3::new(1.0, 1.0, 1.0), material, inverted: false }, &[]);
+        // Luz de área: la penumbra usa el generador aleatorio, igual que el anti-aliasing
+        let mut light = Light::new(Vec3::new(2.0, 4.0, 3.0), Color::new(255, 255, 255), 1.0);
+        light.radius = 1.0;
+        light.shadow_samples = 8;
+        scene.lights = vec![light];
+        let camera = Camera::new(Vec3::new(3.0, 2.5, 4.0), Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+        let settings = RenderSettings { region: None, adaptive_threshold: None, time_budget: None, ..custom_settings() };
+        let image = render_to_image(&scene, &camera, &settings);
+
+        let path = std::env::temp_dir().join(format!("raytracing_sidecar_{}.png", std::process::id()));
+        let path = path.to_str().unwrap();
+        RenderMetadata::new(&settings, &camera, "noon").write_sidecar(path).unwrap();
+        let metadata = RenderMetadata::read_sidecar(path).unwrap();
+        fs::remove_file(sidecar_path(path)).unwrap();
+
+        assert_eq!(render_to_image(&scene, &metadata.camera(), &metadata.settings), image);
+        // Otra semilla cambia el ruido: la igualdad no es casualidad
+        let reseeded = RenderSettings { seed: 99, ..metadata.settings.clone() };
+        assert_ne!(render_to_image(&scene, &metadata.camera(), &reseeded), image);
+    }
+}
@@ -0,0 +1,53 @@
+use nalgebra_glm::Vec3;
+use crate::color::Color;
+
+// Cielo analítico simple (inspirado en Preetham): azul en el cenit, más claro en el horizonte
+// y rojizo hacia el sol cuando este está bajo (amanecer/atardecer)
+pub fn analytic_sky_color(ray_direction: &Vec3, sun_direction: &Vec3) -> Color {
+    let dir = ray_direction.normalize();
+    let sun = sun_direction.normalize();
+
+    let elevation = dir.y.max(0.0);
+    let zenith = Vec3::new(0.25, 0.45, 0.85);
+    let horizon = Vec3::new(0.75, 0.85, 0.95);
+    let mut color = horizon.lerp(&zenith, elevation.sqrt());
+
+    // Cuanto más cerca del horizonte está el sol, más fuerte el enrojecimiento
+    let sunset = (1.0 - sun.y.abs() * 4.0).clamp(0.0, 1.0);
+    let toward_sun = dir.dot(&sun).max(0.0);
+    let near_horizon = (1.0 - elevation).powi(4);
+    let warm = Vec3::new(1.0, 0.45, 0.15);
+    color = color.lerp(&warm, (sunset * toward_sun * toward_sun * near_horizon).clamp(0.0, 1.0));
+
+    // Con el sol bajo el horizonte el cielo se oscurece
+    let daylight = (sun.y * 4.0 + 0.5).clamp(0.05, 1.0);
+    color *= daylight;
+
+    Color::from_rgb_f32(color.x, color.y, color.z)
+}
+
+// Radio angular (radianes) del disco solar dibujado en el cielo; mayor que el real (~0.0047)
+// para que se vea a la resolución de la ventana
+pub const SUN_ANGULAR_RADIUS: f32 = 0.03;
+pub const SUN_DISC_COLOR: Color = Color::new(255, 250, 230);
+
+// true si el rayo apunta dentro del disco del sol (y el sol está sobre el horizonte)
+pub fn hits_sun_disc(ray_direction: &Vec3, sun_direction: &Vec3) -> bool {
+    let sun = sun_direction.normalize();
+    sun.y > 0.0 && ray_direction.normalize().dot(&sun) >= SUN_ANGULAR_RADIUS.cos()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sky_toward_a_low_sun_is_warmer_than_the_zenith() {
+        let sun = Vec3::new(1.0, 0.05, 0.0);
+        let toward_sun = analytic_sky_color(&Vec3::new(1.0, 0.02, 0.0), &sun);
+        let zenith = analytic_sky_color(&Vec3::new(0.0, 1.0, 0.0), &sun);
+        let warmth = |c: &Color| c.r() as i32 - c.b() as i32;
+        assert!(warmth(&toward_sun) > 0, "{:?}", toward_sun.to_hex());
+        assert!(warmth(&toward_sun) > warmth(&zenith));
+    }
+}
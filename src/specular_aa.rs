@@ -0,0 +1,83 @@
+use nalgebra_glm::Vec3;
+
+use crate::ray_intersect::Intersect;
+use crate::scene::Scene;
+use crate::RenderSettings;
+
+// Largo del promedio de las normales dentro del píxel: 1 en una superficie plana y menor
+// mientras más cambia la normal (borde redondeado, forma curva vista de lejos)
+pub fn filtered_normal_length(normals: &[Vec3]) -> f32 {
+    if normals.is_empty() {
+        return 1.0;
+    }
+    let sum = normals.iter().fold(Vec3::new(0.0, 0.0, 0.0), |sum, normal| sum + normal.normalize());
+    (sum.magnitude() / normals.len() as f32).clamp(1e-4, 1.0)
+}
+
+// Toksvig: baja el exponente de Phong según la variación de la normal para que un brillo más
+// chico que el píxel se ensanche en vez de aparecer y desaparecer entre cuadros
+pub fn toksvig_exponent(exponent: f32, normal_length: f32) -> f32 {
+    let factor = normal_length / (normal_length + exponent * (1.0 - normal_length));
+    exponent * factor
+}
+
+// Lo mismo para una rugosidad (lóbulo anisotrópico): suma la varianza de la normal
+pub fn widened_roughness(roughness: f32, normal_length: f32) -> f32 {
+    let variance = (1.0 - normal_length) / normal_length;
+    (roughness * roughness + variance).sqrt()
+}
+
+// Ancho de un píxel a la distancia `distance` de la cámara
+pub fn pixel_footprint(distance: f32, settings: &RenderSettings) -> f32 {
+    distance * 2.0 * (settings.fov / 2.0).tan() / settings.height.max(1) as f32
+}
+
+// Estima la variación de la normal en el píxel lanzando cuatro rayos vecinos, desplazados medio
+// píxel en el plano tangente. Los que no tocan nada usan la normal del impacto central
+pub fn pixel_normal_length(ray_origin: &Vec3, intersect: &Intersect, scene: &Scene, settings: &RenderSettings, time: f32) -> f32 {
+    let distance = (intersect.point - ray_origin).magnitude();
+    let offset = pixel_footprint(distance, settings) * 0.5;
+    let (tangent, bitangent) = intersect.tangent_basis();
+
+    let mut normals = vec![intersect.normal];
+    for step in [tangent, -tangent, bitangent, -bitangent] {
+        let target = intersect.point + step * offset;
+        let neighbor = scene.intersect_at(ray_origin, &(target - ray_origin).normalize(), time);
+        normals.push(if neighbor.is_intersecting { neighbor.normal } else { intersect.normal });
+    }
+    filtered_normal_length(&normals)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn curved_region_gets_broader_highlight_than_flat_one() {
+        let flat = [Vec3::new(0.0, 1.0, 0.0); 5];
+        let curved = [
+            Vec3::new(0.0, 1.0, 0.0),
+            Vec3::new(0.5, 1.0, 0.0),
+            Vec3::new(-0.5, 1.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.5),
+            Vec3::new(0.0, 1.0, -0.5),
+        ];
+        let flat_length = filtered_normal_length(&flat);
+        let curved_length = filtered_normal_length(&curved);
+        assert!((flat_length - 1.0).abs() < 1e-6);
+        assert!(curved_length < 0.95);
+
+        // Exponente menor y rugosidad mayor = brillo más ancho
+        assert_eq!(toksvig_exponent(100.0, flat_length), 100.0);
+        assert!(toksvig_exponent(100.0, curved_length) < 20.0);
+        assert!(widened_roughness(0.1, curved_length) > widened_roughness(0.1, flat_length));
+    }
+
+    #[test]
+    fn footprint_follows_the_buffer_height() {
+        let full = RenderSettings { height: 600, ..RenderSettings::default() };
+        let quarter = RenderSettings { height: 150, ..RenderSettings::default() };
+        let ratio = pixel_footprint(10.0, &quarter) / pixel_footprint(10.0, &full);
+        assert!((ratio - 4.0).abs() < 1e-4);
+    }
+}
@@ -0,0 +1,88 @@
+use crate::camera::Camera;
+use crate::color::Color;
+use crate::framebuffer::Framebuffer;
+use crate::scene::Scene;
+use crate::{render, RenderSettings};
+
+// Cómo se combinan las vistas de ambos ojos en el framebuffer
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StereoMode {
+    SideBySide, // Ojo izquierdo en la mitad izquierda, derecho en la derecha (visores VR)
+    Anaglyph,   // Rojo del ojo izquierdo, verde y azul del derecho (lentes rojo/cian)
+}
+
+// Cámaras de cada ojo: la original desplazada media distancia interpupilar a cada lado
+pub fn eye_cameras(camera: &Camera, interpupillary_distance: f32) -> (Camera, Camera) {
+    let mut left = camera.clone();
+    left.pan(-interpupillary_distance / 2.0, 0.0);
+    let mut right = camera.clone();
+    right.pan(interpupillary_distance / 2.0, 0.0);
+    (left, right)
+}
+
+pub fn render_stereo(
+    framebuffer: &mut Framebuffer,
+    scene: &Scene,
+    camera: &Camera,
+    settings: &RenderSettings,
+    interpupillary_distance: f32,
+    mode: StereoMode,
+) {
+    let (left_camera, right_camera) = eye_cameras(camera, interpupillary_distance);
+
+    // En paralelo cada ojo ocupa la mitad del ancho
+    let eye_width = match mode {
+        StereoMode::SideBySide => framebuffer.width / 2,
+        StereoMode::Anaglyph => framebuffer.width,
+    };
+    let eye_settings = RenderSettings {
+        width: eye_width,
+        height: framebuffer.height,
+        region: None,
+        ..settings.clone()
+    };
+    let mut left = Framebuffer::new(eye_width, framebuffer.height);
+    let mut right = Framebuffer::new(eye_width, framebuffer.height);
+    render(&mut left, scene, &left_camera, &eye_settings);
+    render(&mut right, scene, &right_camera, &eye_settings);
+
+    for y in 0..framebuffer.height {
+        for x in 0..eye_width {
+            let left_pixel = left.get_pixel_color(x, y);
+            let right_pixel = right.get_pixel_color(x, y);
+            match mode {
+                StereoMode::SideBySide => {
+                    framebuffer.buffer[y * framebuffer.width + x] = left_pixel;
+                    framebuffer.buffer[y * framebuffer.width + eye_width + x] = right_pixel;
+                }
+                StereoMode::Anaglyph => {
+                    let left_color = Color::from_hex(left_pixel);
+                    let right_color = Color::from_hex(right_pixel);
+                    let combined = Color::new(left_color.r(), right_color.g(), right_color.b());
+                    framebuffer.buffer[y * framebuffer.width + x] = combined.to_hex();
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn eye_rays_are_offset_by_the_interpupillary_distance() {
+        use nalgebra_glm::Vec3;
+
+        let camera = Camera::new(Vec3::new(1.0, 2.0, 5.0), Vec3::new(1.0, 2.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+        let (left, right) = eye_cameras(&camera, 0.064);
+        let fov = std::f32::consts::PI / 3.0;
+        let (left_origin, left_dir) = left.get_ray(30.0, 12.0, 80.0, 60.0, fov);
+        let (right_origin, right_dir) = right.get_ray(30.0, 12.0, 80.0, 60.0, fov);
+
+        // Mismo rayo desplazado horizontalmente (a la derecha de la cámara, +X)
+        assert!((right_origin - left_origin - Vec3::new(0.064, 0.0, 0.0)).magnitude() < 1e-5);
+        assert!((right_dir - left_dir).magnitude() < 1e-5);
+        assert!(((left_origin + right_origin) / 2.0 - camera.eye).magnitude() < 1e-5);
+    }
+}
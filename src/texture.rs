@@ -1,63 +1,152 @@
 extern crate image;
 use crate::color::Color;
-use image::{DynamicImage, GenericImageView, ImageReader, Pixel};
+use image::{ColorType, DynamicImage, ImageReader, Rgb, RgbImage};
+use nalgebra_glm::Vec3;
 use std::fmt;
 
+// Espacio de color de los texels: las texturas de albedo suelen venir en sRGB,
+// mientras que los mapas de datos (normales, rugosidad) ya son lineales
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColorSpace {
+    Srgb,
+    Linear,
+}
+
+const MISSING_TEXTURE_SIZE: usize = 16;
+const MISSING_TILE_SIZE: usize = 4;
+
+// Errores al cargar una textura desde disco
+#[derive(Debug)]
+pub enum TextureError {
+    NotFound(String),             // Ruta del archivo que no se pudo abrir
+    DecodeFailed(String, String), // Ruta y motivo
+}
+
+impl fmt::Display for TextureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TextureError::NotFound(path) => write!(f, "Texture not found: {}", path),
+            TextureError::DecodeFailed(path, reason) => write!(f, "Failed to decode texture {}: {}", path, reason),
+        }
+    }
+}
+
+impl std::error::Error for TextureError {}
+
 #[derive(Clone)]
 pub struct Texture {
     pub id: String,
-    image: DynamicImage,
     pub width: usize,
     pub height: usize,
-    color_array: Vec<Color>,
+    radiance_array: Vec<Vec3>, // Texels en punto flotante (1.0 = blanco; los mapas HDR lo superan)
+    hdr: bool,
+    pub color_space: ColorSpace,
 }
 
 impl Texture {
+    // Los texels se usan tal como están guardados (mapas de datos: normales, rugosidad); las
+    // texturas de albedo se cargan con new_with_color_space(.., ColorSpace::Srgb).
+    // Si el archivo falta o no se puede decodificar se avisa y se usa el tablero magenta/negro de
+    // "textura faltante", para que la ruta rota se note en el render
     pub fn new(file_path: &str) -> Texture {
-        let img = ImageReader::open(file_path)
-            .expect("Failed to open file")
-            .decode()
-            .expect("Failed to decode image");
+        Texture::new_with_color_space(file_path, ColorSpace::Linear)
+    }
+
+    pub fn try_new(file_path: &str) -> Result<Texture, TextureError> {
+        Texture::try_new_with_color_space(file_path, ColorSpace::Linear)
+    }
 
-        let width = img.width() as usize;
-        let height = img.height() as usize;
+    // Con ColorSpace::Srgb los texels se convierten a lineal al cargar
+    pub fn new_with_color_space(file_path: &str, color_space: ColorSpace) -> Texture {
+        Texture::try_new_with_color_space(file_path, color_space).unwrap_or_else(|error| {
+            eprintln!("{}; using the missing-texture checkerboard", error);
+            Texture::missing(file_path, color_space)
+        })
+    }
+
+    pub fn try_new_with_color_space(file_path: &str, color_space: ColorSpace) -> Result<Texture, TextureError> {
+        let reader = ImageReader::open(file_path)
+            .map_err(|_| TextureError::NotFound(file_path.to_string()))?;
+        let img = reader
+            .decode()
+            .map_err(|error| TextureError::DecodeFailed(file_path.to_string(), error.to_string()))?;
 
         // Verificar que la imagen no tenga dimensiones nulas
-        assert!(
-            width > 0 && height > 0,
-            "Image is empty or not loaded correctly"
-        );
+        if img.width() == 0 || img.height() == 0 {
+            return Err(TextureError::DecodeFailed(file_path.to_string(), "image is empty".to_string()));
+        }
 
-        let mut texture = Texture {
+        Ok(Texture::from_image(file_path, img, color_space))
+    }
+
+    // Tablero magenta / negro de MISSING_TEXTURE_SIZE px con casillas de MISSING_TILE_SIZE px
+    pub fn missing(file_path: &str, color_space: ColorSpace) -> Texture {
+        let size = MISSING_TEXTURE_SIZE as u32;
+        let image = RgbImage::from_fn(size, size, |x, y| {
+            let tile = (x as usize / MISSING_TILE_SIZE + y as usize / MISSING_TILE_SIZE) % 2;
+            if tile == 0 { Rgb([255, 0, 255]) } else { Rgb([0, 0, 0]) }
+        });
+        Texture::from_image(file_path, image.into(), color_space)
+    }
+
+    fn from_image(file_path: &str, img: DynamicImage, color_space: ColorSpace) -> Texture {
+        // Imágenes de 8 bits quedan en 0..1; las de punto flotante (.hdr, .exr) conservan
+        // los valores mayores a 1.0
+        let hdr = matches!(img.color(), ColorType::Rgb32F | ColorType::Rgba32F);
+        let radiance_array = img
+            .to_rgb32f()
+            .pixels()
+            .map(|pixel| {
+                let texel = Vec3::new(pixel[0], pixel[1], pixel[2]);
+                match color_space {
+                    ColorSpace::Srgb if !hdr => texel.map(srgb_channel_to_linear),
+                    _ => texel,
+                }
+            })
+            .collect();
+
+        Texture {
             id: file_path.to_string(),
-            image: img,
-            width,
-            height,
-            color_array: vec![Color::black(); width * height],
-        };
-        texture.load_color_array();
-        texture
-    }
-
-    fn load_color_array(&mut self) {
-        for x in 0..self.width {
-            for y in 0..self.height {
-                let pixel = self.image.get_pixel(x as u32, y as u32).to_rgb();
-                let color =
-                    ((pixel[0] as u32) << 16) | ((pixel[1] as u32) << 8) | (pixel[2] as u32);
-                self.color_array[y * self.width + x] = Color::from_hex(color);
-            }
+            width: img.width() as usize,
+            height: img.height() as usize,
+            radiance_array,
+            hdr,
+            color_space,
         }
     }
 
+    // Carga un mapa de entorno HDR (.hdr Radiance RGBE) conservando valores mayores a 1.0
+    pub fn new_hdr(file_path: &str) -> Texture {
+        Texture::new(file_path)
+    }
+
+    pub fn try_new_hdr(file_path: &str) -> Result<Texture, TextureError> {
+        Texture::try_new(file_path)
+    }
+
+    pub fn is_hdr(&self) -> bool {
+        self.hdr
+    }
+
+    // Texel recortado a 8 bits (para mostrarlo); fuera de la imagen, magenta
     pub fn get_color(&self, x: usize, y: usize) -> Color {
+        Color::from_radiance(&self.get_radiance(x, y))
+    }
+
+    // Radiancia del texel (x, y), sin recortar; fuera de la imagen, magenta
+    pub fn get_radiance(&self, x: usize, y: usize) -> Vec3 {
         if x < self.width && y < self.height {
-            self.color_array[y * self.width + x]
+            self.radiance_array[y * self.width + x]
         } else {
-            Color::from_hex(0xFF00FF) 
+            Vec3::new(1.0, 0.0, 1.0)
         }
     }
 
+    pub fn average_color(&self) -> Color {
+        let sum = self.radiance_array.iter().fold(Vec3::new(0.0, 0.0, 0.0), |sum, texel| sum + texel);
+        Color::from_radiance(&(sum / self.radiance_array.len().max(1) as f32))
+    }
+
     pub fn get_color_at_uv(&self, u: f32, v: f32) -> Color {
         let u = u.clamp(0.0, 1.0);
         let v = v.clamp(0.0, 1.0);
@@ -66,6 +155,70 @@ impl Texture {
 
         self.get_color(x, y)
     }
+
+    // Color en 0..1 interpolado bilinealmente entre los 4 texels vecinos (con borde recortado)
+    pub fn get_bilinear_at_uv(&self, u: f32, v: f32) -> Vec3 {
+        let x = u.clamp(0.0, 1.0) * (self.width as f32 - 1.0);
+        let y = (1.0 - v.clamp(0.0, 1.0)) * (self.height as f32 - 1.0);
+        let (x0, y0) = (x.floor() as usize, y.floor() as usize);
+        let (x1, y1) = ((x0 + 1).min(self.width - 1), (y0 + 1).min(self.height - 1));
+        let (tx, ty) = (x - x0 as f32, y - y0 as f32);
+
+        let texel = |x: usize, y: usize| self.get_radiance(x, y);
+        let top = texel(x0, y0).lerp(&texel(x1, y0), tx);
+        let bottom = texel(x0, y1).lerp(&texel(x1, y1), tx);
+        top.lerp(&bottom, ty)
+    }
+
+    // Radiancia interpolada bilinealmente para mapas de entorno: en horizontal la textura da la vuelta
+    // (la columna 0 es vecina de la última), así no aparece una costura donde u pasa de 1 a 0
+    pub fn get_radiance_bilinear_wrapped(&self, u: f32, v: f32) -> Vec3 {
+        // Centros de texel en (i + 0.5) / ancho
+        let x = u.rem_euclid(1.0) * self.width as f32 - 0.5;
+        let y = ((1.0 - v.clamp(0.0, 1.0)) * self.height as f32 - 0.5).clamp(0.0, self.height as f32 - 1.0);
+        let x_floor = x.floor();
+        let (tx, ty) = (x - x_floor, y - y.floor());
+        let x0 = (x_floor as i64).rem_euclid(self.width as i64) as usize;
+        let x1 = (x0 + 1) % self.width;
+        let y0 = y.floor() as usize;
+        let y1 = (y0 + 1).min(self.height - 1);
+
+        let texel = |x: usize, y: usize| self.get_radiance(x, y);
+        let top = texel(x0, y0).lerp(&texel(x1, y0), tx);
+        let bottom = texel(x0, y1).lerp(&texel(x1, y1), tx);
+        top.lerp(&bottom, ty)
+    }
+
+    // Normal en espacio tangente de un mapa de normales (RGB 0..1 -> XYZ -1..1). Se interpola
+    // bilinealmente y se renormaliza para que el relieve se vea suave y no facetado
+    pub fn sample_normal(&self, u: f32, v: f32) -> Vec3 {
+        let encoded = self.get_bilinear_at_uv(u, v);
+        let normal = encoded * 2.0 - Vec3::new(1.0, 1.0, 1.0);
+        if normal.magnitude() > 1e-6 {
+            normal.normalize()
+        } else {
+            Vec3::new(0.0, 0.0, 1.0)
+        }
+    }
+
+    // Radiancia en el punto UV (texel más cercano); las texturas de 8 bits quedan en 0..1
+    pub fn get_radiance_at_uv(&self, u: f32, v: f32) -> Vec3 {
+        let u = u.clamp(0.0, 1.0);
+        let v = v.clamp(0.0, 1.0);
+        let x = (u * (self.width as f32 - 1.0)) as usize;
+        let y = ((1.0 - v) * (self.height as f32 - 1.0)) as usize;
+
+        self.get_radiance(x, y)
+    }
+}
+
+// Curva sRGB inversa; el resultado queda en f32 para no aplastar los tonos oscuros a 0
+fn srgb_channel_to_linear(c: f32) -> f32 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
 }
 
 impl fmt::Debug for Texture {
@@ -73,6 +226,115 @@ impl fmt::Debug for Texture {
         f.debug_struct("Texture")
             .field("width", &self.width)
             .field("height", &self.height)
+            .field("hdr", &self.is_hdr())
+            .field("color_space", &self.color_space)
             .finish()
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, Rgb32FImage, RgbImage};
+
+    #[test]
+    fn missing_file_is_an_error_from_try_new() {
+        let path = std::env::temp_dir().join(format!("raytracing_missing_{}.png", std::process::id()));
+        let path = path.to_str().unwrap();
+        assert!(matches!(Texture::try_new(path), Err(TextureError::NotFound(p)) if p == path));
+    }
+
+    #[test]
+    fn normal_between_two_texels_is_interpolated_and_normalized() {
+        // Texel izquierdo apunta a +X y el derecho a +Z
+        let image = RgbImage::from_fn(2, 1, |x, _| if x == 0 { Rgb([255, 128, 128]) } else { Rgb([128, 128, 255]) });
+        let normal_map = Texture::from_image("normals", image.into(), ColorSpace::Linear);
+
+        let left = normal_map.sample_normal(0.0, 0.5);
+        assert!((left - Vec3::new(1.0, 0.0, 0.0)).magnitude() < 0.01);
+        let middle = normal_map.sample_normal(0.5, 0.5);
+        assert!((middle.magnitude() - 1.0).abs() < 1e-5);
+        let diagonal = Vec3::new(1.0, 0.0, 1.0).normalize();
+        assert!((middle - diagonal).magnitude() < 0.01, "{:?}", middle);
+        // A un cuarto del camino todavía domina +X
+        let quarter = normal_map.sample_normal(0.25, 0.5);
+        assert!(quarter.x > quarter.z && quarter.z > 0.1);
+    }
+
+    #[test]
+    fn wrapped_sampling_is_continuous_across_the_seam() {
+        // Primera columna roja y última azul: a ambos lados de u = 0 / 1 se mezclan las dos
+        let image = RgbImage::from_fn(8, 2, |x, _| match x {
+            0 => Rgb([255, 0, 0]),
+            7 => Rgb([0, 0, 255]),
+            _ => Rgb([0, 255, 0]),
+        });
+        let skybox = Texture::from_image("sky", image.into(), ColorSpace::Linear);
+
+        let before = skybox.get_radiance_bilinear_wrapped(0.9999, 0.5);
+        let after = skybox.get_radiance_bilinear_wrapped(0.0001, 0.5);
+        assert!((before - after).magnitude() < 0.01, "{:?} vs {:?}", before, after);
+        assert!((before - Vec3::new(0.5, 0.0, 0.5)).magnitude() < 0.01);
+        // El salto entre texels vecinos en el interior es mucho mayor que el de la costura
+        let step = skybox.get_radiance_bilinear_wrapped(1.0 / 16.0, 0.5) - skybox.get_radiance_bilinear_wrapped(3.0 / 16.0, 0.5);
+        assert!(step.magnitude() > 1.0);
+    }
+
+    #[test]
+    fn missing_texture_alternates_magenta_and_black_tiles() {
+        let texture = Texture::missing("missing.png", ColorSpace::Srgb);
+        let magenta = 0xFF00FF;
+        let black = 0x000000;
+        let last = MISSING_TILE_SIZE - 1;
+
+        assert_eq!(texture.get_color(0, 0).to_hex(), magenta);
+        assert_eq!(texture.get_color(last, last).to_hex(), magenta);
+        assert_eq!(texture.get_color(last + 1, 0).to_hex(), black);
+        assert_eq!(texture.get_color(0, last + 1).to_hex(), black);
+        assert_eq!(texture.get_color(last + 1, last + 1).to_hex(), magenta);
+        assert_eq!(texture.id, "missing.png");
+    }
+
+    fn texture_from_pixel(pixel: [u8; 3], color_space: ColorSpace) -> Texture {
+        let image = RgbImage::from_pixel(1, 1, Rgb(pixel));
+        Texture::from_image("test", image.into(), color_space)
+    }
+
+    #[test]
+    fn srgb_texels_are_darkened_and_linear_texels_are_not() {
+        let srgb = texture_from_pixel([128, 128, 128], ColorSpace::Srgb).get_radiance(0, 0);
+        let linear = texture_from_pixel([128, 128, 128], ColorSpace::Linear).get_radiance(0, 0);
+
+        assert!((linear.x - 128.0 / 255.0).abs() < 1e-6);
+        // sRGB 128 ~ 21.6 % lineal
+        assert!((srgb.x - 0.2159).abs() < 1e-3);
+        // Los extremos no cambian
+        assert_eq!(texture_from_pixel([255, 0, 255], ColorSpace::Srgb).get_radiance(0, 0), Vec3::new(1.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn dark_srgb_texels_stay_above_zero() {
+        // En 8 bits sRGB 5 se redondearía a 0; en f32 conserva su valor (~0.0015)
+        let dark = texture_from_pixel([5, 5, 5], ColorSpace::Srgb).get_radiance(0, 0);
+        assert!(dark.x > 0.001 && dark.x < 0.002);
+    }
+
+    #[test]
+    fn hdr_file_keeps_texels_above_one() {
+        let path = std::env::temp_dir().join(format!("raytracing_tiny_{}.hdr", std::process::id()));
+        let image = Rgb32FImage::from_fn(2, 1, |x, _| if x == 0 { Rgb([4.0, 2.5, 1.5]) } else { Rgb([0.25, 0.5, 0.75]) });
+        image.save(&path).unwrap();
+
+        let texture = Texture::try_new_hdr(path.to_str().unwrap()).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        assert!(texture.is_hdr());
+        // RGBE guarda 8 bits de mantisa por canal: se compara con una tolerancia relativa
+        let bright = texture.get_radiance(0, 0);
+        assert!((bright.x - 4.0).abs() < 0.05 && (bright.y - 2.5).abs() < 0.05 && bright.z > 1.0);
+        let dim = texture.get_radiance(1, 0);
+        assert!((dim - Vec3::new(0.25, 0.5, 0.75)).magnitude() < 0.01);
+        // Recortado a 8 bits solo para mostrarlo
+        assert_eq!(texture.get_color(0, 0).r(), 255);
+    }
+}
@@ -0,0 +1,81 @@
+use nalgebra_glm::Vec3;
+
+// Curvas para llevar radiancia HDR (sin límite) al rango 0..1 de la pantalla
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ToneMap {
+    Clamp,    // Recorta en 1.0 (sin compresión)
+    Reinhard, // x / (1 + x): suave, pero desatura los brillos
+    Aces,     // Ajuste de Narkowicz de la curva fílmica ACES: más contraste y caída natural
+}
+
+impl ToneMap {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ToneMap::Clamp => "clamp",
+            ToneMap::Reinhard => "reinhard",
+            ToneMap::Aces => "aces",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<ToneMap> {
+        match name {
+            "clamp" => Some(ToneMap::Clamp),
+            "reinhard" => Some(ToneMap::Reinhard),
+            "aces" => Some(ToneMap::Aces),
+            _ => None,
+        }
+    }
+
+    // Siguiente curva (tecla O): clamp -> Reinhard -> ACES -> clamp
+    pub fn next(&self) -> ToneMap {
+        match self {
+            ToneMap::Clamp => ToneMap::Reinhard,
+            ToneMap::Reinhard => ToneMap::Aces,
+            ToneMap::Aces => ToneMap::Clamp,
+        }
+    }
+
+    pub fn apply(&self, radiance: &Vec3) -> Vec3 {
+        let curve = |x: f32| {
+            let x = x.max(0.0);
+            match self {
+                ToneMap::Clamp => x.min(1.0),
+                ToneMap::Reinhard => x / (1.0 + x),
+                ToneMap::Aces => {
+                    let (a, b, c, d, e) = (2.51, 0.03, 2.43, 0.59, 0.14);
+                    ((x * (a * x + b)) / (x * (c * x + d) + e)).clamp(0.0, 1.0)
+                }
+            }
+        };
+        Vec3::new(curve(radiance.x), curve(radiance.y), curve(radiance.z))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn curve(tone_map: ToneMap, x: f32) -> f32 {
+        tone_map.apply(&Vec3::new(x, x, x)).x
+    }
+
+    #[test]
+    fn aces_and_reinhard_match_reference_points() {
+        // (entrada, ACES de Narkowicz, Reinhard x / (1 + x))
+        let references = [(0.0, 0.0, 0.0), (0.18, 0.26690, 0.15254), (1.0, 0.80380, 0.5), (10.0, 1.0, 0.90909)];
+        for (x, aces, reinhard) in references {
+            assert!((curve(ToneMap::Aces, x) - aces).abs() < 1e-4, "ACES({})", x);
+            assert!((curve(ToneMap::Reinhard, x) - reinhard).abs() < 1e-4, "Reinhard({})", x);
+        }
+        // ACES tiene más contraste en los medios tonos y llega antes al blanco
+        assert!(curve(ToneMap::Aces, 0.5) > curve(ToneMap::Reinhard, 0.5));
+        assert_eq!(curve(ToneMap::Clamp, 3.5), 1.0);
+    }
+
+    #[test]
+    fn names_round_trip() {
+        for tone_map in [ToneMap::Clamp, ToneMap::Reinhard, ToneMap::Aces] {
+            assert_eq!(ToneMap::from_name(tone_map.name()), Some(tone_map));
+        }
+    }
+}
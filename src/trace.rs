@@ -0,0 +1,149 @@
+use nalgebra_glm::Vec3;
+use std::fmt;
+
+use crate::color::Color;
+
+// Registro de cómo se sombreó un píxel, para depurar normales, UV y sombras
+
+// Aporte de una luz en el punto sombreado
+#[derive(Debug, Clone)]
+pub struct LightTrace {
+    pub light_index: usize,
+    pub distance: f32,
+    pub shadow: f32,          // 0 = iluminado, 1 = en sombra completa
+    pub diffuse_factor: f32,
+    pub specular_factor: f32,
+}
+
+// Datos del sombreado de un impacto
+#[derive(Debug, Clone)]
+pub struct ShadeTrace {
+    pub ambient: Color,
+    pub lights: Vec<LightTrace>,
+    pub emission: Color,
+}
+
+impl ShadeTrace {
+    pub fn new() -> Self {
+        ShadeTrace {
+            ambient: Color::black(),
+            lights: Vec::new(),
+            emission: Color::black(),
+        }
+    }
+}
+
+impl Default for ShadeTrace {
+    fn default() -> Self {
+        ShadeTrace::new()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct HitTrace {
+    pub distance: f32,
+    pub point: Vec3,
+    pub normal: Vec3,
+    pub u: f32,
+    pub v: f32,
+    pub material: String, // Resumen del material (albedo, brillo, textura)
+    pub shading: ShadeTrace,
+}
+
+// Por qué se lanzó un rayo
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BounceKind {
+    Camera,
+    Refraction,
+    Reflection,   // Reflexión total interna dentro de un material refractivo
+    Transparency, // Capa siguiente detrás de una superficie transparente sin refracción
+}
+
+#[derive(Debug, Clone)]
+pub struct BounceTrace {
+    pub depth: u32,
+    pub kind: BounceKind,
+    pub ray_origin: Vec3,
+    pub ray_direction: Vec3,
+    pub hit: Option<HitTrace>, // None: el rayo salió al cielo
+}
+
+#[derive(Debug, Clone)]
+pub struct PixelTrace {
+    pub x: usize,
+    pub y: usize,
+    pub bounces: Vec<BounceTrace>, // El primero es el rayo de cámara
+    pub color: Color,
+}
+
+impl PixelTrace {
+    pub fn primary_hit(&self) -> Option<&HitTrace> {
+        self.bounces.first().and_then(|bounce| bounce.hit.as_ref())
+    }
+}
+
+// Píxel del framebuffer bajo el cursor: la ventana puede estar escalada respecto al framebuffer
+pub fn window_to_framebuffer(
+    mouse: (f32, f32),
+    window_size: (usize, usize),
+    framebuffer_size: (usize, usize),
+) -> Option<(usize, usize)> {
+    if window_size.0 == 0 || window_size.1 == 0 || mouse.0 < 0.0 || mouse.1 < 0.0 {
+        return None;
+    }
+    let x = (mouse.0 * framebuffer_size.0 as f32 / window_size.0 as f32) as usize;
+    let y = (mouse.1 * framebuffer_size.1 as f32 / window_size.1 as f32) as usize;
+    (x < framebuffer_size.0 && y < framebuffer_size.1).then_some((x, y))
+}
+
+impl fmt::Display for PixelTrace {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Pixel ({}, {})", self.x, self.y)?;
+        for bounce in &self.bounces {
+            writeln!(
+                f,
+                "{:indent$}{:?} ray (depth {}): origin ({:.3}, {:.3}, {:.3}) dir ({:.3}, {:.3}, {:.3})",
+                "", bounce.kind, bounce.depth,
+                bounce.ray_origin.x, bounce.ray_origin.y, bounce.ray_origin.z,
+                bounce.ray_direction.x, bounce.ray_direction.y, bounce.ray_direction.z,
+                indent = 2 + 2 * bounce.depth as usize
+            )?;
+            let indent = 4 + 2 * bounce.depth as usize;
+            match &bounce.hit {
+                None => writeln!(f, "{:indent$}miss (background)", "")?,
+                Some(hit) => {
+                    writeln!(
+                        f,
+                        "{:indent$}hit: distance {:.3} point ({:.3}, {:.3}, {:.3}) normal ({:.3}, {:.3}, {:.3}) uv ({:.3}, {:.3})",
+                        "", hit.distance, hit.point.x, hit.point.y, hit.point.z,
+                        hit.normal.x, hit.normal.y, hit.normal.z, hit.u, hit.v
+                    )?;
+                    writeln!(f, "{:indent$}material: {}", "", hit.material)?;
+                    writeln!(f, "{:indent$}ambient: {}", "", hit.shading.ambient)?;
+                    for light in &hit.shading.lights {
+                        writeln!(
+                            f,
+                            "{:indent$}light {}: distance {:.3} shadow {:.3} diffuse {:.3} specular {:.3}",
+                            "", light.light_index, light.distance, light.shadow, light.diffuse_factor, light.specular_factor
+                        )?;
+                    }
+                    writeln!(f, "{:indent$}emission: {}", "", hit.shading.emission)?;
+                }
+            }
+        }
+        write!(f, "  color: {}", self.color)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mouse_position_is_scaled_to_the_framebuffer() {
+        assert_eq!(window_to_framebuffer((400.0, 300.0), (800, 600), (400, 300)), Some((200, 150)));
+        assert_eq!(window_to_framebuffer((799.0, 599.0), (800, 600), (1600, 1200)), Some((1598, 1198)));
+        assert_eq!(window_to_framebuffer((810.0, 10.0), (800, 600), (800, 600)), None);
+        assert_eq!(window_to_framebuffer((-1.0, 10.0), (800, 600), (800, 600)), None);
+    }
+}